@@ -1,41 +1,121 @@
 use clap::Parser;
-use commands::{auth, handle_all_command, handle_command, CommandType};
+use commands::{
+    auth, config, doctor, handle_all_command, handle_check_command, handle_command,
+    handle_main_command, open, run, status, CheckOutcome, CommandType, OutputMode,
+};
 use eyre::Result;
 use ghtool::{
-    cli::{self, Commands},
+    cli::{self, Commands, ConfigCommands, LintGroupBy},
     commands, setup, term,
 };
 use setup::setup;
 use term::exit_with_error;
 
-async fn run() -> Result<()> {
+/// Run the command, returning the code to exit with
+async fn run() -> Result<i32> {
     let cli = setup()?;
 
     match &cli.command {
-        Some(Commands::Test { files }) => handle_command(CommandType::Test, &cli, *files).await,
-        Some(Commands::Lint { files }) => handle_command(CommandType::Lint, &cli, *files).await,
-        Some(Commands::Build { files }) => handle_command(CommandType::Build, &cli, *files).await,
-        Some(Commands::All {}) => handle_all_command(&cli).await,
+        Some(Commands::Test {
+            files,
+            counts,
+            group_tests_by_suite,
+            name,
+        }) => {
+            let output_mode = if *group_tests_by_suite {
+                OutputMode::GroupBySuite
+            } else if *counts {
+                OutputMode::FileCounts
+            } else {
+                OutputMode::from_files_flag(*files)
+            };
+            handle_command(CommandType::Test, &cli, output_mode, name.as_ref())
+                .await
+                .map(CheckOutcome::exit_code)
+        }
+        Some(Commands::Lint {
+            files,
+            errors_only,
+            group_by,
+        }) => {
+            let output_mode = match group_by {
+                Some(LintGroupBy::Rule) => OutputMode::GroupByRule,
+                None if *errors_only => OutputMode::ErrorFiles,
+                None => OutputMode::from_files_flag(*files),
+            };
+            handle_command(CommandType::Lint, &cli, output_mode, None)
+                .await
+                .map(CheckOutcome::exit_code)
+        }
+        Some(Commands::Build { files, terse, .. }) => {
+            let output_mode = if *terse {
+                OutputMode::TerseErrors
+            } else {
+                OutputMode::from_files_flag(*files)
+            };
+            handle_command(CommandType::Build, &cli, output_mode, None)
+                .await
+                .map(CheckOutcome::exit_code)
+        }
+        Some(Commands::All {}) => handle_all_command(&cli).await.map(CheckOutcome::exit_code),
+        Some(Commands::Main { files }) => {
+            handle_main_command(&cli, OutputMode::from_files_flag(*files))
+                .await
+                .map(CheckOutcome::exit_code)
+        }
+        Some(Commands::Check { watch_until_green }) => {
+            handle_check_command(&cli, *watch_until_green)
+                .await
+                .map(CheckOutcome::exit_code)
+        }
+        Some(Commands::Status { branches }) => status::status(&cli, branches)
+            .await
+            .map(CheckOutcome::exit_code),
+        Some(Commands::Open { job }) => {
+            open::open(&cli, job.as_deref()).await?;
+            Ok(0)
+        }
+        Some(Commands::Run { check, exec }) => {
+            run::run(&cli, check, *exec)?;
+            Ok(0)
+        }
         Some(Commands::Login { stdin }) => {
             auth::login(*stdin).await?;
-            Ok(())
+            Ok(0)
         }
         Some(Commands::Logout {}) => {
-            auth::logout()?;
-            Ok(())
+            auth::logout(&cli)?;
+            Ok(0)
+        }
+        Some(Commands::Doctor {}) => {
+            doctor::doctor(&cli).await?;
+            Ok(0)
+        }
+        Some(Commands::Tools {}) => {
+            config::print_tools()?;
+            Ok(0)
+        }
+        Some(Commands::Config { command }) => {
+            match command {
+                ConfigCommands::Schema {} => config::print_config_schema()?,
+                ConfigCommands::TestPattern { check } => config::test_pattern(&cli, check)?,
+            }
+            Ok(0)
         }
         None => {
             // Show help if no command is given. arg_required_else_help clap thing is supposed to
             // do this but that doesn't work if some arguments, but no command, are given
             cli::Cli::parse_from(["--help"]);
-            Ok(())
+            Ok(0)
         }
     }
 }
 
 #[tokio::main]
 async fn main() {
-    if let Err(e) = run().await {
-        let _ = exit_with_error::<eyre::Error>(e);
+    match run().await {
+        Ok(0) => {}
+        Ok(code) => std::process::exit(code),
+        Err(e) => exit_with_error(e),
     }
 }
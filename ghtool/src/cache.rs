@@ -1,4 +1,4 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use eyre::Result;
 use futures::Future;
@@ -58,6 +58,39 @@ where
     Ok(value)
 }
 
+/// Get a value that was put no longer than max_age ago
+pub fn get_fresh<K, V>(key: K, max_age: Duration) -> Result<Option<V>>
+where
+    K: AsRef<[u8]> + std::fmt::Debug,
+    V: DeserializeOwned,
+{
+    let db = open_db()?;
+    let Some(bytes) = db.get(&key)? else {
+        return Ok(None);
+    };
+    let value: CacheValue<V> = serde_json::from_slice(&bytes)?;
+    // A timestamp in the future, e.g. after the clock was changed, counts as stale
+    let age = SystemTime::now().duration_since(value.timestamp);
+    if age.is_ok_and(|age| age <= max_age) {
+        debug!(?key, "found fresh cached key");
+        Ok(Some(value.value))
+    } else {
+        debug!(?key, "cached key is stale");
+        Ok(None)
+    }
+}
+
+pub fn remove<K>(key: K) -> Result<()>
+where
+    K: AsRef<[u8]> + std::fmt::Debug,
+{
+    let db = open_db()?;
+    db.remove(&key)?;
+    debug!(?key, "cache key removed");
+    db.flush()?;
+    Ok(())
+}
+
 pub async fn memoize<F, Fut, K, V>(key: K, f: F) -> Result<V>
 where
     F: FnOnce() -> Fut,
@@ -1,4 +1,7 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use regex::Regex;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -9,15 +12,304 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
 
-    /// Print verbose output
+    /// Print verbose output; repeat (-vv) for debug output
     #[arg(global = true)]
-    #[clap(long, short)]
-    pub verbose: bool,
+    #[clap(long, short, action = clap::ArgAction::Count)]
+    pub verbose: u8,
 
     /// Target branch; defaults to current branch
     #[arg(global = true)]
     #[clap(long, short)]
     pub branch: Option<String>,
+
+    /// Target repository as owner/name; defaults to the repository's origin remote
+    #[arg(global = true)]
+    #[clap(long)]
+    pub repo: Option<String>,
+
+    /// Hostname of the repository given with --repo
+    #[arg(global = true)]
+    #[clap(long, default_value = "github.com")]
+    pub host: String,
+
+    /// Fail unless the pull request's head commit starts with this sha
+    #[arg(global = true)]
+    #[clap(long, value_name = "SHA")]
+    pub expect_sha: Option<String>,
+
+    /// Use the token of the gh CLI from its hosts.yml, instead of GH_TOKEN or the token stored
+    /// with ght login
+    #[arg(global = true)]
+    #[clap(long)]
+    pub token_from_gh: bool,
+
+    /// Authenticate as an installation of this GitHub App, e.g. for bots, instead of a user.
+    /// Requires --app-installation-id and --app-private-key. Read from GHTOOL_APP_ID if not
+    /// given.
+    #[arg(global = true)]
+    #[clap(long, value_name = "ID", conflicts_with = "token_from_gh")]
+    pub app_id: Option<String>,
+
+    /// Id of the GitHub App's installation to authenticate as. Read from
+    /// GHTOOL_APP_INSTALLATION_ID if not given.
+    #[arg(global = true)]
+    #[clap(long, value_name = "ID")]
+    pub app_installation_id: Option<u64>,
+
+    /// Path of the GitHub App's private key in PEM format. The key itself is read from
+    /// GHTOOL_APP_PRIVATE_KEY if not given.
+    #[arg(global = true)]
+    #[clap(long, value_name = "PATH")]
+    pub app_private_key: Option<PathBuf>,
+
+    /// Read check runs of the latest commit of the repository's default branch instead of a pull
+    /// request's; they are not waited for
+    #[arg(global = true)]
+    #[clap(long, conflicts_with_all = ["expect_sha", "last", "show_checks"])]
+    pub default_branch: bool,
+
+    /// Read the jobs of this workflow run, e.g. from the url of a run on a branch without a pull
+    /// request, instead of a pull request's check runs; they are not waited for
+    #[arg(global = true)]
+    #[clap(
+        long,
+        value_name = "RUN_ID",
+        conflicts_with_all = ["default_branch", "expect_sha", "last", "show_checks"]
+    )]
+    pub run: Option<u64>,
+
+    /// Fail instead of reading checks when the branch's pull request is a draft, e.g. in CI
+    #[arg(global = true)]
+    #[clap(long, conflicts_with = "default_branch")]
+    pub skip_drafts: bool,
+
+    /// Read the most recent completed run of each job instead of waiting for runs in progress,
+    /// looking back at the pull request's last 10 commits
+    #[arg(global = true)]
+    #[clap(long, conflicts_with = "expect_sha")]
+    pub last: bool,
+
+    /// Only report errors in files matching the glob; can be given multiple times
+    #[arg(global = true)]
+    #[clap(long, value_name = "GLOB")]
+    pub file_filter: Vec<String>,
+
+    /// Print check run sections in reverse order
+    #[arg(global = true)]
+    #[clap(long)]
+    pub reverse: bool,
+
+    /// Print every check run of the pull request and whether it matches a job pattern
+    #[arg(global = true)]
+    #[clap(long)]
+    pub show_checks: bool,
+
+    /// Read config from this file instead of .ghtool.toml in the repository root
+    #[arg(global = true)]
+    #[clap(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Only consider check runs of the GitHub app with this slug; "*" for any app. Overrides the
+    /// app in config, which defaults to github-actions.
+    #[arg(global = true)]
+    #[clap(long, value_name = "SLUG")]
+    pub app: Option<String>,
+
+    /// Also report checks that concluded as neutral. Checks requiring action, e.g. approval, are
+    /// always reported.
+    #[arg(global = true)]
+    #[clap(long)]
+    pub include_neutral: bool,
+
+    /// Report and exit with an error only for failed or timed out checks required to merge the
+    /// pull request, e.g. in a merge queue, ignoring optional ones
+    #[arg(global = true)]
+    #[clap(long, conflicts_with_all = ["default_branch", "run"])]
+    pub only_required_failing: bool,
+
+    /// Hide errors that are also on the latest commit of the pull request's base branch, reading
+    /// the logs of its failed jobs too. The base branch's errors are cached by commit.
+    #[arg(global = true)]
+    #[clap(long, conflicts_with_all = ["default_branch", "run"])]
+    pub vs_base: bool,
+
+    /// Don't print links to failed checks whose logs had no errors that could be parsed
+    #[arg(global = true)]
+    #[clap(long)]
+    pub no_url_fallback: bool,
+
+    /// Proxy to use for requests to GitHub; overrides HTTPS_PROXY and HTTP_PROXY
+    #[arg(global = true)]
+    #[clap(long, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// Load environment variables, e.g. GH_TOKEN and REPO, from a file of KEY=VALUE lines.
+    /// Variables already set in the environment are kept.
+    #[arg(global = true)]
+    #[clap(long, value_name = "FILE")]
+    pub env_file: Option<PathBuf>,
+
+    /// Print the git and other commands ghtool runs, and the HTTP requests it makes, to stderr.
+    /// Tokens are not printed.
+    #[arg(global = true)]
+    #[clap(long, visible_alias = "print-command")]
+    pub trace_requests: bool,
+
+    /// PEM file with additional CA certificates to trust; defaults to SSL_CERT_FILE
+    #[arg(global = true)]
+    #[clap(long, value_name = "FILE")]
+    pub cainfo: Option<PathBuf>,
+
+    /// Print the last N lines of logs of failed checks whose logs had no errors that could be
+    /// parsed
+    #[arg(global = true)]
+    #[clap(long, value_name = "N")]
+    pub tail: Option<usize>,
+
+    /// Read errors of failed jobs from their check run annotations instead of logs. Logs are parsed
+    /// for jobs without failure or warning annotations.
+    #[arg(global = true)]
+    #[clap(long)]
+    pub annotations: bool,
+
+    /// Read the checks again up to N times, a few seconds apart, when logs of a failed check
+    /// aren't found, e.g. because the check was re-run while it was being read
+    #[arg(global = true)]
+    #[clap(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+
+    /// Stop downloading logs of a job after this many bytes, e.g. 500MB; the downloaded part is
+    /// parsed
+    #[arg(global = true)]
+    #[clap(long, value_name = "SIZE", default_value = "500MB", value_parser = parse_size)]
+    pub max_download: u64,
+
+    /// Stop at the first failed job with errors, printing only its errors, without downloading
+    /// the logs of the rest
+    #[arg(global = true)]
+    #[clap(long)]
+    pub fail_fast: bool,
+
+    /// Fail when the checks last completed longer than this ago, e.g. 3d; units are s, m, h, d
+    /// and w
+    #[arg(global = true)]
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub max_age: Option<chrono::Duration>,
+
+    /// Show checks older than --max-age with a warning instead of failing
+    #[arg(global = true)]
+    #[clap(long, requires = "max_age")]
+    pub force: bool,
+
+    /// Rewrite paths and positions of errors in files with a source map next to them in the
+    /// checkout, e.g. dist/a.test.js.map, to those in the original sources
+    #[arg(global = true)]
+    #[clap(long)]
+    pub resolve_sourcemaps: bool,
+
+    /// Tag lint and build issues with whether their line was last changed by the pull request's
+    /// commits or is pre-existing, using git blame in the local checkout
+    #[arg(global = true)]
+    #[clap(long, conflicts_with = "default_branch")]
+    pub merge_base_context: bool,
+
+    /// Write the downloaded logs of failed checks to DIR, one file per check
+    #[arg(global = true)]
+    #[clap(long, value_name = "DIR")]
+    pub save_logs: Option<PathBuf>,
+
+    /// Keep downloaded logs of failed checks on disk for a week and read them from there the next
+    /// time, instead of downloading them again. Logs cut at --max-download aren't kept.
+    #[arg(global = true)]
+    #[clap(long)]
+    pub cache_logs: bool,
+
+    /// Parse the logs cached with --cache-logs again without downloading them, e.g. to try
+    /// changes to job patterns or tools; fails if a log isn't cached
+    #[arg(global = true)]
+    #[clap(long)]
+    pub reparse: bool,
+
+    /// Print the number of failing files and errors of each owner in the checkout's CODEOWNERS
+    /// after the errors
+    #[arg(global = true)]
+    #[clap(long)]
+    pub team_summary: bool,
+
+    /// Print a "## <job> (<url>)" line above the errors of each job instead of a box
+    #[arg(global = true)]
+    #[clap(long)]
+    pub compact: bool,
+
+    /// Don't ask for confirmation: show errors of all failed jobs without asking which ones when
+    /// several failed, and go on with commands that change state, such as logout and run --exec.
+    /// Asking is skipped also when not run in a terminal, and with --format json, ndjson or
+    /// locations or --output-template.
+    #[arg(global = true)]
+    #[clap(long, short, visible_alias = "assume-yes")]
+    pub yes: bool,
+
+    /// Don't print informational messages, such as a newer version being available or all
+    /// checks being green
+    #[arg(global = true)]
+    #[clap(long, short)]
+    pub quiet: bool,
+
+    /// Print progress messages instead of spinners
+    #[arg(global = true)]
+    #[clap(long)]
+    pub no_spinner: bool,
+
+    /// Output format; ndjson writes a JSON object per error, or per file with --files. See README
+    /// for the structure of JSON output
+    #[arg(global = true)]
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Print a line for each issue in this format instead, e.g. "{path}:{line}:{col} {message}".
+    /// Placeholders are {path}, {line}, {col}, {message} and {check}
+    #[arg(global = true)]
+    #[clap(long, value_name = "TEMPLATE", conflicts_with = "format")]
+    pub output_template: Option<String>,
+
+    /// When to use colors in output
+    #[arg(global = true)]
+    #[clap(long, value_enum, default_value_t = ColorWhen::Auto)]
+    pub color: ColorWhen,
+
+    /// Remove ANSI colors of the CI logs from printed lines. Done by default when colors aren't
+    /// used, e.g. when output isn't a terminal or with --color never.
+    #[arg(global = true)]
+    #[clap(long, conflicts_with = "keep_ansi")]
+    pub strip_ansi: bool,
+
+    /// Print lines with the ANSI colors of the CI logs even when colors aren't used otherwise
+    #[arg(global = true)]
+    #[clap(long)]
+    pub keep_ansi: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Ndjson,
+    /// path:line:col of each issue, or path when the tool reports no positions, sorted
+    Locations,
+    /// A collapsed section with the errors of each job, e.g. for a pull request comment
+    Markdown,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum LintGroupBy {
+    Rule,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ColorWhen {
+    Auto,
+    Always,
+    Never,
 }
 
 #[derive(Subcommand, Debug)]
@@ -27,6 +319,20 @@ pub enum Commands {
         /// Output only the file paths
         #[clap(long, short)]
         files: bool,
+
+        /// With --files, output the number of failed tests of each file, most first
+        #[clap(long, requires = "files")]
+        counts: bool,
+
+        /// Output the failed tests of each file under the suites (describe blocks) they're in;
+        /// jest only
+        #[clap(long, conflicts_with = "files")]
+        group_tests_by_suite: bool,
+
+        /// Output only the failed tests whose names, including the suites they're in, match the
+        /// regex
+        #[clap(long, value_name = "PATTERN")]
+        name: Option<Regex>,
     },
 
     /// Get lint issues for the current branch's pull request's checks
@@ -34,6 +340,14 @@ pub enum Commands {
         /// Output only the file paths
         #[clap(long, short)]
         files: bool,
+
+        /// With --files, output only files with at least one error, not just warnings
+        #[clap(long, requires = "files")]
+        errors_only: bool,
+
+        /// Group issues, printing the number of issues and files of each group
+        #[clap(long, value_enum, value_name = "GROUP", conflicts_with = "files")]
+        group_by: Option<LintGroupBy>,
     },
 
     /// Get build issues for the current branch's pull request's checks
@@ -41,11 +355,63 @@ pub enum Commands {
         /// Output only the file paths
         #[clap(long, short)]
         files: bool,
+
+        /// Output only the first line of each error, e.g. without the "Overload 1 of 2" cascades
+        /// of tsc errors
+        #[clap(long, conflicts_with = "files", overrides_with = "full")]
+        terse: bool,
+
+        /// Output each error with its full context; the default, overriding an earlier --terse
+        #[clap(long, overrides_with = "terse")]
+        full: bool,
     },
 
     /// Wait for checks to complete and run all test, lint and build together
     All {},
 
+    /// Get test, lint and build issues of the latest commit of the repository's default branch,
+    /// to tell if it's broken
+    Main {
+        /// Output only the file paths
+        #[clap(long, short)]
+        files: bool,
+    },
+
+    /// Print whether the pull request's checks have passed, without waiting for or reading logs.
+    /// Exits with 1 if any check failed, or 8 if any is still pending.
+    Check {
+        /// Wait for all checks to complete, then print the errors of failed test, lint and build
+        /// checks. Exits with 0 only if every check passed.
+        #[clap(long, conflicts_with = "last")]
+        watch_until_green: bool,
+    },
+
+    /// Print the counts of passed, failed and pending checks of the pull requests of several
+    /// branches. Exits with 1 if any check failed, or 8 if any is still pending.
+    Status {
+        /// Comma-separated branches, e.g. a,b,c. Read from stdin, one per line, if not given.
+        #[clap(long, value_delimiter = ',', value_name = "BRANCHES")]
+        branches: Vec<String>,
+    },
+
+    /// Open the page of a failed job of the pull request in the browser, asking which one when
+    /// several failed
+    Open {
+        /// Open the failed job whose name contains this, ignoring case, e.g. "unit"
+        #[clap(long, value_name = "NAME")]
+        job: Option<String>,
+    },
+
+    /// Print the run steps of the workflow job of a check, to reproduce it locally
+    Run {
+        /// Name of the check, e.g. "Unit tests (1)"
+        check: String,
+
+        /// Run the steps in the repository after printing them
+        #[clap(long)]
+        exec: bool,
+    },
+
     /// Authenticate ghtool with GitHub API
     Login {
         /// Use stdin to pass a token that will be saved to system key store
@@ -55,4 +421,108 @@ pub enum Commands {
 
     /// Deauthenticate ghtool with GitHub API
     Logout {},
+
+    /// Check the repository, config, token and connection to GitHub, and how to fix what is wrong
+    Doctor {},
+
+    /// List the test runners, lint tools and build tools whose logs can be parsed, by their
+    /// `tool` value in .ghtool.toml
+    Tools {},
+
+    /// Utilities for working with .ghtool.toml
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Print a JSON Schema describing .ghtool.toml, for editor validation and completion
+    Schema {},
+
+    /// Print which config sections' job_pattern matches a check name, e.g. one copied from
+    /// GitHub, to debug patterns that don't match any jobs
+    TestPattern {
+        /// Name of the check run, e.g. "Unit tests (1/3)"
+        check: String,
+    },
+}
+
+/// Parse a size in bytes with an optional K, M or G suffix (powers of 1024), e.g. 500MB
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(digits_end);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size {s:?}, expected e.g. 500MB"))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => {
+            return Err(format!(
+                "invalid size unit {unit:?}, expected one of K, M or G"
+            ))
+        }
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size {s:?} is too large"))
+}
+
+/// Parse a duration with a s, m, h, d or w suffix, e.g. 12h
+fn parse_duration(s: &str) -> Result<chrono::Duration, String> {
+    let s = s.trim();
+    let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(digits_end);
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration {s:?}, expected e.g. 3d"))?;
+    let seconds: i64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid duration unit {unit:?}, expected one of s, m, h, d or w"
+            ))
+        }
+    };
+    number
+        .checked_mul(seconds)
+        // chrono::Duration holds milliseconds
+        .filter(|seconds| *seconds <= i64::MAX / 1000)
+        .map(chrono::Duration::seconds)
+        .ok_or_else(|| format!("duration {s:?} is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("500MB"), Ok(500 * 1024 * 1024));
+        assert_eq!(parse_size("2g"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("10 K"), Ok(10 * 1024));
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("5TB").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Ok(chrono::Duration::seconds(90)));
+        assert_eq!(parse_duration("12h"), Ok(chrono::Duration::hours(12)));
+        assert_eq!(parse_duration("3D"), Ok(chrono::Duration::days(3)));
+        assert_eq!(parse_duration("1w"), Ok(chrono::Duration::weeks(1)));
+        assert!(parse_duration("3").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("2y").is_err());
+    }
 }
@@ -0,0 +1,119 @@
+use eyre::Result;
+use tracing::info;
+
+use crate::{
+    commands::CheckError,
+    git::Repository,
+    github::{Annotation, GithubClient, SimpleCheckRun},
+};
+
+/// GitHub Actions annotates failed jobs with e.g. "Process completed with exit code 1." on this
+/// path; the annotation doesn't point at an error
+const RUNNER_ANNOTATION_PATH: &str = ".github";
+
+/// Errors from the annotations of a check run, or None if it has none to read errors from
+pub async fn fetch_annotation_errors(
+    client: &GithubClient,
+    repo: &Repository,
+    check_run: &SimpleCheckRun,
+) -> Result<Option<Vec<CheckError>>> {
+    let annotations = client
+        .get_check_run_annotations(&repo.owner, &repo.name, check_run.id)
+        .await?;
+    let errors = annotation_errors(&annotations);
+    info!(
+        name = ?check_run.name,
+        annotations = annotations.len(),
+        errors = errors.len(),
+        "got check run annotations"
+    );
+    Ok(Some(errors).filter(|errors| !errors.is_empty()))
+}
+
+/// An error for each failure or warning annotation, e.g. "src/a.ts:3:21 failure TS2322" followed by
+/// the lines of the message
+fn annotation_errors(annotations: &[Annotation]) -> Vec<CheckError> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.annotation_level != "notice")
+        .filter(|annotation| annotation.path != RUNNER_ANNOTATION_PATH)
+        .map(|annotation| {
+            let position = match annotation.start_column {
+                Some(column) => format!("{}:{}:{}", annotation.path, annotation.start_line, column),
+                None => format!("{}:{}", annotation.path, annotation.start_line),
+            };
+            let mut header = format!("{} {}", position, annotation.annotation_level);
+            if let Some(title) = annotation
+                .title
+                .as_deref()
+                .filter(|title| !title.is_empty())
+            {
+                header.push(' ');
+                header.push_str(title);
+            }
+
+            let mut lines = vec![header];
+            lines.extend(annotation.message.lines().map(|line| format!("  {line}")));
+            CheckError {
+                path: annotation.path.clone(),
+                lines,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn annotation(path: &str, level: &str, message: &str) -> Annotation {
+        Annotation {
+            path: path.to_string(),
+            start_line: 3,
+            start_column: Some(21),
+            annotation_level: level.to_string(),
+            title: Some("TS2322".to_string()),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_annotation_errors() {
+        let annotations = vec![
+            annotation(
+                "src/a.ts",
+                "failure",
+                "Type 'number' is not assignable to type 'string'.\nSecond line",
+            ),
+            annotation("src/b.ts", "notice", "Just so you know"),
+            annotation(".github", "failure", "Process completed with exit code 2."),
+            Annotation {
+                start_column: None,
+                title: None,
+                ..annotation("src/c.ts", "warning", "Unused variable")
+            },
+        ];
+
+        assert_eq!(
+            annotation_errors(&annotations),
+            vec![
+                CheckError {
+                    path: "src/a.ts".to_string(),
+                    lines: vec![
+                        "src/a.ts:3:21 failure TS2322".to_string(),
+                        "  Type 'number' is not assignable to type 'string'.".to_string(),
+                        "  Second line".to_string(),
+                    ],
+                },
+                CheckError {
+                    path: "src/c.ts".to_string(),
+                    lines: vec![
+                        "src/c.ts:3 warning".to_string(),
+                        "  Unused variable".to_string(),
+                    ],
+                },
+            ]
+        );
+    }
+}
@@ -2,7 +2,6 @@ use std::time::Duration;
 
 use eyre::{eyre, Context, Result};
 use http::StatusCode;
-use indicatif::ProgressBar;
 use tracing::info;
 
 use crate::{
@@ -10,7 +9,7 @@ use crate::{
         AccessToken, AccessTokenResponse, CodeResponse, CurrentUser, GithubApiError,
         GithubAuthClient, GithubClient,
     },
-    spinner::make_spinner_style,
+    spinner::new_spinner,
     term::{bold, prompt_for_user_to_continue, read_stdin},
     token_store::{self, get_token},
 };
@@ -33,7 +32,7 @@ pub async fn login(use_stdin_token: bool) -> Result<()> {
     token_store::set_token(hostname, &access_token)
         .map_err(|e| eyre!(e).wrap_err("Failed to store token"))?;
 
-    let client = GithubClient::new(&access_token)?;
+    let client = GithubClient::new(&access_token, hostname)?;
     let current_user = client.get_current_user().await?;
 
     println!(
@@ -56,7 +55,7 @@ async fn validate_existing_token(hostname: &str) -> Result<Option<CurrentUser>>
         }
     };
 
-    let client = GithubClient::new(&token)?;
+    let client = GithubClient::new(&token, hostname)?;
     match client.get_current_user().await {
         Ok(current_user) => Ok(Some(current_user)),
         Err(GithubApiError::ErrorResponse(StatusCode::UNAUTHORIZED, _)) => {
@@ -84,20 +83,12 @@ async fn acquire_token_from_github() -> Result<String> {
     info!("Opening {} in browser", code_response.verification_uri);
     open::that(&code_response.verification_uri)?;
 
-    let pb = create_progress_bar();
+    let pb = new_spinner("Waiting for authorization...");
     let token = await_authorization(&auth_client, &code_response).await?;
     pb.finish_and_clear();
     Ok(token.access_token)
 }
 
-fn create_progress_bar() -> ProgressBar {
-    let pb = ProgressBar::new_spinner();
-    pb.enable_steady_tick(Duration::from_millis(100));
-    pb.set_style(make_spinner_style());
-    pb.set_message("Waiting for authorization...");
-    pb
-}
-
 async fn await_authorization(
     auth_client: &GithubAuthClient,
     code_response: &CodeResponse,
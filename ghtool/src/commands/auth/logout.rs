@@ -1,9 +1,16 @@
-use crate::{term::bold, token_store};
+use crate::{
+    cli::Cli,
+    term::{bold, confirm},
+    token_store,
+};
 use eyre::Result;
 
-pub fn logout() -> Result<()> {
+pub fn logout(cli: &Cli) -> Result<()> {
     // Assume hostname github.com for now
     let hostname = "github.com";
+    if !confirm(cli.yes, &format!("Log out of {}?", bold(hostname)))? {
+        return Ok(());
+    }
     token_store::delete_token(hostname)?;
     println!("Logged out of {} account", bold(hostname));
     Ok(())
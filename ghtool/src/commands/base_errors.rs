@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::commands::{CheckError, CheckRunErrors, Command};
+
+lazy_static! {
+    /// Regex to match the duration of a test, e.g. "(5 ms)" in "✓ adds numbers (5 ms)"
+    static ref TEST_DURATION: Regex = Regex::new(r"\(\d+(?:\.\d+)? m?s\)").unwrap();
+}
+
+/// Line without colors, issue position and test durations, so that an issue moved by lines added
+/// above it, or a test taking a different time, compares equal
+fn normalized_line(command: &dyn Command, line: &str) -> String {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let mut line = line_no_ansi.trim().to_string();
+    if let Some((line_number, column)) = command.issue_position(&line) {
+        line = without_position(&line, line_number, column);
+    }
+    TEST_DURATION.replace_all(&line, "").into_owned()
+}
+
+/// The line with the first occurrence of the position, e.g. 3:21 or (3,21), or of only the line
+/// number when the column isn't next to it, replaced with zeros
+fn without_position(line: &str, line_number: u32, column: u32) -> String {
+    let position = Regex::new(&format!(r"(^|\D){line_number}([:,]){column}(\D|$)")).unwrap();
+    if position.is_match(line) {
+        return position.replace(line, "${1}0${2}0${3}").into_owned();
+    }
+    let line_number = Regex::new(&format!(r"(^|\D){line_number}(\D|$)")).unwrap();
+    line_number.replace(line, "${1}0${2}").into_owned()
+}
+
+/// Errors of a base branch, to tell which errors of a pull request are already on it
+pub struct BaseErrors<'a> {
+    /// Lines reporting an issue, by file
+    issues: HashSet<(&'a str, String)>,
+    /// All lines of each error, for errors reporting no single issue, e.g. failed test files
+    errors: HashSet<(&'a str, Vec<String>)>,
+}
+
+impl<'a> BaseErrors<'a> {
+    pub fn new(command: &dyn Command, base_errors: &'a [CheckError]) -> Self {
+        let mut issues = HashSet::new();
+        let mut errors = HashSet::new();
+        for error in base_errors {
+            for line in &error.lines {
+                if command.issue_position(line).is_some() {
+                    issues.insert((error.path.as_str(), normalized_line(command, line)));
+                }
+            }
+            let lines = error
+                .lines
+                .iter()
+                .map(|line| normalized_line(command, line))
+                .collect();
+            errors.insert((error.path.as_str(), lines));
+        }
+        BaseErrors { issues, errors }
+    }
+
+    fn has_issue(&self, command: &dyn Command, path: &str, line: &str) -> bool {
+        self.issues
+            .contains(&(path, normalized_line(command, line)))
+    }
+
+    fn has_error(&self, command: &dyn Command, path: &str, lines: &[String]) -> bool {
+        let lines = lines
+            .iter()
+            .map(|line| normalized_line(command, line))
+            .collect();
+        self.errors.contains(&(path, lines))
+    }
+
+    /// Remove the errors that are also on the base branch, returning how many issues were removed.
+    /// Errors with several issues, e.g. eslint's of a file, keep the issues that aren't on it.
+    pub fn subtract(&self, command: &dyn Command, sections: &mut [CheckRunErrors]) -> usize {
+        let mut removed = 0;
+        for section in sections {
+            section.errors.retain_mut(|error| {
+                let CheckError { path, lines } = error;
+                let issue_count = lines
+                    .iter()
+                    .filter(|line| command.issue_position(line).is_some())
+                    .count();
+
+                if issue_count > 1 {
+                    lines.retain(|line| {
+                        let on_base = command.issue_position(line).is_some()
+                            && self.has_issue(command, path, line);
+                        removed += on_base as usize;
+                        !on_base
+                    });
+                    return lines
+                        .iter()
+                        .any(|line| command.issue_position(line).is_some());
+                }
+
+                let on_base = match lines
+                    .iter()
+                    .find(|line| command.issue_position(line).is_some())
+                {
+                    Some(issue_line) => self.has_issue(command, path, issue_line),
+                    None => self.has_error(command, path, lines),
+                };
+                removed += on_base as usize;
+                !on_base
+            });
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::{LintCommand, TestCommand},
+        github::{CheckConclusionState, CheckStatusState, SimpleCheckRun},
+        repo_config::RepoConfig,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn error(path: &str, lines: &[&str]) -> CheckError {
+        CheckError {
+            path: path.to_string(),
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_subtract() {
+        let repo_config: RepoConfig =
+            toml::from_str("[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n").unwrap();
+        let command = LintCommand::from_repo_config(&repo_config).unwrap();
+        let base_errors = vec![
+            error(
+                "src/a.ts",
+                &[
+                    "src/a.ts",
+                    "  3:10  error  'foo' is defined but never used  no-unused-vars",
+                ],
+            ),
+            error(
+                "src/b.ts",
+                &[
+                    "src/b.ts",
+                    "  8:1  error  Unexpected any  @typescript-eslint/no-explicit-any",
+                ],
+            ),
+        ];
+        let mut sections = vec![CheckRunErrors {
+            check_run: SimpleCheckRun {
+                id: 1,
+                name: "Lint".to_string(),
+                status: CheckStatusState::Completed,
+                conclusion: Some(CheckConclusionState::Failure),
+                url: None,
+                started_at: None,
+                completed_at: None,
+                app: None,
+                is_required: false,
+            },
+            errors: vec![
+                // The issue on base moved down by lines added above it
+                error(
+                    "src/a.ts",
+                    &[
+                        "src/a.ts",
+                        "  5:10  error  'foo' is defined but never used  no-unused-vars",
+                        "  9:3  error  'bar' is defined but never used  no-unused-vars",
+                    ],
+                ),
+                error(
+                    "src/b.ts",
+                    &[
+                        "src/b.ts",
+                        "  10:1  error  Unexpected any  @typescript-eslint/no-explicit-any",
+                    ],
+                ),
+            ],
+        }];
+
+        let base_errors = BaseErrors::new(&command, &base_errors);
+        assert_eq!(base_errors.subtract(&command, &mut sections), 2);
+        assert_eq!(
+            sections[0].errors,
+            vec![error(
+                "src/a.ts",
+                &[
+                    "src/a.ts",
+                    "  9:3  error  'bar' is defined but never used  no-unused-vars"
+                ],
+            )]
+        );
+    }
+
+    #[test]
+    fn test_normalized_line() {
+        let repo_config: RepoConfig = toml::from_str(
+            "[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n\
+             [test]\njob_pattern = \"Test\"\ntool = \"jest\"\n",
+        )
+        .unwrap();
+        let lint = LintCommand::from_repo_config(&repo_config).unwrap();
+        assert_eq!(
+            normalized_line(
+                &lint,
+                "  5:10  error  'foo' is defined but never used  no-unused-vars"
+            ),
+            normalized_line(
+                &lint,
+                "  3:2  error  'foo' is defined but never used  no-unused-vars"
+            )
+        );
+        assert_ne!(
+            normalized_line(
+                &lint,
+                "  5:10  error  Expected 2 arguments, but got 1  ts-rule"
+            ),
+            normalized_line(
+                &lint,
+                "  5:10  error  Expected 3 arguments, but got 1  ts-rule"
+            )
+        );
+
+        let test = TestCommand::from_repo_config(&repo_config).unwrap();
+        assert_eq!(
+            normalized_line(&test, "✕ adds 2 numbers (5 ms)"),
+            normalized_line(&test, "✕ adds 2 numbers (1.2 s)")
+        );
+        assert_ne!(
+            normalized_line(&test, "Expected: 2"),
+            normalized_line(&test, "Expected: 3")
+        );
+    }
+}
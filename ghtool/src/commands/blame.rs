@@ -0,0 +1,206 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    process::Command as Process,
+};
+
+use eyre::{Result, WrapErr};
+use tracing::debug;
+
+use super::{CheckError, Command};
+use crate::trace::trace_command;
+
+/// Commits made in the pull request are those of its head that aren't on the default branch
+const DEFAULT_BRANCH_REF: &str = "refs/remotes/origin/HEAD";
+
+/// Finds with git blame in a local checkout whether lines were last changed by the pull request
+pub struct Blame {
+    repo_path: PathBuf,
+    head_sha: String,
+    pull_request_commits: HashSet<String>,
+}
+
+impl Blame {
+    pub fn new(repo_path: &Path, head_sha: &str) -> Result<Blame> {
+        git(
+            repo_path,
+            &["cat-file", "-e", &format!("{head_sha}^{{commit}}")],
+        )
+        .wrap_err_with(|| {
+            format!("Commit {head_sha} of the checks was not found in the local repository")
+        })?;
+        let commits = git(
+            repo_path,
+            &["rev-list", head_sha, &format!("^{DEFAULT_BRANCH_REF}")],
+        )
+        .wrap_err("Error finding commits of the pull request; run git remote set-head origin --auto if origin/HEAD is missing")?;
+
+        Ok(Blame {
+            repo_path: repo_path.to_path_buf(),
+            head_sha: head_sha.to_string(),
+            pull_request_commits: commits.lines().map(String::from).collect(),
+        })
+    }
+
+    /// Commits that last changed each line of the file at the head commit, the first line's
+    /// first. None when the file can't be blamed, e.g. when it isn't in the head commit.
+    fn line_commits(&self, path: &str) -> Option<Vec<String>> {
+        let output = git(
+            &self.repo_path,
+            &["blame", "--porcelain", &self.head_sha, "--", path],
+        )
+        .inspect_err(|e| debug!(?e, path, "git blame failed"))
+        .ok()?;
+        Some(parse_porcelain_commits(&output))
+    }
+
+    /// Whether the line, one-based, was last changed by the pull request. None when the line
+    /// isn't in the file.
+    fn is_introduced(&self, line_commits: &[String], line: u32) -> Option<bool> {
+        let sha = line_commits.get(usize::try_from(line).ok()?.checked_sub(1)?)?;
+        Some(self.pull_request_commits.contains(sha))
+    }
+}
+
+/// Commit of each line from git blame --porcelain output, where every line of the file has a
+/// header of its commit and line numbers, e.g. "<sha> 1 1 2", before the line itself
+fn parse_porcelain_commits(output: &str) -> Vec<String> {
+    let mut commits = Vec::new();
+    for line in output.lines().filter(|line| !line.starts_with('\t')) {
+        let mut fields = line.split(' ');
+        let Some(sha) = fields.next() else {
+            continue;
+        };
+        let is_header = sha.len() == 40
+            && sha.chars().all(|c| c.is_ascii_hexdigit())
+            && fields.all(|field| field.parse::<u32>().is_ok());
+        if is_header {
+            commits.push(sha.to_string());
+        }
+    }
+    commits
+}
+
+fn git(repo_path: &Path, args: &[&str]) -> Result<String> {
+    let mut process = Process::new("git");
+    process.args(args).current_dir(repo_path);
+    trace_command(&process);
+    let output = process.output()?;
+    if !output.status.success() {
+        eyre::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Tag the issue lines of errors with whether the line of the issue was introduced by the pull
+/// request. Lines that can't be blamed, e.g. of files not in the checkout, are left as is. Each
+/// file is blamed once.
+pub fn annotate_introduced_lines(command: &dyn Command, errors: &mut [CheckError], blame: &Blame) {
+    let mut file_commits: HashMap<String, Option<Vec<String>>> = HashMap::new();
+    for error in errors {
+        let line_commits = file_commits
+            .entry(error.path.clone())
+            .or_insert_with(|| blame.line_commits(&error.path));
+        let Some(line_commits) = line_commits else {
+            continue;
+        };
+        for line in &mut error.lines {
+            let Some(line_number) = command.issue_line_number(line) else {
+                continue;
+            };
+            match blame.is_introduced(line_commits, line_number) {
+                Some(true) => line.push_str(" (introduced by this PR)"),
+                Some(false) => line.push_str(" (pre-existing)"),
+                None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commands::LintCommand, repo_config::RepoConfig};
+    use pretty_assertions::assert_eq;
+    use std::fs;
+
+    fn commit(repo_path: &Path, path: &str, contents: &str) -> String {
+        fs::write(repo_path.join(path), contents).unwrap();
+        git(repo_path, &["add", path]).unwrap();
+        git(
+            repo_path,
+            &[
+                "-c",
+                "user.name=ghtool",
+                "-c",
+                "user.email=ghtool@example.com",
+                "commit",
+                "-q",
+                "-m",
+                path,
+            ],
+        )
+        .unwrap();
+        git(repo_path, &["rev-parse", "HEAD"])
+            .unwrap()
+            .trim()
+            .to_string()
+    }
+
+    #[test]
+    fn test_annotate_introduced_lines() {
+        let dir = std::env::temp_dir().join(format!("ghtool-blame-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        git(&dir, &["init", "-q"]).unwrap();
+        let base_sha = commit(&dir, "a.ts", "const a = 1\nconst b = 2\n");
+        git(&dir, &["update-ref", "refs/remotes/origin/main", &base_sha]).unwrap();
+        git(
+            &dir,
+            &[
+                "symbolic-ref",
+                DEFAULT_BRANCH_REF,
+                "refs/remotes/origin/main",
+            ],
+        )
+        .unwrap();
+        let head_sha = commit(&dir, "a.ts", "const a = 1\nconst b: string = 2\n");
+
+        let blame = Blame::new(&dir, &head_sha).unwrap();
+        let repo_config: RepoConfig =
+            toml::from_str("[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n").unwrap();
+        let command = LintCommand::from_repo_config(&repo_config).unwrap();
+        let mut errors = vec![
+            CheckError {
+                path: "a.ts".to_string(),
+                lines: vec![
+                    "a.ts".to_string(),
+                    "  1:7  error  'a' is assigned a value but never used  no-unused-vars"
+                        .to_string(),
+                    "  2:7  error  'b' is assigned a value but never used  no-unused-vars"
+                        .to_string(),
+                    "  9:1  error  Line 9 doesn't exist  some-rule".to_string(),
+                ],
+            },
+            CheckError {
+                path: "missing.ts".to_string(),
+                lines: vec!["  1:1  error  File doesn't exist  some-rule".to_string()],
+            },
+        ];
+        annotate_introduced_lines(&command, &mut errors, &blame);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            errors[0].lines,
+            vec![
+                "a.ts",
+                "  1:7  error  'a' is assigned a value but never used  no-unused-vars (pre-existing)",
+                "  2:7  error  'b' is assigned a value but never used  no-unused-vars (introduced by this PR)",
+                "  9:1  error  Line 9 doesn't exist  some-rule",
+            ]
+        );
+        assert_eq!(
+            errors[1].lines,
+            vec!["  1:1  error  File doesn't exist  some-rule"]
+        );
+    }
+}
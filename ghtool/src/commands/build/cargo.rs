@@ -0,0 +1,172 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::debug;
+
+use crate::commands::CheckError;
+
+lazy_static! {
+    /// Regex to match a timestamp and single space after it, if the line goes on
+    static ref TIMESTAMP: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z(?:\s|$)").unwrap();
+
+    /// Regex to match the first line of a rustc diagnostic
+    /// Example: error[E0412]: cannot find type `Foo` in this scope
+    static ref DIAGNOSTIC_START: Regex =
+        Regex::new(r"^(?P<level>error|warning)(?:\[\w+\])?: ").unwrap();
+
+    /// Regex to match the location of a diagnostic
+    /// Example:  --> src/main.rs:3:5
+    static ref LOCATION: Regex =
+        Regex::new(r"^\s*--> (?P<path>[^:]+):(?P<line>\d+):(?P<col>\d+)").unwrap();
+}
+
+/// Line and column of a diagnostic's location line
+pub fn issue_position(line: &str) -> Option<(u32, u32)> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = LOCATION.captures(&line_no_ansi)?;
+    Some((caps["line"].parse().ok()?, caps["col"].parse().ok()?))
+}
+
+#[derive(Debug)]
+pub struct CargoLogParser {
+    /// Lines of the error diagnostic currently being parsed
+    current_lines: Option<Vec<String>>,
+    current_path: Option<String>,
+    all_errors: Vec<CheckError>,
+}
+
+impl CargoLogParser {
+    pub fn new() -> Self {
+        CargoLogParser {
+            current_lines: None,
+            current_path: None,
+            all_errors: Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, full_line: &str) {
+        let line = TIMESTAMP.replace(full_line, "");
+        let line_no_ansi = strip_ansi_escapes::strip_str(&line);
+
+        if let Some(caps) = DIAGNOSTIC_START.captures(&line_no_ansi) {
+            self.finish_error();
+            // Warnings don't fail the build, unless denied, in which case they're errors
+            if &caps["level"] == "error" {
+                self.current_lines = Some(vec![line.to_string()]);
+            }
+            return;
+        }
+
+        let Some(current_lines) = self.current_lines.as_mut() else {
+            return;
+        };
+
+        // Diagnostics are separated by an empty line
+        if line_no_ansi.trim().is_empty() {
+            self.finish_error();
+            return;
+        }
+
+        if self.current_path.is_none() {
+            if let Some(caps) = LOCATION.captures(&line_no_ansi) {
+                self.current_path = Some(caps["path"].to_string());
+            }
+        }
+        current_lines.push(line.to_string());
+    }
+
+    /// Diagnostics without a location, like "error: could not compile `ghtool`", are dropped
+    fn finish_error(&mut self) {
+        let lines = self.current_lines.take();
+        if let (Some(lines), Some(path)) = (lines, self.current_path.take()) {
+            self.all_errors.push(CheckError { path, lines });
+        }
+    }
+
+    pub fn parse(log: &str) -> Vec<CheckError> {
+        let mut parser = CargoLogParser::new();
+
+        for line in log.lines() {
+            parser.parse_line(line);
+        }
+        parser.finish_error();
+
+        debug!(errors = parser.all_errors.len(), "parsed cargo log");
+        parser.all_errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_errors() {
+        let logs = r#"
+2024-03-01T10:00:00.1000000Z    Compiling ghtool v0.10.0 (/home/runner/work/ghtool/ghtool/ghtool)
+2024-03-01T10:00:01.1000000Z warning: unused variable: `x`
+2024-03-01T10:00:01.1000000Z  --> ghtool/src/cli.rs:10:9
+2024-03-01T10:00:01.1000000Z   |
+2024-03-01T10:00:01.1000000Z 10 |     let x = 1;
+2024-03-01T10:00:01.1000000Z   |         ^ help: if this is intentional, prefix it with an underscore: `_x`
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.2000000Z error[E0412]: cannot find type `Foo` in this scope
+2024-03-01T10:00:01.2000000Z  --> ghtool/src/main.rs:3:5
+2024-03-01T10:00:01.2000000Z   |
+2024-03-01T10:00:01.2000000Z 3 |     Foo
+2024-03-01T10:00:01.2000000Z   |     ^^^ not found in this scope
+2024-03-01T10:00:01.2000000Z
+2024-03-01T10:00:01.3000000Z error: unused import: `std::fs`
+2024-03-01T10:00:01.3000000Z  --> ghtool/src/git.rs:1:5
+2024-03-01T10:00:01.3000000Z   |
+2024-03-01T10:00:01.3000000Z 1 | use std::fs;
+2024-03-01T10:00:01.3000000Z   |     ^^^^^^^
+2024-03-01T10:00:01.3000000Z
+2024-03-01T10:00:01.4000000Z error: aborting due to 2 previous errors
+2024-03-01T10:00:01.4000000Z
+2024-03-01T10:00:01.4000000Z For more information about this error, try `rustc --explain E0412`.
+2024-03-01T10:00:01.5000000Z error: could not compile `ghtool` (bin "ght") due to 2 previous errors
+2024-03-01T10:00:01.6000000Z ##[error]Process completed with exit code 101."#;
+
+        assert_eq!(
+            CargoLogParser::parse(logs),
+            vec![
+                CheckError {
+                    path: "ghtool/src/main.rs".to_string(),
+                    lines: vec![
+                        "error[E0412]: cannot find type `Foo` in this scope".to_string(),
+                        " --> ghtool/src/main.rs:3:5".to_string(),
+                        "  |".to_string(),
+                        "3 |     Foo".to_string(),
+                        "  |     ^^^ not found in this scope".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "ghtool/src/git.rs".to_string(),
+                    lines: vec![
+                        "error: unused import: `std::fs`".to_string(),
+                        " --> ghtool/src/git.rs:1:5".to_string(),
+                        "  |".to_string(),
+                        "1 | use std::fs;".to_string(),
+                        "  |     ^^^^^^^".to_string(),
+                    ]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_colored_errors() {
+        let logs = "2024-03-01T10:00:01.2000000Z \u{1b}[0m\u{1b}[1m\u{1b}[38;5;9merror[E0425]\u{1b}[0m\u{1b}[0m\u{1b}[1m: cannot find value `y` in this scope\u{1b}[0m
+2024-03-01T10:00:01.2000000Z \u{1b}[0m \u{1b}[0m\u{1b}[0m\u{1b}[1m\u{1b}[38;5;12m--> \u{1b}[0m\u{1b}[0msrc/lib.rs:5:13\u{1b}[0m
+2024-03-01T10:00:01.2000000Z \u{1b}[0m\u{1b}[1m\u{1b}[38;5;12m5\u{1b}[0m\u{1b}[0m \u{1b}[0m\u{1b}[0m\u{1b}[1m\u{1b}[38;5;12m|\u{1b}[0m\u{1b}[0m     let x = y;
+2024-03-01T10:00:01.2000000Z \u{1b}[0m
+2024-03-01T10:00:01.3000000Z \u{1b}[0m\u{1b}[1m\u{1b}[38;5;9merror\u{1b}[0m\u{1b}[0m\u{1b}[1m: could not compile `lib`\u{1b}[0m";
+
+        let errors = CargoLogParser::parse(logs);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "src/lib.rs");
+        assert_eq!(errors[0].lines.len(), 3);
+    }
+}
@@ -1,23 +1,27 @@
 use eyre::Result;
-use regex::Regex;
 
+use crate::error::GhtoolError;
 use crate::repo_config::BuildConfig;
+use crate::repo_config::BuildTool;
 use crate::repo_config::RepoConfig;
 
+use self::cargo::CargoLogParser;
+use self::phpstan::PhpstanLogParser;
+use self::psalm::PsalmLogParser;
 use self::tsc::TscLogParser;
 
+use super::parse_with_most_errors;
+use super::pluralize;
+use super::strip_ansi;
 use super::CheckError;
 use super::Command;
 use super::ConfigPattern;
 
+mod cargo;
+mod phpstan;
+mod psalm;
 mod tsc;
 
-impl ConfigPattern for BuildConfig {
-    fn job_pattern(&self) -> &Regex {
-        &self.job_pattern
-    }
-}
-
 #[derive(Clone)]
 pub struct BuildCommand {
     config: BuildConfig,
@@ -28,7 +32,7 @@ impl BuildCommand {
         let build_config = repo_config
             .build
             .clone()
-            .ok_or_else(|| eyre::eyre!("Error: no build section found in .ghtool.toml"))?;
+            .ok_or(GhtoolError::MissingConfigSection("build"))?;
 
         Ok(Self {
             config: build_config,
@@ -46,10 +50,42 @@ impl Command for BuildCommand {
     }
 
     fn config(&self) -> &dyn ConfigPattern {
-        &self.config
+        &self.config.section
     }
 
     fn parse_log(&self, log: &str) -> Result<Vec<CheckError>> {
-        TscLogParser::parse(log)
+        parse_with_most_errors(&self.config.tool, |tool| match tool {
+            BuildTool::Tsc => TscLogParser::parse(log),
+            BuildTool::Cargo => Ok(CargoLogParser::parse(log)),
+            BuildTool::Phpstan => Ok(PhpstanLogParser::parse(log)),
+            BuildTool::Psalm => Ok(PsalmLogParser::parse(log)),
+        })
+    }
+
+    fn summarize_errors(&self, errors: &[&CheckError]) -> String {
+        pluralize(errors.len(), "error")
+    }
+
+    fn issue_position(&self, line: &str) -> Option<(u32, u32)> {
+        self.config.tool.iter().find_map(|tool| match tool {
+            BuildTool::Tsc => tsc::issue_position(line),
+            BuildTool::Cargo => cargo::issue_position(line),
+            BuildTool::Phpstan => phpstan::issue_position(line),
+            BuildTool::Psalm => psalm::issue_position(line),
+        })
+    }
+
+    fn issue_message(&self, line: &str) -> String {
+        self.config
+            .tool
+            .iter()
+            .find_map(|tool| match tool {
+                BuildTool::Tsc => tsc::issue_message(line),
+                // The message is on the diagnostic's first line, before the location
+                BuildTool::Cargo => None,
+                BuildTool::Phpstan => phpstan::issue_message(line),
+                BuildTool::Psalm => psalm::issue_message(line),
+            })
+            .unwrap_or_else(|| strip_ansi(line).trim().to_string())
     }
 }
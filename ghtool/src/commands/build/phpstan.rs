@@ -0,0 +1,220 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::debug;
+
+use crate::commands::CheckError;
+
+lazy_static! {
+    /// Regex to match a timestamp and single space after it, if the line goes on
+    static ref TIMESTAMP: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z(?:\s|$)").unwrap();
+
+    /// Regex to match a horizontal border of a table
+    /// Example:  ------ -----------------------------------
+    static ref BORDER: Regex = Regex::new(r"^\s*-+\s+-+\s*$").unwrap();
+
+    /// Regex to match the header of a file's table, optionally with the class of a trait's errors
+    /// Example:   Line   src/Controller/UserController.php
+    /// Example:   Line   src/Concerns/HasName.php (in context of class App\User)
+    static ref FILE_HEADER: Regex = Regex::new(r"^\s*Line\s+(?P<path>\S+)(?:\s+\(.*\))?\s*$").unwrap();
+
+    /// Regex to match the first line of an issue in a file's table
+    /// Example:   25     Parameter #1 $id of method App\Repository\UserRepository::find()
+    static ref ISSUE_START: Regex = Regex::new(r"^\s*(?P<line>\d+)\s{2,}\S").unwrap();
+}
+
+/// Line and column of an issue's first line. phpstan reports no columns, so the column is 1.
+pub fn issue_position(line: &str) -> Option<(u32, u32)> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = ISSUE_START.captures(&line_no_ansi)?;
+    Some((caps["line"].parse().ok()?, 1))
+}
+
+/// Message of an issue's first line without its line number
+pub fn issue_message(line: &str) -> Option<String> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let line_number = ISSUE_START.captures(&line_no_ansi)?.name("line")?;
+    Some(line_no_ansi[line_number.end()..].trim().to_string())
+}
+
+#[derive(Debug, PartialEq)]
+enum State {
+    LookingForTable,
+    /// Between the top border and the header of a table
+    ParsingHeader,
+    /// In the table of the file
+    ParsingIssues,
+}
+
+#[derive(Debug)]
+pub struct PhpstanLogParser {
+    state: State,
+    /// File of the table being parsed and its header line, which heads each of its issues
+    current_file: Option<(String, String)>,
+    current_error: Option<CheckError>,
+    all_errors: Vec<CheckError>,
+}
+
+impl PhpstanLogParser {
+    pub fn new() -> Self {
+        PhpstanLogParser {
+            state: State::LookingForTable,
+            current_file: None,
+            current_error: None,
+            all_errors: Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, full_line: &str) {
+        let line = TIMESTAMP.replace(full_line, "");
+        let line_no_ansi = strip_ansi_escapes::strip_str(&line);
+
+        if BORDER.is_match(&line_no_ansi) {
+            self.state = match self.state {
+                State::LookingForTable => State::ParsingHeader,
+                // The border below the header
+                State::ParsingHeader if self.current_file.is_some() => State::ParsingIssues,
+                State::ParsingHeader | State::ParsingIssues => {
+                    self.finish_error();
+                    self.current_file = None;
+                    State::LookingForTable
+                }
+            };
+            return;
+        }
+
+        match self.state {
+            State::LookingForTable => {}
+            State::ParsingHeader => match FILE_HEADER.captures(&line_no_ansi) {
+                Some(caps) => {
+                    self.current_file = Some((caps["path"].to_string(), line.to_string()))
+                }
+                // Tables of errors not in files, e.g. of the configuration, have other headers
+                None => self.state = State::LookingForTable,
+            },
+            State::ParsingIssues => {
+                if ISSUE_START.is_match(&line_no_ansi) {
+                    self.finish_error();
+                    if let Some((path, header)) = &self.current_file {
+                        self.current_error = Some(CheckError {
+                            path: path.clone(),
+                            lines: vec![header.clone(), line.to_string()],
+                        });
+                    }
+                } else if let Some(error) = self.current_error.as_mut() {
+                    error.lines.push(line.to_string());
+                }
+            }
+        }
+    }
+
+    fn finish_error(&mut self) {
+        if let Some(error) = self.current_error.take() {
+            self.all_errors.push(error);
+        }
+    }
+
+    pub fn parse(log: &str) -> Vec<CheckError> {
+        let mut parser = PhpstanLogParser::new();
+
+        for line in log.lines() {
+            parser.parse_line(line);
+        }
+        parser.finish_error();
+
+        debug!(errors = parser.all_errors.len(), "parsed phpstan log");
+        parser.all_errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_errors() {
+        let logs = r#"2024-03-01T10:00:00.1000000Z > vendor/bin/phpstan analyse --no-progress
+2024-03-01T10:00:02.1000000Z Note: Using configuration file /home/runner/work/app/app/phpstan.neon.
+2024-03-01T10:00:02.2000000Z  ------ ----------------------------------------------------------------------
+2024-03-01T10:00:02.2000000Z   Line   src/Controller/UserController.php
+2024-03-01T10:00:02.2000000Z  ------ ----------------------------------------------------------------------
+2024-03-01T10:00:02.2000000Z   25     Parameter #1 $id of method App\Repository\UserRepository::find()
+2024-03-01T10:00:02.2000000Z          expects int, string given.
+2024-03-01T10:00:02.2000000Z          🪪  argument.type
+2024-03-01T10:00:02.2000000Z   42     Method App\Controller\UserController::show() should return
+2024-03-01T10:00:02.2000000Z          Symfony\Component\HttpFoundation\Response but returns null.
+2024-03-01T10:00:02.2000000Z          🪪  return.type
+2024-03-01T10:00:02.2000000Z  ------ ----------------------------------------------------------------------
+2024-03-01T10:00:02.2000000Z
+2024-03-01T10:00:02.2000000Z  ------ ----------------------------------------------------------------------
+2024-03-01T10:00:02.2000000Z   Line   src/Concerns/HasName.php (in context of class App\Entity\User)
+2024-03-01T10:00:02.2000000Z  ------ ----------------------------------------------------------------------
+2024-03-01T10:00:02.2000000Z   12     Access to an undefined property App\Entity\User::$nam.
+2024-03-01T10:00:02.2000000Z          🪪  property.notFound
+2024-03-01T10:00:02.2000000Z          💡 Learn more: https://phpstan.org/blog/solving-phpstan-access-to-undefined-property
+2024-03-01T10:00:02.2000000Z  ------ ----------------------------------------------------------------------
+2024-03-01T10:00:02.2000000Z
+2024-03-01T10:00:02.3000000Z  -- ---------------------------------------------------------------------------
+2024-03-01T10:00:02.3000000Z      Error
+2024-03-01T10:00:02.3000000Z  -- ---------------------------------------------------------------------------
+2024-03-01T10:00:02.3000000Z      Ignored error pattern #^Call to an undefined method# was not matched in reported errors.
+2024-03-01T10:00:02.3000000Z  -- ---------------------------------------------------------------------------
+2024-03-01T10:00:02.3000000Z
+2024-03-01T10:00:02.3000000Z
+2024-03-01T10:00:02.3000000Z  [ERROR] Found 4 errors
+2024-03-01T10:00:02.3000000Z
+2024-03-01T10:00:02.4000000Z ##[error]Process completed with exit code 1."#;
+
+        assert_eq!(
+            PhpstanLogParser::parse(logs),
+            vec![
+                CheckError {
+                    path: "src/Controller/UserController.php".to_string(),
+                    lines: vec![
+                        "  Line   src/Controller/UserController.php".to_string(),
+                        "  25     Parameter #1 $id of method App\\Repository\\UserRepository::find()"
+                            .to_string(),
+                        "         expects int, string given.".to_string(),
+                        "         🪪  argument.type".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "src/Controller/UserController.php".to_string(),
+                    lines: vec![
+                        "  Line   src/Controller/UserController.php".to_string(),
+                        "  42     Method App\\Controller\\UserController::show() should return"
+                            .to_string(),
+                        "         Symfony\\Component\\HttpFoundation\\Response but returns null."
+                            .to_string(),
+                        "         🪪  return.type".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "src/Concerns/HasName.php".to_string(),
+                    lines: vec![
+                        "  Line   src/Concerns/HasName.php (in context of class App\\Entity\\User)"
+                            .to_string(),
+                        "  12     Access to an undefined property App\\Entity\\User::$nam."
+                            .to_string(),
+                        "         🪪  property.notFound".to_string(),
+                        "         💡 Learn more: https://phpstan.org/blog/solving-phpstan-access-to-undefined-property".to_string(),
+                    ]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_issue_position() {
+        assert_eq!(
+            issue_position("  42     Method App\\Controller\\UserController::show() should return"),
+            Some((42, 1))
+        );
+        assert_eq!(
+            issue_message("  42     Method App\\Controller\\UserController::show() should return"),
+            Some("Method App\\Controller\\UserController::show() should return".to_string())
+        );
+        assert_eq!(issue_position("         🪪  return.type"), None);
+    }
+}
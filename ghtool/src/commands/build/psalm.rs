@@ -0,0 +1,158 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::debug;
+
+use crate::commands::CheckError;
+
+lazy_static! {
+    /// Regex to match a timestamp and single space after it, if the line goes on
+    static ref TIMESTAMP: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z(?:\s|$)").unwrap();
+
+    /// Regex to match the first line of an issue, with its type and location
+    /// Example: ERROR: InvalidReturnType - src/Foo.php:12:16 - The declared return type 'int' for Foo::bar is incorrect
+    static ref ISSUE: Regex = Regex::new(
+        r"^(?P<level>ERROR|INFO): (?P<type>\w+) - (?P<path>\S+?):(?P<line>\d+):(?P<col>\d+) - ",
+    )
+    .unwrap();
+}
+
+/// Line and column of an issue's first line
+pub fn issue_position(line: &str) -> Option<(u32, u32)> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = ISSUE.captures(&line_no_ansi)?;
+    Some((caps["line"].parse().ok()?, caps["col"].parse().ok()?))
+}
+
+/// Type and message of an issue's first line without its level and location, e.g.
+/// "InvalidReturnType: The declared return type 'int' for Foo::bar is incorrect"
+pub fn issue_message(line: &str) -> Option<String> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = ISSUE.captures(&line_no_ansi)?;
+    let message = &line_no_ansi[caps.get(0)?.end()..];
+    Some(format!("{}: {}", &caps["type"], message.trim()))
+}
+
+#[derive(Debug)]
+pub struct PsalmLogParser {
+    /// Lines of the issue being parsed: its first line and the code snippet after it
+    current_error: Option<CheckError>,
+    all_errors: Vec<CheckError>,
+}
+
+impl PsalmLogParser {
+    pub fn new() -> Self {
+        PsalmLogParser {
+            current_error: None,
+            all_errors: Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, full_line: &str) {
+        let line = TIMESTAMP.replace(full_line, "");
+        let line_no_ansi = strip_ansi_escapes::strip_str(&line);
+
+        if let Some(caps) = ISSUE.captures(&line_no_ansi) {
+            self.finish_error();
+            // Info issues, shown with --show-info, don't fail the analysis
+            if &caps["level"] == "ERROR" {
+                self.current_error = Some(CheckError {
+                    path: caps["path"].to_string(),
+                    lines: vec![line.to_string()],
+                });
+            }
+            return;
+        }
+
+        // Issues are separated by empty lines
+        if line_no_ansi.trim().is_empty() {
+            self.finish_error();
+        } else if let Some(error) = self.current_error.as_mut() {
+            error.lines.push(line.to_string());
+        }
+    }
+
+    fn finish_error(&mut self) {
+        if let Some(error) = self.current_error.take() {
+            self.all_errors.push(error);
+        }
+    }
+
+    pub fn parse(log: &str) -> Vec<CheckError> {
+        let mut parser = PsalmLogParser::new();
+
+        for line in log.lines() {
+            parser.parse_line(line);
+        }
+        parser.finish_error();
+
+        debug!(errors = parser.all_errors.len(), "parsed psalm log");
+        parser.all_errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_errors() {
+        let logs = r#"2024-03-01T10:00:00.1000000Z > vendor/bin/psalm --no-progress --show-info=true
+2024-03-01T10:00:03.1000000Z Target PHP version: 8.2 (inferred from composer.json).
+2024-03-01T10:00:03.1000000Z Scanning files...
+2024-03-01T10:00:03.2000000Z Analyzing files...
+2024-03-01T10:00:03.2000000Z
+2024-03-01T10:00:03.3000000Z ERROR: InvalidReturnType - src/Service/Price.php:12:16 - The declared return type 'int' for App\Service\Price::total is incorrect, got 'string' (see https://psalm.dev/011)
+2024-03-01T10:00:03.3000000Z   public function total(): int
+2024-03-01T10:00:03.3000000Z
+2024-03-01T10:00:03.3000000Z
+2024-03-01T10:00:03.3000000Z INFO: MissingParamType - src/Service/Price.php:20:27 - Parameter $rate has no provided type (see https://psalm.dev/154)
+2024-03-01T10:00:03.3000000Z   public function convert($rate)
+2024-03-01T10:00:03.3000000Z
+2024-03-01T10:00:03.3000000Z
+2024-03-01T10:00:03.3000000Z ERROR: UndefinedVariable - src/Command/Import.php:48:9 - Cannot find referenced variable $reader (see https://psalm.dev/024)
+2024-03-01T10:00:03.3000000Z         $reader->open($path);
+2024-03-01T10:00:03.3000000Z
+2024-03-01T10:00:03.3000000Z
+2024-03-01T10:00:03.4000000Z ------------------------------
+2024-03-01T10:00:03.4000000Z 2 errors found
+2024-03-01T10:00:03.4000000Z 1 other issues found.
+2024-03-01T10:00:03.4000000Z ------------------------------
+2024-03-01T10:00:03.4000000Z
+2024-03-01T10:00:03.4000000Z Checks took 1.42 seconds and used 120.309MB of memory
+2024-03-01T10:00:03.5000000Z ##[error]Process completed with exit code 2."#;
+
+        assert_eq!(
+            PsalmLogParser::parse(logs),
+            vec![
+                CheckError {
+                    path: "src/Service/Price.php".to_string(),
+                    lines: vec![
+                        "ERROR: InvalidReturnType - src/Service/Price.php:12:16 - The declared return type 'int' for App\\Service\\Price::total is incorrect, got 'string' (see https://psalm.dev/011)".to_string(),
+                        "  public function total(): int".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "src/Command/Import.php".to_string(),
+                    lines: vec![
+                        "ERROR: UndefinedVariable - src/Command/Import.php:48:9 - Cannot find referenced variable $reader (see https://psalm.dev/024)".to_string(),
+                        "        $reader->open($path);".to_string(),
+                    ]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_issue_position() {
+        assert_eq!(
+            issue_position("ERROR: UndefinedVariable - src/Command/Import.php:48:9 - Cannot find referenced variable $reader"),
+            Some((48, 9))
+        );
+        assert_eq!(
+            issue_message("ERROR: UndefinedVariable - src/Command/Import.php:48:9 - Cannot find referenced variable $reader"),
+            Some("UndefinedVariable: Cannot find referenced variable $reader".to_string())
+        );
+    }
+}
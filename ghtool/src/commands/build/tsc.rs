@@ -1,5 +1,6 @@
 use lazy_static::lazy_static;
 use regex::Regex;
+use tracing::debug;
 
 use crate::commands::CheckError;
 
@@ -12,11 +13,31 @@ lazy_static! {
 
     /// Regex to match an error line of the TypeScript compiler (tsc) log
     static ref TSC_ERROR_LINE: Regex = Regex::new(&format!(
-        r"(?i){TIMESTAMP_PATTERN}\s+(?P<error>##\[error\]).*?({ANSI_RESET})?(?P<path>[a-zA-Z0-9._/-]*)\(\d+,\d+\):\serror\sTS\d+",
+        r"(?i){TIMESTAMP_PATTERN}\s+(?P<error>##\[error\]).*?({ANSI_RESET})?(?P<path>[a-zA-Z0-9._/\\:-]*)\(\d+,\d+\):\serror\sTS\d+",
         //                                                ^^^^^^^^^^^^^^^^^^ See test_extract_failing_files_3
 
     ))
     .unwrap();
+
+    /// Regex to match the position of a tsc error
+    /// Example: src/index.ts(3,21): error TS2322: Type 'string' is not assignable to type 'number'.
+    static ref TSC_ERROR_POSITION: Regex =
+        Regex::new(r"\((?P<line>\d+),(?P<col>\d+)\):\serror\s(?P<message>TS\d+.*)").unwrap();
+}
+
+/// Line and column of the error on a tsc error line
+pub fn issue_position(line: &str) -> Option<(u32, u32)> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = TSC_ERROR_POSITION.captures(&line_no_ansi)?;
+    Some((caps["line"].parse().ok()?, caps["col"].parse().ok()?))
+}
+
+/// Message of a tsc error line after its position and severity, e.g. "TS2322: Type 'string' is
+/// not assignable to type 'number'."
+pub fn issue_message(line: &str) -> Option<String> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = TSC_ERROR_POSITION.captures(&line_no_ansi)?;
+    Some(caps["message"].trim().to_string())
 }
 
 #[derive(PartialEq, Debug)]
@@ -32,6 +53,7 @@ pub struct TscLogParser {
     all_errors: Vec<CheckError>,
     error_tag_start_col: usize,
     error_line_count: usize,
+    error_lines_seen: usize,
 }
 
 impl TscLogParser {
@@ -42,6 +64,7 @@ impl TscLogParser {
             all_errors: Vec::new(),
             error_tag_start_col: 0,
             error_line_count: 0,
+            error_lines_seen: 0,
         }
     }
 
@@ -51,7 +74,9 @@ impl TscLogParser {
         match self.state {
             State::LookingForError => {
                 if let Some(caps) = TSC_ERROR_LINE.captures(full_line) {
-                    let path = caps.name("path").unwrap().as_str().to_string();
+                    self.error_lines_seen += 1;
+                    // tsc on Windows separates directories with backslashes
+                    let path = caps.name("path").unwrap().as_str().replace('\\', "/");
                     let without_error_tag = line.strip_prefix("##[error]").unwrap_or(&line);
                     self.error_tag_start_col = caps.name("error").unwrap().start();
                     self.current_error = Some(CheckError {
@@ -108,6 +133,11 @@ impl TscLogParser {
             parser.all_errors.push(current_error);
         }
 
+        debug!(
+            error_lines_seen = parser.error_lines_seen,
+            errors = parser.all_errors.len(),
+            "parsed tsc log"
+        );
         Ok(parser.all_errors)
     }
 }
@@ -118,6 +148,39 @@ mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn test_issue_position() {
+        assert_eq!(
+            issue_position("src/index.ts(3,21): error TS2322: Type 'string' is not assignable to type 'number'."),
+            Some((3, 21))
+        );
+        assert_eq!(
+            issue_position("  Types of property 'a' are incompatible."),
+            None
+        );
+        assert_eq!(
+            issue_message("src/index.ts(3,21): error TS2322: Type 'string' is not assignable to type 'number'."),
+            Some("TS2322: Type 'string' is not assignable to type 'number'.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_failing_files_windows_paths() {
+        let logs = r#"
+2023-06-26T16:57:36.5365262Z ##[error]src\components\Foo.tsx(3,21): error TS2322: Type 'number' is not assignable to type 'string'.
+2023-06-26T16:57:36.5533457Z ##[error]Process completed with exit code 2."#;
+
+        assert_eq!(
+            TscLogParser::parse(logs).unwrap(),
+            vec![CheckError {
+                path: "src/components/Foo.tsx".to_string(),
+                lines: vec![
+                    "src\\components\\Foo.tsx(3,21): error TS2322: Type 'number' is not assignable to type 'string'.".to_string(),
+                ]
+            }]
+        );
+    }
+
     #[test]
     fn test_extract_failing_files_1() {
         let logs = r#"
@@ -0,0 +1,221 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use eyre::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use tracing::{debug, warn};
+
+use super::CheckRunErrors;
+use crate::{setup::get_repo_path, term::bold};
+
+/// Where GitHub looks for CODEOWNERS, in the order it looks
+const CODEOWNERS_PATHS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+const NO_OWNER: &str = "(no owner)";
+
+struct Rule {
+    matcher: GlobSet,
+    owners: Vec<String>,
+}
+
+/// Owners of files by the rules of a CODEOWNERS file. The last matching rule wins, like on
+/// GitHub.
+pub struct CodeOwners {
+    rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    /// Read CODEOWNERS of the checkout, or None if it has none
+    pub fn read(repo_path: &Path) -> Result<Option<CodeOwners>> {
+        for path in CODEOWNERS_PATHS {
+            let path = repo_path.join(path);
+            if path.is_file() {
+                debug!(?path, "reading CODEOWNERS");
+                return Ok(Some(CodeOwners::parse(&fs::read_to_string(path)?)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse(contents: &str) -> CodeOwners {
+        let rules = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?;
+                let owners = parts
+                    .take_while(|part| !part.starts_with('#'))
+                    .map(String::from)
+                    .collect();
+                match pattern_matcher(pattern) {
+                    Some(matcher) => Some(Rule { matcher, owners }),
+                    None => {
+                        warn!(pattern, "invalid CODEOWNERS pattern");
+                        None
+                    }
+                }
+            })
+            .collect();
+        CodeOwners { rules }
+    }
+
+    /// Owners of the path, relative to the repository root. A rule without owners, e.g. to
+    /// leave files of an owned directory unowned, leaves the path without owners.
+    fn owners(&self, path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matcher.is_match(path))
+            .map_or(&[], |rule| rule.owners.as_slice())
+    }
+}
+
+/// Glob of a gitignore style CODEOWNERS pattern. Patterns without a slash other than a trailing
+/// one match at any depth, others from the repository root, and both match everything in a
+/// directory they match.
+fn pattern_matcher(pattern: &str) -> Option<GlobSet> {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    let glob = if anchored {
+        pattern.to_string()
+    } else {
+        format!("**/{pattern}")
+    };
+    let globs = match glob.strip_suffix('/') {
+        Some(directory) => vec![format!("{directory}/**")],
+        None => vec![glob.clone(), format!("{glob}/**")],
+    };
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(
+            GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .ok()?,
+        );
+    }
+    builder.build().ok()
+}
+
+/// Number of files and errors of each owner
+pub type OwnerSummary = BTreeMap<String, (usize, usize)>;
+
+/// Summary of the failures by the CODEOWNERS of the checkout ghtool is run in, or None without one
+pub fn read_owner_summary<'a>(
+    sections: impl IntoIterator<Item = &'a CheckRunErrors>,
+) -> Result<Option<OwnerSummary>> {
+    let Ok(repo_path) = get_repo_path() else {
+        return Ok(None);
+    };
+    let Some(code_owners) = CodeOwners::read(&repo_path)? else {
+        debug!("no CODEOWNERS found");
+        return Ok(None);
+    };
+    Ok(Some(owner_summary(&code_owners, sections)))
+}
+
+/// Files no rule gives owners to are counted under NO_OWNER
+fn owner_summary<'a>(
+    code_owners: &CodeOwners,
+    sections: impl IntoIterator<Item = &'a CheckRunErrors>,
+) -> OwnerSummary {
+    let mut files: BTreeMap<String, BTreeMap<&str, usize>> = BTreeMap::new();
+    for error in sections.into_iter().flat_map(|section| &section.errors) {
+        let owners = code_owners.owners(&error.path);
+        let owners = if owners.is_empty() {
+            vec![NO_OWNER.to_string()]
+        } else {
+            owners.to_vec()
+        };
+        for owner in owners {
+            *files
+                .entry(owner)
+                .or_default()
+                .entry(error.path.as_str())
+                .or_default() += 1;
+        }
+    }
+
+    files
+        .into_iter()
+        .map(|(owner, files)| {
+            let errors = files.values().sum();
+            (owner, (files.len(), errors))
+        })
+        .collect()
+}
+
+/// Print e.g. "@org/web  3 files, 5 errors" for each owner of failing files to stderr
+pub fn print_owner_summary(summary: &OwnerSummary) {
+    let width = summary.keys().map(|owner| owner.len()).max().unwrap_or(0);
+    eprintln!("Failures by owner:");
+    for (owner, (files, errors)) in summary {
+        eprintln!(
+            "  {}{}  {}, {}",
+            bold(owner),
+            " ".repeat(width - owner.len()),
+            super::pluralize(*files, "file"),
+            super::pluralize(*errors, "error")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{commands::CheckError, github::SimpleCheckRun};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_owner_summary() {
+        let code_owners = CodeOwners::parse(
+            r#"
+# Default owners
+*                @org/everyone
+*.ts             @org/web # Inline comment
+/apps/api/       @org/api @alice
+docs/**/*.md     @org/docs
+/apps/api/vendor
+"#,
+        );
+        let error = |path: &str| CheckError {
+            path: path.to_string(),
+            lines: vec![],
+        };
+        let sections = vec![CheckRunErrors {
+            check_run: SimpleCheckRun {
+                id: 1,
+                name: "Build".to_string(),
+                status: crate::github::CheckStatusState::Completed,
+                conclusion: None,
+                url: None,
+                started_at: None,
+                completed_at: None,
+                app: None,
+                is_required: false,
+            },
+            errors: vec![
+                error("src/index.ts"),
+                error("src/index.ts"),
+                error("apps/api/src/server.ts"),
+                error("apps/api/vendor/lib.js"),
+                error("docs/guide/intro.md"),
+                error("Makefile"),
+            ],
+        }];
+
+        assert_eq!(
+            owner_summary(&code_owners, &sections),
+            BTreeMap::from([
+                (NO_OWNER.to_string(), (1, 1)),
+                ("@alice".to_string(), (1, 1)),
+                ("@org/api".to_string(), (1, 1)),
+                ("@org/docs".to_string(), (1, 1)),
+                ("@org/everyone".to_string(), (1, 1)),
+                ("@org/web".to_string(), (1, 2)),
+            ])
+        );
+    }
+}
@@ -1,62 +1,241 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
+    io::{self, IsTerminal, Write},
+    path::Path,
+    process::Stdio,
     sync::Arc,
 };
 
-use eyre::Result;
-use futures::future::try_join_all;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use dialoguer::Select;
+use eyre::{Result, WrapErr};
+use futures::{future::join_all, Future};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use indicatif::HumanDuration;
+use lazy_static::lazy_static;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use tokio::task::JoinHandle;
-use tracing::info;
+use tracing::{debug, info, warn};
 
 use crate::{
-    cli::Cli,
-    commands::{BuildCommand, LintCommand, TestCommand},
+    cache,
+    cli::{Cli, OutputFormat},
+    commands::{
+        annotations::fetch_annotation_errors,
+        base_errors::BaseErrors,
+        blame::{annotate_introduced_lines, Blame},
+        codeowners::{print_owner_summary, read_owner_summary},
+        output_template::{OutputTemplate, TemplateValues},
+        paths::{join_working_dir, normalize_path},
+        sourcemaps::resolve_sourcemaps,
+        test::junit::{fetch_junit_errors, JunitReports},
+        BuildCommand, LintCommand, TestCommand,
+    },
+    error::{GhtoolError, CHECK_ERRORS_EXIT_CODE},
+    gh_config::get_gh_token,
     git::Repository,
     github::{
-        fetch_check_run_logs, wait_for_pr_checks, CheckConclusionState, GithubClient,
-        SimpleCheckRun,
+        fetch_check_run_logs, get_installation_token, wait_for_all_pr_checks, wait_for_pr_checks,
+        AppCredentials, CheckConclusionState, GithubApiError, GithubClient, PullRequestChecks,
+        SimpleCheckRun, SimplePullRequest,
     },
-    repo_config::RepoConfig,
-    setup::get_repo_config,
-    term::{bold, print_all_checks_green, print_check_run_header},
+    log_cache,
+    repo_config::{RepoConfig, SectionConfig, Tools},
+    setup::{get_repo_config, get_repo_path},
+    term::{bold, green, log_line, print_all_checks_green, print_check_run_header, red},
     token_store,
+    trace::trace_command,
+    update_check::print_update_notice,
 };
 
+lazy_static! {
+    /// Regex to match a timestamp and single space after it, if the line goes on
+    static ref TIMESTAMP: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z(?:\s|$)").unwrap();
+}
+
 pub trait ConfigPattern {
     fn job_pattern(&self) -> &Regex;
+
+    /// Directory the tool is run in, which its relative paths are relative to
+    fn working_dir(&self) -> Option<&str>;
+
+    /// Shell command to pipe the results through as JSON before printing
+    fn post_parse_command(&self) -> Option<&str>;
+
+    /// Slug of the app whose check runs are considered, DEFAULT_APP if not set
+    fn app(&self) -> Option<&str>;
+
+    /// Check runs whose app isn't known are considered to be from any app
+    fn matches_app(&self, check_run: &SimpleCheckRun) -> bool {
+        let app = self.app().unwrap_or(DEFAULT_APP);
+        app == ANY_APP || check_run.app.as_deref().is_none_or(|slug| slug == app)
+    }
+
+    fn matches_check_run(&self, check_run: &SimpleCheckRun) -> bool {
+        self.matches_app(check_run) && self.job_pattern().is_match(&check_run.name)
+    }
+}
+
+impl ConfigPattern for SectionConfig {
+    fn job_pattern(&self) -> &Regex {
+        &self.job_pattern
+    }
+
+    fn working_dir(&self) -> Option<&str> {
+        self.working_dir.as_deref()
+    }
+
+    fn post_parse_command(&self) -> Option<&str> {
+        self.post_parse_command.as_deref()
+    }
+
+    fn app(&self) -> Option<&str> {
+        self.app.as_deref()
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Most checks that ghtool can parse are run with GitHub Actions, whose logs can be downloaded
+pub const DEFAULT_APP: &str = "github-actions";
+
+/// App filter that considers check runs of any app
+pub const ANY_APP: &str = "*";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CheckError {
     pub path: String,
     pub lines: Vec<String>,
 }
 
+/// A failed check run and the errors parsed from its logs
+#[derive(Debug, Clone)]
+pub struct CheckRunErrors {
+    pub check_run: SimpleCheckRun,
+    pub errors: Vec<CheckError>,
+}
+
 pub trait Command: Sync + Send {
     fn name(&self) -> &'static str;
     fn config(&self) -> &dyn ConfigPattern;
     fn check_error_plural(&self) -> &'static str;
     fn parse_log(&self, logs: &str) -> Result<Vec<CheckError>>;
+
+    /// Matches the part of a sharded job's name that identifies the shard
+    fn shard_pattern(&self) -> Option<&Regex> {
+        None
+    }
+
+    /// Id of the rule reported on an error line, e.g. no-unused-vars
+    fn issue_rule(&self, _line: &str) -> Option<String> {
+        None
+    }
+
+    /// Line and column in the error's file that an error line reports an issue on, e.g. (3, 21)
+    /// of src/a.ts(3,21): error TS2322
+    fn issue_position(&self, _line: &str) -> Option<(u32, u32)> {
+        None
+    }
+
+    /// Message of an error line, without the position and severity the tool reports it with, e.g.
+    /// "'foo' is defined but never used  no-unused-vars" of
+    /// "4:10  error  'foo' is defined but never used  no-unused-vars"
+    fn issue_message(&self, line: &str) -> String {
+        strip_ansi(line).trim().to_string()
+    }
+
+    fn issue_line_number(&self, line: &str) -> Option<u32> {
+        self.issue_position(line)
+            .map(|(line_number, _)| line_number)
+    }
+
+    /// Name of an artifact with JUnit reports to read errors from instead of logs
+    fn junit_artifact(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the path of an error is a file's, to be joined to the working directory, rather
+    /// than e.g. the name of a test class or task
+    fn is_file_path(&self, _path: &str) -> bool {
+        true
+    }
+
+    /// Count of the errors for the summary after `all`, e.g. "3 files"
+    fn summarize_errors(&self, errors: &[&CheckError]) -> String {
+        let files: HashSet<_> = errors.iter().map(|error| &error.path).collect();
+        pluralize(files.len(), "file")
+    }
+
+    /// Number of failures an error consists of, e.g. failed tests of a test file
+    fn failure_count(&self, _error: &CheckError) -> usize {
+        1
+    }
+
+    /// Names of the failed tests of an error, with the suites they're in separated by " › ", e.g.
+    /// "Suite › fails"
+    fn failed_tests(&self, _error: &CheckError) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Keep only the failed tests of the error whose names match the pattern, returning whether
+    /// any is left. Errors of tools reporting several tests in one keep the lines of those only.
+    fn retain_failed_tests(&self, error: &mut CheckError, pattern: &Regex) -> bool {
+        self.failed_tests(error)
+            .iter()
+            .any(|name| pattern.is_match(name))
+    }
+
+    /// Whether any issue of the error is of error severity rather than e.g. a warning. Tools
+    /// without severities only report errors.
+    fn has_error_severity(&self, _error: &CheckError) -> bool {
+        true
+    }
 }
 
+/// Whether a check run with the conclusion is reported. Check runs requiring action, e.g. waiting
+/// for approval, block merging like failed ones do.
+pub(crate) fn is_reported_conclusion(
+    conclusion: Option<CheckConclusionState>,
+    include_neutral: bool,
+) -> bool {
+    match conclusion {
+        Some(CheckConclusionState::Failure | CheckConclusionState::ActionRequired) => true,
+        Some(CheckConclusionState::Neutral) => include_neutral,
+        _ => false,
+    }
+}
+
+/// Failed check runs matching the command, whether any is in progress and whether none matched.
+/// With only_required, failed check runs not required to merge are left out.
 fn filter_check_runs(
     command: &dyn Command,
     check_runs: &[SimpleCheckRun],
+    include_neutral: bool,
+    only_required: bool,
 ) -> (Vec<SimpleCheckRun>, bool, bool) {
     let mut failed_check_runs = Vec::new();
     let mut any_in_progress = false;
     let mut no_matching_runs = true;
 
     for run in check_runs {
-        if command.config().job_pattern().is_match(&run.name) {
+        if command.config().matches_check_run(run) {
             no_matching_runs = false;
 
             if run.conclusion.is_none() {
                 any_in_progress = true;
             }
 
-            if run.conclusion == Some(CheckConclusionState::Failure) {
+            if only_required {
+                // A required check timing out blocks merging like a failure
+                if run.is_required
+                    && (is_reported_conclusion(run.conclusion, include_neutral)
+                        || run.conclusion == Some(CheckConclusionState::TimedOut))
+                {
+                    failed_check_runs.push(run.clone());
+                }
+            } else if is_reported_conclusion(run.conclusion, include_neutral) {
                 failed_check_runs.push(run.clone());
             }
         }
@@ -65,65 +244,309 @@ fn filter_check_runs(
     (failed_check_runs, any_in_progress, no_matching_runs)
 }
 
+/// Which of several failed jobs to show the errors of. Asked once, so that checks read again with
+/// --retries keep the answer.
+#[derive(Default)]
+struct CheckRunPick {
+    /// Name of the picked job, None for all of them, or unset until asked
+    picked: Option<Option<String>>,
+}
+
+impl CheckRunPick {
+    fn retain_picked(&mut self, cli: &Cli, check_runs: &mut Vec<SimpleCheckRun>) -> Result<()> {
+        if self.picked.is_none() {
+            self.picked = Some(pick_check_run(cli, check_runs)?);
+        }
+        if let Some(Some(name)) = &self.picked {
+            check_runs.retain(|check_run| &check_run.name == name);
+        }
+        Ok(())
+    }
+}
+
+/// Ask which of the failed jobs to show errors of, returning the name of the picked one or None
+/// for all. Output of machine-readable formats is read by tools rather than someone who could be
+/// asked, so it's like --yes.
+fn pick_check_run(cli: &Cli, check_runs: &[SimpleCheckRun]) -> Result<Option<String>> {
+    // A check run matching patterns of more than one command is listed once
+    let mut choices: Vec<&SimpleCheckRun> = Vec::new();
+    for check_run in check_runs.iter() {
+        if !choices.iter().any(|choice| choice.id == check_run.id) {
+            choices.push(check_run);
+        }
+    }
+
+    let is_machine_readable = matches!(
+        cli.format,
+        OutputFormat::Json | OutputFormat::Ndjson | OutputFormat::Locations
+    ) || cli.output_template.is_some();
+    if cli.yes
+        || is_machine_readable
+        || choices.len() < 2
+        || !io::stdout().is_terminal()
+        || !io::stderr().is_terminal()
+    {
+        return Ok(None);
+    }
+
+    let items: Vec<_> = std::iter::once(format!("All {} failed jobs", choices.len()))
+        .chain(choices.iter().map(|check_run| check_run.name.clone()))
+        .collect();
+    let selection = Select::new()
+        .with_prompt("Show errors of")
+        .items(&items)
+        .default(0)
+        .interact()?;
+
+    Ok((selection > 0).then(|| choices[selection - 1].name.clone()))
+}
+
+/// What handle_command prints of the errors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputMode {
+    Errors,
+    Files,
+    /// Only the first line of each error, e.g. without the overload cascades of tsc errors
+    TerseErrors,
+    /// Files with at least one issue of error severity
+    ErrorFiles,
+    /// Files with the number of failures in each
+    FileCounts,
+    GroupByRule,
+    /// Failed tests of each file under the suites they're in
+    GroupBySuite,
+}
+
+impl OutputMode {
+    pub fn from_files_flag(files: bool) -> Self {
+        if files {
+            OutputMode::Files
+        } else {
+            OutputMode::Errors
+        }
+    }
+}
+
 pub async fn handle_command(
     command_type: CommandType,
     cli: &Cli,
-    show_files_only: bool,
-) -> Result<()> {
+    output_mode: OutputMode,
+    test_name: Option<&Regex>,
+) -> Result<CheckOutcome> {
+    if output_mode == OutputMode::GroupByRule && cli.format != OutputFormat::Text {
+        eyre::bail!("--group-by is only supported with --format text");
+    }
+    if output_mode == OutputMode::GroupBySuite && cli.format != OutputFormat::Text {
+        eyre::bail!("--group-tests-by-suite is only supported with --format text");
+    }
+    if output_mode == OutputMode::FileCounts && cli.format != OutputFormat::Text {
+        eyre::bail!("--counts is only supported with --format text");
+    }
+    if output_mode == OutputMode::GroupByRule && cli.merge_base_context {
+        eyre::bail!("--group-by can't be used with --merge-base-context");
+    }
+    let output_template = parse_output_template(cli, output_mode)?;
+
     let (repo_config, repo, branch) = get_repo_config(cli)?;
     let command = command_from_type(command_type, &repo_config)?;
-    let token = get_token(&repo.hostname)?;
-    let client = GithubClient::new(&token)?;
-    let pull_request = client
-        .get_pr_for_branch_memoized(&repo.owner, &repo.name, &branch)
-        .await?
-        .ok_or_else(|| eyre::eyre!("No pull request found for branch {}", bold(&branch)))?;
+    let file_filter = build_file_filter(&cli.file_filter)?;
+    let token = get_token(cli, &repo.hostname).await?;
+    let client = GithubClient::new(&token, &repo.hostname)?;
+    check_for_updates(cli, &repo_config, &repo, &client).await;
 
     let command_clone = command.clone();
-    let match_checkrun_name =
-        move |name: &str| -> bool { command_clone.config().job_pattern().is_match(name) };
+    let match_check_run =
+        move |check_run: &SimpleCheckRun| command_clone.config().matches_check_run(check_run);
 
-    let all_check_runs =
-        wait_for_pr_checks(&client, pull_request.id, Some(&match_checkrun_name)).await?;
+    let mut retries_left = cli.retries;
+    let mut check_run_pick = CheckRunPick::default();
+    let (pr_checks, failed_check_runs, processed) = loop {
+        let pr_checks = get_checks(
+            cli,
+            cli.default_branch,
+            &client,
+            &repo,
+            &branch,
+            |pull_request| {
+                let (client, command, match_check_run) = (&client, &command, &match_check_run);
+                async move {
+                    if cli.show_checks {
+                        show_checks(client, &pull_request.id, &[command.as_ref()]).await?;
+                    }
 
-    let (failed_check_runs, _, no_matching_runs) = filter_check_runs(&*command, &all_check_runs);
-    info!(?failed_check_runs, "got failed check runs");
+                    if cli.last {
+                        return client
+                            .get_pr_last_completed_checks(&pull_request.id, Some(match_check_run))
+                            .await;
+                    }
 
-    if no_matching_runs {
-        eprintln!(
-            "No {} jobs found matching the pattern /{}/",
-            command.name(),
-            command.config().job_pattern()
+                    wait_for_pr_checks(
+                        client,
+                        pull_request.id,
+                        Some(match_check_run),
+                        cli.expect_sha.as_deref(),
+                    )
+                    .await
+                }
+            },
+        )
+        .await?;
+
+        let (failed_check_runs, _, no_matching_runs) = filter_check_runs(
+            &*command,
+            &pr_checks.check_runs,
+            cli.include_neutral,
+            cli.only_required_failing,
         );
-        return Ok(());
-    }
+        info!(?failed_check_runs, "got failed check runs");
 
-    if failed_check_runs.is_empty() {
-        print_all_checks_green();
-        return Ok(());
-    }
+        if no_matching_runs {
+            eprintln!(
+                "No {} jobs found matching the pattern /{}/",
+                command.name(),
+                command.config().job_pattern()
+            );
+            write_no_errors_json(
+                cli,
+                output_mode,
+                &pr_checks.head_sha,
+                JsonOutcome::NoMatchingChecks,
+            )?;
+            return Ok(CheckOutcome::Passed);
+        }
 
-    let check_run_errors = process_failed_check_runs(
-        &client,
-        &repo,
-        CommandMode::Single(command.clone()),
-        &failed_check_runs,
-    )
-    .await?;
+        if failed_check_runs.is_empty() {
+            print_success(cli, &repo_config);
+            write_no_errors_json(cli, output_mode, &pr_checks.head_sha, JsonOutcome::Passed)?;
+            return Ok(CheckOutcome::Passed);
+        }
+
+        let mut failed_check_runs = failed_check_runs;
+        check_run_pick.retain_picked(cli, &mut failed_check_runs)?;
+
+        let processed = process_failed_check_runs(
+            &client,
+            &repo,
+            CommandMode::Single(command.clone()),
+            &failed_check_runs,
+            LogOptions::from_cli(cli),
+        )
+        .await;
+        match processed {
+            Err(e) if retries_left > 0 && is_logs_not_found(&e) => {
+                retries_left -= 1;
+                wait_before_checks_retry(&e).await;
+            }
+            result => break (pr_checks, failed_check_runs, result?),
+        }
+    };
+
+    let ProcessedCheckRuns {
+        mut check_errors,
+        unparsed_logs,
+        skipped,
+    } = processed;
+    let mut failed_check_runs = failed_check_runs;
+    failed_check_runs.retain(|check_run| !skipped.contains(&check_run.id));
+    print_skipped_check_runs(skipped.len());
+
+    let mut sections = to_sections(&failed_check_runs, &mut check_errors);
+    let unparsed_check_runs = unparsed_check_runs(&sections);
+    let hidden = if cli.vs_base {
+        hide_base_errors(cli, &client, &repo, &branch, &command, &mut sections).await?
+    } else {
+        0
+    };
+    if let Some(shard_pattern) = command.shard_pattern() {
+        sections = merge_shards(sections, shard_pattern);
+    }
+    if cli.resolve_sourcemaps {
+        resolve_section_sourcemaps(&mut sections)?;
+    }
+    if let Some(post_parse_command) = command.config().post_parse_command() {
+        sections = run_post_parse_command(post_parse_command, sections, &pr_checks.head_sha)?;
+    }
+    if let Some(file_filter) = &file_filter {
+        apply_file_filter(&mut sections, file_filter);
+    }
+    if let Some(test_name) = test_name {
+        retain_test_names(&*command, &mut sections, test_name);
+    }
+    if let Some(blame) = merge_base_blame(cli, &pr_checks.head_sha)? {
+        annotate_section_blame(command.as_ref(), &mut sections, &blame);
+    }
+    if output_mode == OutputMode::ErrorFiles {
+        retain_error_severity(&*command, &mut sections);
+    }
+    if output_mode == OutputMode::TerseErrors {
+        keep_first_lines(&mut sections);
+    }
 
-    let all_checks_errors = check_run_errors.into_values().collect::<Vec<_>>();
-    if all_checks_errors.iter().all(|s| s.is_empty()) {
+    if sections.iter().all(|s| s.errors.is_empty()) {
+        // Everything that failed fails on the base branch too
+        if hidden > 0 && unparsed_check_runs.is_empty() {
+            write_no_errors_json(cli, output_mode, &pr_checks.head_sha, JsonOutcome::Passed)?;
+            return Ok(CheckOutcome::Passed);
+        }
         eprintln!("No {} found in log output", command.check_error_plural());
-        return Ok(());
+        print_unparsed_check_runs(&unparsed_check_runs, &unparsed_logs, !cli.no_url_fallback);
+        write_no_errors_json(cli, output_mode, &pr_checks.head_sha, JsonOutcome::Failed)?;
+        return Ok(CheckOutcome::Failed);
+    }
+
+    if cli.reverse {
+        sections.reverse();
     }
+    let owner_summary = if cli.team_summary {
+        read_owner_summary(&sections)?
+    } else {
+        None
+    };
 
-    if show_files_only {
-        print_errored_files(all_checks_errors);
+    if let Some(output_template) = &output_template {
+        print_templated_issues(&templated_issues(&*command, &sections, output_template));
     } else {
-        print_errors(&failed_check_runs, all_checks_errors);
+        match (cli.format, output_mode) {
+            (_, OutputMode::GroupByRule) => print_rule_groups(&group_by_rule(&*command, &sections)),
+            (_, OutputMode::GroupBySuite) => {
+                print_suite_groups(&group_by_suite(&*command, &sections))
+            }
+            (_, OutputMode::FileCounts) => {
+                print_file_failure_counts(&file_failure_counts(&*command, &sections))
+            }
+            (
+                OutputFormat::Text | OutputFormat::Locations | OutputFormat::Markdown,
+                OutputMode::Files | OutputMode::ErrorFiles,
+            ) => print_errored_files(&sections),
+            (OutputFormat::Locations, OutputMode::Errors | OutputMode::TerseErrors) => {
+                print_error_locations(&error_locations(&*command, &sections))
+            }
+            (OutputFormat::Text, OutputMode::Errors | OutputMode::TerseErrors) => {
+                print_errors(&sections, &pr_checks.head_sha)
+            }
+            (OutputFormat::Markdown, OutputMode::Errors | OutputMode::TerseErrors) => {
+                print!("{}", markdown_errors(&sections))
+            }
+            (OutputFormat::Json, OutputMode::Files | OutputMode::ErrorFiles) => {
+                write_errored_files_json(&sections, JsonOutcome::Failed)?
+            }
+            (OutputFormat::Json, OutputMode::Errors | OutputMode::TerseErrors) => {
+                write_errors_json(&sections, &pr_checks.head_sha, JsonOutcome::Failed)?
+            }
+            (OutputFormat::Ndjson, OutputMode::Files | OutputMode::ErrorFiles) => {
+                write_errored_files_ndjson(&sections)?
+            }
+            (OutputFormat::Ndjson, OutputMode::Errors | OutputMode::TerseErrors) => {
+                write_errors_ndjson(&sections)?
+            }
+        }
     }
 
-    Ok(())
+    print_unparsed_check_runs(&unparsed_check_runs, &unparsed_logs, !cli.no_url_fallback);
+    if let Some(owner_summary) = &owner_summary {
+        print_owner_summary(owner_summary);
+    }
+    Ok(CheckOutcome::Failed)
 }
 
 #[derive(Eq, Hash, PartialEq, Clone, Copy, Debug)]
@@ -133,51 +556,185 @@ pub enum CommandType {
     Build,
 }
 
-pub async fn handle_all_command(cli: &Cli) -> Result<()> {
+pub async fn handle_all_command(cli: &Cli) -> Result<CheckOutcome> {
+    handle_all(cli, cli.default_branch, OutputMode::Errors).await
+}
+
+/// Read all of test, lint and build of the latest commit of the repository's default branch, to
+/// tell if it's broken
+pub async fn handle_main_command(cli: &Cli, output_mode: OutputMode) -> Result<CheckOutcome> {
+    if cli.last
+        || cli.expect_sha.is_some()
+        || cli.show_checks
+        || cli.run.is_some()
+        || cli.only_required_failing
+    {
+        eyre::bail!(
+            "--last, --expect-sha, --show-checks, --run and --only-required-failing can't be used \
+             with ght main"
+        );
+    }
+    handle_all(cli, true, output_mode).await
+}
+
+async fn handle_all(
+    cli: &Cli,
+    default_branch: bool,
+    output_mode: OutputMode,
+) -> Result<CheckOutcome> {
+    if cli.vs_base {
+        eyre::bail!("--vs-base is only supported with ght test, lint and build");
+    }
     let (repo_config, repo, branch) = get_repo_config(cli)?;
-    let token = get_token(&repo.hostname)?;
-    let client = GithubClient::new(&token)?;
-    let pull_request = client
-        .get_pr_for_branch_memoized(&repo.owner, &repo.name, &branch)
-        .await?
-        .ok_or_else(|| eyre::eyre!("No pull request found for branch {}", bold(&branch)))?;
+    let token = get_token(cli, &repo.hostname).await?;
+    let client = GithubClient::new(&token, &repo.hostname)?;
+    check_for_updates(cli, &repo_config, &repo, &client).await;
 
-    let all_check_runs = wait_for_pr_checks(&client, pull_request.id, None).await?;
-    let mut all_failed_check_runs = Vec::new();
-    let mut check_run_command_map: HashMap<CheckRunId, CommandType> = HashMap::new();
-    let mut command_check_run_map: HashMap<CommandType, Vec<CheckRunId>> = HashMap::new();
+    let context = CheckContext {
+        repo_config: &repo_config,
+        repo: &repo,
+        branch: &branch,
+        client: &client,
+    };
+    print_all_errors(cli, default_branch, output_mode, &context, None).await
+}
 
-    let command_types = [CommandType::Test, CommandType::Build, CommandType::Lint];
-    let commands: Result<HashMap<CommandType, Arc<dyn Command + Send + Sync>>> = command_types
-        .iter()
-        .map(|&command_type| Ok((command_type, command_from_type(command_type, &repo_config)?)))
-        .collect();
-    let commands = commands?;
-
-    for (command_type, command) in &commands {
-        add_command_info(
-            command.as_ref(),
-            *command_type,
-            &all_check_runs,
-            &mut all_failed_check_runs,
-            &mut check_run_command_map,
-            &mut command_check_run_map,
-        );
+/// Config, repository and branch whose checks are read, and the client they're read with
+#[derive(Clone, Copy)]
+struct CheckContext<'a> {
+    repo_config: &'a RepoConfig,
+    repo: &'a Repository,
+    branch: &'a str,
+    client: &'a GithubClient,
+}
+
+/// Print the errors of the failed test, lint and build checks. Checks that were already read,
+/// e.g. by ght check --watch-until-green, are used instead of reading them again, unless logs are
+/// retried.
+async fn print_all_errors(
+    cli: &Cli,
+    default_branch: bool,
+    output_mode: OutputMode,
+    context: &CheckContext<'_>,
+    mut read_checks: Option<PullRequestChecks>,
+) -> Result<CheckOutcome> {
+    let CheckContext {
+        repo_config,
+        repo,
+        branch,
+        client,
+    } = *context;
+    let output_template = parse_output_template(cli, output_mode)?;
+    let file_filter = build_file_filter(&cli.file_filter)?;
+
+    let commands: HashMap<CommandType, Arc<dyn Command + Send + Sync>> =
+        configured_commands(repo_config).into_iter().collect();
+    if commands.is_empty() {
+        return Err(GhtoolError::MissingConfigSection("test, build or lint").into());
     }
+    let command_types: Vec<_> = [CommandType::Test, CommandType::Build, CommandType::Lint]
+        .into_iter()
+        .filter(|command_type| commands.contains_key(command_type))
+        .collect();
 
-    let mut all_check_errors = process_failed_check_runs(
-        &client,
-        &repo,
-        CommandMode::Multiple {
-            command_map: commands,
-            check_run_command_map,
-        },
-        &all_failed_check_runs,
-    )
-    .await?;
+    let mut retries_left = cli.retries;
+    let mut check_run_pick = CheckRunPick::default();
+    let (pr_checks, mut command_check_run_map, processed) = loop {
+        let pr_checks = match read_checks.take() {
+            Some(pr_checks) => pr_checks,
+            None => {
+                get_checks(cli, default_branch, client, repo, branch, |pull_request| {
+                    let (commands, command_types) = (&commands, &command_types);
+                    async move {
+                        if cli.show_checks {
+                            let commands: Vec<_> = command_types
+                                .iter()
+                                .map(|command_type| commands[command_type].as_ref() as &dyn Command)
+                                .collect();
+                            show_checks(client, &pull_request.id, &commands).await?;
+                        }
+
+                        if cli.last {
+                            let match_any_command = |check_run: &SimpleCheckRun| {
+                                commands
+                                    .values()
+                                    .any(|command| command.config().matches_check_run(check_run))
+                            };
+                            return client
+                                .get_pr_last_completed_checks(
+                                    &pull_request.id,
+                                    Some(&match_any_command),
+                                )
+                                .await;
+                        }
+
+                        wait_for_pr_checks(client, pull_request.id, None, cli.expect_sha.as_deref())
+                            .await
+                    }
+                })
+                .await?
+            }
+        };
+        let all_check_runs = &pr_checks.check_runs;
+        let mut all_failed_check_runs = Vec::new();
+        let mut check_run_command_map: HashMap<CheckRunId, CommandType> = HashMap::new();
+        let mut command_check_run_map: HashMap<CommandType, Vec<CheckRunId>> = HashMap::new();
+
+        for (command_type, command) in &commands {
+            add_command_info(
+                command.as_ref(),
+                *command_type,
+                all_check_runs,
+                cli,
+                &mut all_failed_check_runs,
+                &mut check_run_command_map,
+                &mut command_check_run_map,
+            );
+        }
+
+        check_run_pick.retain_picked(cli, &mut all_failed_check_runs)?;
+        for check_run_ids in command_check_run_map.values_mut() {
+            check_run_ids.retain(|id| all_failed_check_runs.iter().any(|run| run.id == *id));
+        }
+
+        let processed = process_failed_check_runs(
+            client,
+            repo,
+            CommandMode::Multiple {
+                command_map: commands.clone(),
+                check_run_command_map,
+            },
+            &all_failed_check_runs,
+            LogOptions::from_cli(cli),
+        )
+        .await;
+        match processed {
+            Err(e) if retries_left > 0 && is_logs_not_found(&e) => {
+                retries_left -= 1;
+                wait_before_checks_retry(&e).await;
+            }
+            result => break (pr_checks, command_check_run_map, result?),
+        }
+    };
+
+    let all_check_runs = &pr_checks.check_runs;
+    let ProcessedCheckRuns {
+        check_errors: mut all_check_errors,
+        unparsed_logs,
+        skipped,
+    } = processed;
+    for check_run_ids in command_check_run_map.values_mut() {
+        check_run_ids.retain(|id| !skipped.contains(id));
+    }
+    print_skipped_check_runs(skipped.len());
 
-    let mut all_green = true;
-    for command_type in &[CommandType::Test, CommandType::Build, CommandType::Lint] {
+    let mut command_sections = Vec::new();
+    let mut locations = BTreeSet::new();
+    let mut templated = Vec::new();
+    let mut all_unparsed_check_runs = Vec::new();
+    let mut summary = Vec::new();
+    let blame = merge_base_blame(cli, &pr_checks.head_sha)?;
+    for command_type in &command_types {
         let check_run_ids = command_check_run_map
             .remove(command_type)
             .unwrap_or_default();
@@ -186,92 +743,1433 @@ pub async fn handle_all_command(cli: &Cli) -> Result<()> {
             .filter_map(|&id| all_check_runs.iter().find(|&run| run.id == id).cloned())
             .collect();
 
-        let mut check_errors = Vec::new();
-        for check_run_id in &check_run_ids {
-            if let Some(errors) = all_check_errors.remove(check_run_id) {
-                check_errors.push(errors);
-            }
+        let mut sections = to_sections(&check_runs, &mut all_check_errors);
+        all_unparsed_check_runs.extend(unparsed_check_runs(&sections));
+        if let Some(shard_pattern) = commands[command_type].shard_pattern() {
+            sections = merge_shards(sections, shard_pattern);
+        }
+        if cli.resolve_sourcemaps {
+            resolve_section_sourcemaps(&mut sections)?;
+        }
+        if let Some(post_parse_command) = commands[command_type].config().post_parse_command() {
+            sections = run_post_parse_command(post_parse_command, sections, &pr_checks.head_sha)?;
+        }
+        if let Some(file_filter) = &file_filter {
+            apply_file_filter(&mut sections, file_filter);
+        }
+        if let Some(blame) = &blame {
+            annotate_section_blame(commands[command_type].as_ref(), &mut sections, blame);
         }
 
-        if check_errors.iter().all(|s| s.is_empty()) {
+        summary.push((
+            commands[command_type].name(),
+            command_summary(commands[command_type].as_ref(), &check_runs, &sections),
+        ));
+
+        if sections.iter().all(|s| s.errors.is_empty()) {
             continue;
         }
 
-        all_green = false;
-        print_errors(&check_runs, check_errors);
+        if cli.format == OutputFormat::Locations {
+            locations.extend(error_locations(commands[command_type].as_ref(), &sections));
+        }
+        if let Some(output_template) = &output_template {
+            let command = commands[command_type].as_ref();
+            templated.extend(templated_issues(command, &sections, output_template));
+        }
+        command_sections.push((commands[command_type].name(), sections));
     }
 
-    if all_green {
-        print_all_checks_green();
+    if command_sections.is_empty() {
+        if all_unparsed_check_runs.is_empty() {
+            print_success(cli, repo_config);
+            write_no_errors_json(cli, output_mode, &pr_checks.head_sha, JsonOutcome::Passed)?;
+            return Ok(CheckOutcome::Passed);
+        }
+        eprintln!("No errors found in log output");
+        print_unparsed_check_runs(
+            &all_unparsed_check_runs,
+            &unparsed_logs,
+            !cli.no_url_fallback,
+        );
+        print_summary(&summary);
+        write_no_errors_json(cli, output_mode, &pr_checks.head_sha, JsonOutcome::Failed)?;
+        return Ok(CheckOutcome::Failed);
     }
 
-    Ok(())
+    if cli.reverse {
+        command_sections.reverse();
+        command_sections
+            .iter_mut()
+            .for_each(|(_, sections)| sections.reverse());
+    }
+    let owner_summary = if cli.team_summary {
+        read_owner_summary(command_sections.iter().flat_map(|(_, sections)| sections))?
+    } else {
+        None
+    };
+
+    if output_mode == OutputMode::Files {
+        let sections: Vec<_> = command_sections
+            .into_iter()
+            .flat_map(|(_, sections)| sections)
+            .collect();
+        match cli.format {
+            OutputFormat::Text | OutputFormat::Locations | OutputFormat::Markdown => {
+                print_errored_files(&sections)
+            }
+            OutputFormat::Json => write_errored_files_json(&sections, JsonOutcome::Failed)?,
+            OutputFormat::Ndjson => write_errored_files_ndjson(&sections)?,
+        }
+    } else if output_template.is_some() {
+        print_templated_issues(&templated);
+    } else {
+        match cli.format {
+            OutputFormat::Text => command_sections
+                .iter()
+                .for_each(|(_, sections)| print_errors(sections, &pr_checks.head_sha)),
+            OutputFormat::Json => {
+                let sections: Vec<_> = command_sections
+                    .into_iter()
+                    .flat_map(|(_, sections)| sections)
+                    .collect();
+                write_errors_json(&sections, &pr_checks.head_sha, JsonOutcome::Failed)?;
+            }
+            OutputFormat::Ndjson => {
+                for (_, sections) in &command_sections {
+                    write_errors_ndjson(sections)?;
+                }
+            }
+            OutputFormat::Locations => print_error_locations(&locations),
+            OutputFormat::Markdown => {
+                for (name, sections) in &command_sections {
+                    println!("## {name}\n");
+                    print!("{}", markdown_errors(sections));
+                }
+            }
+        }
+    }
+
+    print_unparsed_check_runs(
+        &all_unparsed_check_runs,
+        &unparsed_logs,
+        !cli.no_url_fallback,
+    );
+    if let Some(owner_summary) = &owner_summary {
+        print_owner_summary(owner_summary);
+    }
+    print_summary(&summary);
+    Ok(CheckOutcome::Failed)
 }
 
-fn command_from_type(
-    command_type: CommandType,
-    repo_config: &RepoConfig,
-) -> Result<Arc<dyn Command + Send + Sync>> {
-    let command: Box<dyn Command + Send + Sync> = match command_type {
-        CommandType::Test => Box::new(TestCommand::from_repo_config(repo_config)?),
-        CommandType::Build => Box::new(BuildCommand::from_repo_config(repo_config)?),
-        CommandType::Lint => Box::new(LintCommand::from_repo_config(repo_config)?),
+fn print_skipped_check_runs(count: usize) {
+    if count > 0 {
+        eprintln!(
+            "Stopped at the first failed check with errors; {} not read",
+            pluralize(count, "more failed check")
+        );
+    }
+}
+
+/// Remove the errors that the latest commit of the pull request's base branch has too, returning
+/// how many were removed. Errors of the base branch are read like the pull request's, and cached
+/// by commit once its checks have completed.
+async fn hide_base_errors(
+    cli: &Cli,
+    client: &GithubClient,
+    repo: &Repository,
+    branch: &str,
+    command: &Arc<dyn Command + Send + Sync>,
+    sections: &mut [CheckRunErrors],
+) -> Result<usize> {
+    let base_branch = client
+        .get_pr_for_branch_memoized(repo, branch)
+        .await?
+        .and_then(|pull_request| pull_request.base_ref_name);
+    let base_checks = match &base_branch {
+        Some(base_branch) => {
+            client
+                .get_branch_checks(&repo.owner, &repo.name, base_branch)
+                .await?
+        }
+        // The branch has no pull request to be compared to the base of, e.g. with --default-branch
+        None => {
+            client
+                .get_default_branch_checks(&repo.owner, &repo.name)
+                .await?
+        }
     };
-    Ok(Arc::from(command))
+
+    let key = base_errors_cache_key(repo, &base_checks.head_sha, command.name());
+    let base_errors: Vec<CheckError> = match cache::get(&key)? {
+        Some(base_errors) => base_errors,
+        None => {
+            let (failed_check_runs, pending, _) = filter_check_runs(
+                command.as_ref(),
+                &base_checks.check_runs,
+                cli.include_neutral,
+                false,
+            );
+            let processed = process_failed_check_runs(
+                client,
+                repo,
+                CommandMode::Single(command.clone()),
+                &failed_check_runs,
+                LogOptions {
+                    tail: None,
+                    save_logs: None,
+                    fail_fast: false,
+                    ..LogOptions::from_cli(cli)
+                },
+            )
+            .await?;
+            let base_errors: Vec<_> = processed.check_errors.into_values().flatten().collect();
+            if !pending {
+                cache::put(&key, &base_errors)?;
+            }
+            base_errors
+        }
+    };
+
+    let hidden =
+        BaseErrors::new(command.as_ref(), &base_errors).subtract(command.as_ref(), sections);
+    if hidden > 0 {
+        eprintln!(
+            "Hid {} that {} also on {} ({})",
+            pluralize(hidden, "error"),
+            if hidden == 1 { "is" } else { "are" },
+            base_branch.as_deref().unwrap_or("the default branch"),
+            &base_checks.head_sha[..base_checks.head_sha.len().min(7)]
+        );
+    }
+    Ok(hidden)
 }
 
-fn print_errored_files(all_checks_errors: Vec<Vec<CheckError>>) {
-    let files: HashSet<String> = all_checks_errors
-        .into_iter()
-        .flat_map(|errors| errors.into_iter().map(|error| error.path))
-        .collect();
+fn base_errors_cache_key(repo: &Repository, sha: &str, command_name: &str) -> String {
+    format!(
+        "base_errors_{}/{}/{}_{}_{}",
+        repo.hostname, repo.owner, repo.name, sha, command_name
+    )
+}
 
-    for file in files {
-        println!("{}", file);
+/// Status of a command in the summary after `all`: ✓ when none of its checks failed, otherwise
+/// the count of errors
+fn command_summary(
+    command: &dyn Command,
+    failed_check_runs: &[SimpleCheckRun],
+    sections: &[CheckRunErrors],
+) -> String {
+    if failed_check_runs.is_empty() {
+        return green("✓");
+    }
+    let errors: Vec<_> = sections
+        .iter()
+        .flat_map(|section| &section.errors)
+        .collect();
+    if errors.is_empty() {
+        return red("failed");
     }
+    red(&command.summarize_errors(&errors))
 }
 
-fn print_errors(failed_check_runs: &[SimpleCheckRun], all_checks_errors: Vec<Vec<CheckError>>) {
-    failed_check_runs
+/// Print e.g. "test: 3 files · build: ✓ · lint: 12 issues" to stderr, so that the bottom line is
+/// seen after long output
+fn print_summary(summary: &[(&str, String)]) {
+    let parts: Vec<_> = summary
         .iter()
-        .zip(all_checks_errors)
-        .for_each(|(check_run, check_errors)| {
-            print_check_run_header(check_run);
-
-            check_errors
-                .into_iter()
-                .flat_map(|error| error.lines)
-                .for_each(|line| println!("{}", line));
-        });
+        .map(|(name, status)| format!("{}: {}", bold(name), status))
+        .collect();
+    eprintln!("{}", parts.join(" · "));
 }
 
-type CheckRunId = u64;
+pub fn pluralize(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
 
-enum CommandMode {
-    Single(Arc<dyn Command + Send + Sync>),
-    Multiple {
-        // Used to provide command's parse log function
-        command_map: HashMap<CommandType, Arc<dyn Command + Send + Sync>>,
-        // Used to determine how check run's logs should be parsed
-        check_run_command_map: HashMap<CheckRunId, CommandType>,
-    },
+/// Errors of the log parsed with each of the tools, from the one that finds the most of them, or the
+/// first one when none finds more than the others
+pub fn parse_with_most_errors<T: std::fmt::Debug>(
+    tools: &Tools<T>,
+    parse: impl Fn(&T) -> Result<Vec<CheckError>>,
+) -> Result<Vec<CheckError>> {
+    let mut selected: Option<(&T, Vec<CheckError>)> = None;
+    for tool in tools.iter() {
+        let errors = parse(tool)?;
+        if selected
+            .as_ref()
+            .is_none_or(|(_, selected_errors)| errors.len() > selected_errors.len())
+        {
+            selected = Some((tool, errors));
+        }
+    }
+
+    let (tool, errors) = selected.expect("tools are never empty");
+    if tools.iter().len() > 1 {
+        debug!(?tool, errors = errors.len(), "selected parser");
+    }
+    Ok(errors)
 }
 
-/// Get logs for each failed check run, and parse them into a map of command type to check errors
-async fn process_failed_check_runs(
+/// Exit code of ght check when no check has failed but some are still pending, same as gh's
+pub(crate) const CHECKS_PENDING_EXIT_CODE: i32 = 8;
+
+/// How long to wait before reading checks again with --retries
+const CHECKS_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Outcome of checks, of a single check run or of all the checks a command read. Commands return
+/// it so that the exit code tells scripts e.g. failed checks from all of them passing.
+#[derive(Eq, Hash, PartialEq, Clone, Copy, Debug)]
+pub enum CheckOutcome {
+    Passed,
+    Failed,
+    Pending,
+}
+
+impl CheckOutcome {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            CheckOutcome::Passed => 0,
+            CheckOutcome::Failed => CHECK_ERRORS_EXIT_CODE,
+            CheckOutcome::Pending => CHECKS_PENDING_EXIT_CODE,
+        }
+    }
+}
+
+pub(crate) fn check_outcome(check_run: &SimpleCheckRun) -> CheckOutcome {
+    match check_run.conclusion {
+        None => CheckOutcome::Pending,
+        Some(
+            CheckConclusionState::Success
+            | CheckConclusionState::Neutral
+            | CheckConclusionState::Skipped,
+        ) => CheckOutcome::Passed,
+        Some(_) => CheckOutcome::Failed,
+    }
+}
+
+/// Print the outcome of each check run of the pull request and return their overall outcome.
+/// With watch_until_green, wait for all of them to complete first, and print the errors of the
+/// failed ones.
+pub async fn handle_check_command(cli: &Cli, watch_until_green: bool) -> Result<CheckOutcome> {
+    if cli.vs_base {
+        eyre::bail!("--vs-base is only supported with ght test, lint and build");
+    }
+    let (repo_config, repo, branch) = get_repo_config(cli)?;
+    let token = get_token(cli, &repo.hostname).await?;
+    let client = GithubClient::new(&token, &repo.hostname)?;
+    check_for_updates(cli, &repo_config, &repo, &client).await;
+
+    let pr_checks = get_checks(
+        cli,
+        cli.default_branch,
+        &client,
+        &repo,
+        &branch,
+        |pull_request| {
+            let client = &client;
+            async move {
+                if cli.last {
+                    client
+                        .get_pr_last_completed_checks(&pull_request.id, None)
+                        .await
+                } else if watch_until_green {
+                    wait_for_all_pr_checks(client, pull_request.id, None, cli.expect_sha.as_deref())
+                        .await
+                } else {
+                    client.get_pr_status_checks(&pull_request.id, true).await
+                }
+            }
+        },
+    )
+    .await?;
+    if pr_checks.check_runs.is_empty() {
+        return Err(GhtoolError::NoChecks.into());
+    }
+    let mut pr_checks = pr_checks;
+    if cli.only_required_failing {
+        pr_checks
+            .check_runs
+            .retain(|check_run| check_run.is_required);
+    }
+
+    let max_name_length = pr_checks
+        .check_runs
+        .iter()
+        .map(|check_run| check_run.name.len())
+        .max()
+        .unwrap_or(0);
+    let mut counts: HashMap<CheckOutcome, usize> = HashMap::new();
+
+    for check_run in &pr_checks.check_runs {
+        let outcome = check_outcome(check_run);
+        *counts.entry(outcome).or_default() += 1;
+        let symbol = match outcome {
+            CheckOutcome::Passed => green("✓"),
+            CheckOutcome::Failed => red("X"),
+            CheckOutcome::Pending => "-".to_string(),
+        };
+        // Conclusion is only set once the check run has completed
+        let state = check_run
+            .conclusion
+            .map_or_else(|| format!("{:?}", check_run.status), |c| format!("{:?}", c));
+        println!(
+            "{} {:<name_width$}  {}",
+            symbol,
+            check_run.name,
+            state,
+            name_width = max_name_length
+        );
+    }
+
+    let count = |outcome| counts.get(&outcome).copied().unwrap_or(0);
+    let (failed, pending) = (count(CheckOutcome::Failed), count(CheckOutcome::Pending));
+    eprintln!(
+        "\n{} failed, {} passed, {} pending",
+        failed,
+        count(CheckOutcome::Passed),
+        pending
+    );
+
+    if failed > 0 {
+        if watch_until_green && failed_checks_have_commands(&repo_config, &pr_checks.check_runs) {
+            eprintln!();
+            let context = CheckContext {
+                repo_config: &repo_config,
+                repo: &repo,
+                branch: &branch,
+                client: &client,
+            };
+            print_all_errors(
+                cli,
+                cli.default_branch,
+                OutputMode::Errors,
+                &context,
+                Some(pr_checks),
+            )
+            .await?;
+        }
+        return Ok(CheckOutcome::Failed);
+    }
+    if pending > 0 {
+        return Ok(CheckOutcome::Pending);
+    }
+    Ok(CheckOutcome::Passed)
+}
+
+/// Whether any of the failed check runs is one of test, lint or build, whose errors can be read
+fn failed_checks_have_commands(repo_config: &RepoConfig, check_runs: &[SimpleCheckRun]) -> bool {
+    let commands = configured_commands(repo_config);
+    check_runs.iter().any(|check_run| {
+        check_outcome(check_run) == CheckOutcome::Failed
+            && commands
+                .iter()
+                .any(|(_, command)| command.config().matches_check_run(check_run))
+    })
+}
+
+async fn get_pull_request(
     client: &GithubClient,
     repo: &Repository,
-    command_mode: CommandMode,
-    all_failed_check_runs: &[SimpleCheckRun],
-) -> Result<HashMap<CheckRunId, Vec<CheckError>>> {
-    let log_map = fetch_check_run_logs(client, repo, all_failed_check_runs).await?;
-    #[allow(clippy::type_complexity)]
-    let mut parse_futures: Vec<JoinHandle<Result<(CheckRunId, Vec<CheckError>)>>> = Vec::new();
+    branch: &str,
+    skip_drafts: bool,
+) -> Result<SimplePullRequest> {
+    let pull_request = client
+        .get_pr_for_branch_memoized(repo, branch)
+        .await?
+        .ok_or_else(|| GhtoolError::NoPullRequest {
+            branch: branch.to_string(),
+        })?;
 
-    for (check_run_id, log_bytes) in log_map.iter() {
-        let check_run_id = *check_run_id;
-        let log_bytes = log_bytes.clone();
-        let command = match &command_mode {
+    if pull_request.is_draft {
+        if skip_drafts {
+            eyre::bail!(
+                "Pull request for branch {} is a draft, skipping with --skip-drafts",
+                bold(branch)
+            );
+        }
+        eprintln!("Note: pull request for branch {} is a draft", bold(branch));
+    }
+    Ok(pull_request)
+}
+
+fn print_success(cli: &Cli, repo_config: &RepoConfig) {
+    if !cli.quiet {
+        print_all_checks_green(repo_config.success_message.as_deref());
+    }
+}
+
+async fn check_for_updates(
+    cli: &Cli,
+    repo_config: &RepoConfig,
+    repo: &Repository,
+    client: &GithubClient,
+) {
+    if repo_config.check_for_updates && !cli.quiet {
+        print_update_notice(client, &repo.hostname).await;
+    }
+}
+
+/// Get the checks of the default branch with default_branch, otherwise those f gets for the
+/// branch's pull request
+pub(crate) async fn get_checks<F, Fut>(
+    cli: &Cli,
+    default_branch: bool,
+    client: &GithubClient,
+    repo: &Repository,
+    branch: &str,
+    f: F,
+) -> Result<PullRequestChecks>
+where
+    F: Fn(SimplePullRequest) -> Fut,
+    Fut: Future<Output = Result<PullRequestChecks>>,
+{
+    let pr_checks = if let Some(run_id) = cli.run {
+        client
+            .get_workflow_run_checks(&repo.owner, &repo.name, run_id)
+            .await?
+    } else if default_branch {
+        let pr_checks = client
+            .get_default_branch_checks(&repo.owner, &repo.name)
+            .await?;
+        if pr_checks.check_runs.is_empty() {
+            return Err(GhtoolError::NoChecks.into());
+        }
+        pr_checks
+    } else {
+        with_pull_request(client, repo, branch, cli.skip_drafts, f).await?
+    };
+
+    if cli.only_required_failing && !client.knows_required_checks() {
+        eyre::bail!(
+            "GitHub didn't tell which checks are required; can't use --only-required-failing"
+        );
+    }
+    if let Some(max_age) = cli.max_age {
+        check_max_age(&pr_checks, max_age, cli.force, Utc::now())?;
+    }
+    Ok(pr_checks)
+}
+
+/// Checks that last completed longer than max_age ago are likely stale, e.g. when coming back to
+/// a pull request after a long time. With force, they're only warned about.
+fn check_max_age(
+    pr_checks: &PullRequestChecks,
+    max_age: chrono::Duration,
+    force: bool,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let Some(completed_at) = pr_checks
+        .check_runs
+        .iter()
+        .filter_map(|check_run| check_run.completed_at)
+        .max()
+    else {
+        return Ok(());
+    };
+
+    let age = now - completed_at;
+    if age <= max_age {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Checks of {} last completed {} ago, longer than --max-age",
+        pr_checks.head_sha,
+        HumanDuration(age.to_std().unwrap_or_default())
+    );
+    if force {
+        eprintln!("Warning: {message}");
+        Ok(())
+    } else {
+        eyre::bail!("{message}; use --force to show them anyway")
+    }
+}
+
+/// Run f with the branch's pull request. The pull request is cached, so it may no longer exist;
+/// in that case the cached pull request is forgotten and f is run once more with the pull request
+/// looked up again.
+async fn with_pull_request<F, Fut, T>(
+    client: &GithubClient,
+    repo: &Repository,
+    branch: &str,
+    skip_drafts: bool,
+    f: F,
+) -> Result<T>
+where
+    F: Fn(SimplePullRequest) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let pull_request = get_pull_request(client, repo, branch, skip_drafts).await?;
+    match f(pull_request).await {
+        Err(e) if is_node_not_found(&e) => {
+            info!("cached pull request not found, looking it up again");
+            client.forget_pr_for_branch(repo, branch)?;
+            let pull_request = get_pull_request(client, repo, branch, skip_drafts).await?;
+            f(pull_request).await
+        }
+        result => result,
+    }
+}
+
+fn is_node_not_found(error: &eyre::Report) -> bool {
+    matches!(
+        error.downcast_ref::<GithubApiError>(),
+        Some(GithubApiError::NodeNotFound)
+    )
+}
+
+/// Logs of a failed check that still aren't found after retrying tell that the checks read were
+/// inconsistent, e.g. the check was re-run in between
+fn is_logs_not_found(error: &eyre::Report) -> bool {
+    matches!(
+        error.downcast_ref::<GithubApiError>(),
+        Some(GithubApiError::ErrorResponse(
+            reqwest::StatusCode::NOT_FOUND,
+            _
+        ))
+    )
+}
+
+async fn wait_before_checks_retry(error: &eyre::Report) {
+    info!(
+        ?error,
+        "logs of a failed check not found, reading checks again"
+    );
+    eprintln!("Logs of a failed check weren't found, reading checks again...");
+    tokio::time::sleep(CHECKS_RETRY_DELAY).await;
+}
+
+/// Print every check run of the pull request with the commands whose job pattern matches it,
+/// to help debug job patterns
+async fn show_checks(
+    client: &GithubClient,
+    pull_request_id: &cynic::Id,
+    commands: &[&dyn Command],
+) -> Result<()> {
+    let pr_checks = client.get_pr_status_checks(pull_request_id, true).await?;
+    let max_name_length = pr_checks
+        .check_runs
+        .iter()
+        .map(|check_run| check_run.name.len())
+        .max()
+        .unwrap_or(0);
+
+    for check_run in &pr_checks.check_runs {
+        let matching_commands = matching_command_names(check_run, commands);
+        eprintln!(
+            "{:<name_width$}  status: {:<11}  conclusion: {:<15}  matches: {}",
+            check_run.name,
+            format!("{:?}", check_run.status),
+            check_run
+                .conclusion
+                .map_or_else(|| "-".to_string(), |c| format!("{:?}", c)),
+            if matching_commands.is_empty() {
+                "-".to_string()
+            } else {
+                matching_commands.join(", ")
+            },
+            name_width = max_name_length,
+        );
+    }
+    eprintln!();
+
+    Ok(())
+}
+
+fn matching_command_names(
+    check_run: &SimpleCheckRun,
+    commands: &[&dyn Command],
+) -> Vec<&'static str> {
+    commands
+        .iter()
+        .filter(|command| command.config().matches_check_run(check_run))
+        .map(|command| command.name())
+        .collect()
+}
+
+/// Commands of the sections that .ghtool.toml has, e.g. only lint in a repository without tests
+fn configured_commands(
+    repo_config: &RepoConfig,
+) -> Vec<(CommandType, Arc<dyn Command + Send + Sync>)> {
+    [CommandType::Test, CommandType::Build, CommandType::Lint]
+        .into_iter()
+        .filter_map(|command_type| {
+            let command = command_from_type(command_type, repo_config).ok()?;
+            Some((command_type, command))
+        })
+        .collect()
+}
+
+fn command_from_type(
+    command_type: CommandType,
+    repo_config: &RepoConfig,
+) -> Result<Arc<dyn Command + Send + Sync>> {
+    let command: Box<dyn Command + Send + Sync> = match command_type {
+        CommandType::Test => Box::new(TestCommand::from_repo_config(repo_config)?),
+        CommandType::Build => Box::new(BuildCommand::from_repo_config(repo_config)?),
+        CommandType::Lint => Box::new(LintCommand::from_repo_config(repo_config)?),
+    };
+    Ok(Arc::from(command))
+}
+
+/// Pair each check run with its parsed errors. Sections are sorted by check run name, then id,
+/// so that output doesn't depend on the order logs were fetched in.
+fn to_sections(
+    check_runs: &[SimpleCheckRun],
+    check_run_errors: &mut HashMap<CheckRunId, Vec<CheckError>>,
+) -> Vec<CheckRunErrors> {
+    let mut sections: Vec<_> = check_runs
+        .iter()
+        .map(|check_run| CheckRunErrors {
+            check_run: check_run.clone(),
+            errors: check_run_errors.remove(&check_run.id).unwrap_or_default(),
+        })
+        .collect();
+    sections.sort_by(|a, b| {
+        (&a.check_run.name, a.check_run.id).cmp(&(&b.check_run.name, b.check_run.id))
+    });
+    sections
+}
+
+/// Failed check runs whose logs had no errors the parser could find, e.g. because of an
+/// infrastructure error or an unsupported reporter
+fn unparsed_check_runs(sections: &[CheckRunErrors]) -> Vec<SimpleCheckRun> {
+    sections
+        .iter()
+        .filter(|section| section.errors.is_empty())
+        .map(|section| section.check_run.clone())
+        .collect()
+}
+
+/// Point to unparsed check runs so that there's always something to follow up on
+fn print_unparsed_check_runs(
+    check_runs: &[SimpleCheckRun],
+    unparsed_logs: &UnparsedLogs,
+    show_url: bool,
+) {
+    for check_run in check_runs {
+        if show_url {
+            let outcome = match check_run.conclusion {
+                Some(CheckConclusionState::ActionRequired) => "requires action",
+                Some(CheckConclusionState::Neutral) => "concluded as neutral",
+                Some(CheckConclusionState::TimedOut) => "timed out",
+                _ => "failed without errors found in its logs",
+            };
+            match &check_run.url {
+                Some(url) => eprintln!("Check {} {}: {}", bold(&check_run.name), outcome, url),
+                None => eprintln!("Check {} {}", bold(&check_run.name), outcome),
+            }
+        }
+
+        if let Some(lines) = unparsed_logs.failure_lines.get(&check_run.id) {
+            eprintln!("Failure in the log of {}:", bold(&check_run.name));
+            lines
+                .iter()
+                .for_each(|line| eprintln!("{}", log_line(line)));
+        }
+        if let Some(lines) = unparsed_logs.tails.get(&check_run.id) {
+            eprintln!("Last {} lines of {}:", lines.len(), bold(&check_run.name));
+            lines
+                .iter()
+                .for_each(|line| eprintln!("{}", log_line(line)));
+        }
+    }
+}
+
+/// Combine sections of check runs that are shards of the same job under one section named after
+/// the job. Errors repeated across shards are included once.
+fn merge_shards(sections: Vec<CheckRunErrors>, shard_pattern: &Regex) -> Vec<CheckRunErrors> {
+    let mut merged: Vec<CheckRunErrors> = Vec::new();
+
+    for section in sections {
+        let job_name = shard_pattern
+            .replace_all(&section.check_run.name, "")
+            .trim()
+            .to_string();
+        if job_name == section.check_run.name {
+            merged.push(section);
+            continue;
+        }
+
+        match merged.iter_mut().find(|s| s.check_run.name == job_name) {
+            Some(merged_section) => {
+                for error in section.errors {
+                    if !merged_section.errors.contains(&error) {
+                        merged_section.errors.push(error);
+                    }
+                }
+            }
+            None => {
+                let mut check_run = section.check_run;
+                check_run.name = job_name;
+                let mut errors: Vec<CheckError> = Vec::new();
+                for error in section.errors {
+                    if !errors.contains(&error) {
+                        errors.push(error);
+                    }
+                }
+                merged.push(CheckRunErrors { check_run, errors });
+            }
+        }
+    }
+
+    merged
+}
+
+fn build_file_filter(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| eyre::eyre!("Invalid --file-filter glob {}: {}", bold(pattern), e))?;
+        builder.add(glob);
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// Keep only errors whose path matches the file filter
+fn apply_file_filter(sections: &mut [CheckRunErrors], file_filter: &GlobSet) {
+    for section in sections {
+        section
+            .errors
+            .retain(|error| file_filter.is_match(&error.path));
+    }
+}
+
+/// Drop failed tests whose names don't match the pattern of --name
+fn retain_test_names(command: &dyn Command, sections: &mut [CheckRunErrors], pattern: &Regex) {
+    for section in sections {
+        section
+            .errors
+            .retain_mut(|error| command.retain_failed_tests(error, pattern));
+    }
+}
+
+/// Drop errors without any issue of error severity, e.g. files with only warnings
+fn retain_error_severity(command: &dyn Command, sections: &mut [CheckRunErrors]) {
+    for section in sections {
+        section
+            .errors
+            .retain(|error| command.has_error_severity(error));
+    }
+}
+
+fn keep_first_lines(sections: &mut [CheckRunErrors]) {
+    for error in sections.iter_mut().flat_map(|section| &mut section.errors) {
+        error.lines.truncate(1);
+    }
+}
+
+fn print_errored_files(sections: &[CheckRunErrors]) {
+    for file in errored_files(sections) {
+        println!("{}", file);
+    }
+}
+
+/// Files with the number of failures in each, most failures first
+fn file_failure_counts<'a>(
+    command: &dyn Command,
+    sections: &'a [CheckRunErrors],
+) -> Vec<(&'a str, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for error in sections.iter().flat_map(|section| &section.errors) {
+        *counts.entry(error.path.as_str()).or_default() += command.failure_count(error);
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(a_path, a_count), (b_path, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_path.cmp(b_path))
+    });
+    counts
+}
+
+fn print_file_failure_counts(counts: &[(&str, usize)]) {
+    for (path, count) in counts {
+        println!("{} ({} failing)", path, count);
+    }
+}
+
+/// Where an issue is for --format locations. Errors of tools that report no positions, e.g. failed
+/// test files, are located by path only.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct ErrorLocation {
+    path: String,
+    position: Option<(u32, u32)>,
+}
+
+impl std::fmt::Display for ErrorLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "{}:{}:{}", self.path, line, column),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+fn error_locations(command: &dyn Command, sections: &[CheckRunErrors]) -> BTreeSet<ErrorLocation> {
+    let mut locations = BTreeSet::new();
+    for error in sections.iter().flat_map(|section| &section.errors) {
+        let positions: Vec<_> = error
+            .lines
+            .iter()
+            .filter_map(|line| command.issue_position(line))
+            .collect();
+        if positions.is_empty() {
+            locations.insert(ErrorLocation {
+                path: error.path.clone(),
+                position: None,
+            });
+        }
+        locations.extend(positions.into_iter().map(|position| ErrorLocation {
+            path: error.path.clone(),
+            position: Some(position),
+        }));
+    }
+    locations
+}
+
+/// Output template of --output-template, which replaces how errors are printed, not e.g. --files
+fn parse_output_template(cli: &Cli, output_mode: OutputMode) -> Result<Option<OutputTemplate>> {
+    let Some(template) = &cli.output_template else {
+        return Ok(None);
+    };
+    if !matches!(output_mode, OutputMode::Errors | OutputMode::TerseErrors) {
+        eyre::bail!("--output-template can't be used with --files, --counts or --group-by");
+    }
+    OutputTemplate::parse(template).map(Some)
+}
+
+/// A line for each issue of the errors, or for each error when its tool reports no positions, e.g.
+/// a failed test file
+fn templated_issues(
+    command: &dyn Command,
+    sections: &[CheckRunErrors],
+    template: &OutputTemplate,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for section in sections {
+        for error in &section.errors {
+            let render = |line: &str, position| {
+                template.render(&TemplateValues {
+                    path: &error.path,
+                    position,
+                    message: &command.issue_message(line),
+                    check: &section.check_run.name,
+                })
+            };
+            let issues: Vec<_> = error
+                .lines
+                .iter()
+                .filter_map(|line| Some((line, command.issue_position(line)?)))
+                .collect();
+            if issues.is_empty() {
+                let first_line = error.lines.first().unwrap_or(&error.path);
+                lines.push(render(first_line, None));
+            }
+            lines.extend(
+                issues
+                    .into_iter()
+                    .map(|(line, position)| render(line, Some(position))),
+            );
+        }
+    }
+    lines
+}
+
+fn print_templated_issues(lines: &[String]) {
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+fn print_error_locations(locations: &BTreeSet<ErrorLocation>) {
+    for location in locations {
+        println!("{}", location);
+    }
+}
+
+/// Issues reported for a rule, and the files they're in
+#[derive(Debug, PartialEq)]
+struct RuleGroup {
+    rule: String,
+    count: usize,
+    files: Vec<String>,
+}
+
+/// Groups sorted by issue count, most common first
+fn group_by_rule(command: &dyn Command, sections: &[CheckRunErrors]) -> Vec<RuleGroup> {
+    let mut groups: Vec<RuleGroup> = Vec::new();
+
+    for error in sections.iter().flat_map(|section| &section.errors) {
+        for rule in error
+            .lines
+            .iter()
+            .filter_map(|line| command.issue_rule(line))
+        {
+            let group = match groups.iter().position(|g| g.rule == rule) {
+                Some(i) => &mut groups[i],
+                None => {
+                    groups.push(RuleGroup {
+                        rule,
+                        count: 0,
+                        files: Vec::new(),
+                    });
+                    groups.last_mut().unwrap()
+                }
+            };
+            group.count += 1;
+            if !group.files.contains(&error.path) {
+                group.files.push(error.path.clone());
+            }
+        }
+    }
+
+    groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.rule.cmp(&b.rule)));
+    groups
+}
+
+fn print_rule_groups(groups: &[RuleGroup]) {
+    let max_rule_length = groups.iter().map(|g| g.rule.len()).max().unwrap_or(0);
+    for group in groups {
+        // Padded before making bold so that escape codes don't count towards the width
+        let rule = format!("{:<width$}", group.rule, width = max_rule_length);
+        println!("{}  {}", bold(&rule), group.count);
+        for file in &group.files {
+            println!("  {}", file);
+        }
+    }
+}
+
+/// Failed tests of a file, grouped by the suite they're in. Tests outside of suites, e.g. "Test
+/// suite failed to run", are grouped under None.
+#[derive(Debug, PartialEq)]
+struct SuiteGroups {
+    path: String,
+    suites: Vec<(Option<String>, Vec<String>)>,
+}
+
+/// Files in the order they failed in, merging the errors of a file reported by several check runs
+fn group_by_suite(command: &dyn Command, sections: &[CheckRunErrors]) -> Vec<SuiteGroups> {
+    let mut files: Vec<SuiteGroups> = Vec::new();
+
+    for error in sections.iter().flat_map(|section| &section.errors) {
+        let file = match files.iter().position(|f| f.path == error.path) {
+            Some(i) => &mut files[i],
+            None => {
+                files.push(SuiteGroups {
+                    path: error.path.clone(),
+                    suites: Vec::new(),
+                });
+                files.last_mut().unwrap()
+            }
+        };
+
+        for name in command.failed_tests(error) {
+            let (suite, test) = match name.rsplit_once(" › ") {
+                Some((suite, test)) => (Some(suite.to_string()), test.to_string()),
+                None => (None, name),
+            };
+            match file.suites.iter_mut().find(|(s, _)| *s == suite) {
+                Some((_, tests)) if tests.contains(&test) => {}
+                Some((_, tests)) => tests.push(test),
+                None => file.suites.push((suite, vec![test])),
+            }
+        }
+    }
+
+    files
+}
+
+fn print_suite_groups(files: &[SuiteGroups]) {
+    for file in files {
+        println!("{}", bold(&file.path));
+        for (suite, tests) in &file.suites {
+            let indent = match suite {
+                Some(suite) => {
+                    println!("  {}", suite);
+                    "    "
+                }
+                None => "  ",
+            };
+            for test in tests {
+                println!("{}{}", indent, test);
+            }
+        }
+    }
+}
+
+fn print_errors(sections: &[CheckRunErrors], head_sha: &str) {
+    sections.iter().for_each(|section| {
+        print_check_run_header(&section.check_run, head_sha);
+
+        section
+            .errors
+            .iter()
+            .flat_map(|error| &error.lines)
+            .for_each(|line| println!("{}", log_line(line)));
+    });
+}
+
+/// Errors of each check run in a collapsed <details> section, with their lines in a code block
+fn markdown_errors(sections: &[CheckRunErrors]) -> String {
+    let mut markdown = String::new();
+    for section in sections {
+        let name = html_escape(&section.check_run.name);
+        let summary = match &section.check_run.url {
+            Some(url) => format!("{} (<a href=\"{}\">logs</a>)", name, html_escape(url)),
+            None => name,
+        };
+        let lines: Vec<_> = section
+            .errors
+            .iter()
+            .flat_map(|error| &error.lines)
+            .map(|line| strip_ansi(line))
+            .collect();
+        let fence = code_fence(&lines);
+        markdown.push_str(&format!(
+            "<details>\n<summary>{}</summary>\n\n{}\n{}\n{}\n\n</details>\n\n",
+            summary,
+            fence,
+            lines.join("\n"),
+            fence
+        ));
+    }
+    markdown
+}
+
+/// Backticks one longer than any run of them starting a line, so that the block isn't closed early
+fn code_fence(lines: &[String]) -> String {
+    let longest = lines
+        .iter()
+        .map(|line| line.trim_start().chars().take_while(|&c| c == '`').count())
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Version of the structure of JSON output. Bump when fields change.
+const JSON_SCHEMA_VERSION: u32 = 2;
+
+/// Outcome of the checks in JSON output, so that a document without errors tells checks that
+/// passed from failed ones whose errors weren't found in their logs
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum JsonOutcome {
+    Passed,
+    Failed,
+    NoMatchingChecks,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonOutput<'a> {
+    schema_version: u32,
+    head_sha: &'a str,
+    outcome: JsonOutcome,
+    checks: Vec<JsonCheck<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonCheck<'a> {
+    name: &'a str,
+    url: Option<&'a str>,
+    errors: Vec<JsonError<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonError<'a> {
+    path: &'a str,
+    lines: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonFilesOutput<'a> {
+    schema_version: u32,
+    outcome: JsonOutcome,
+    files: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorRecord<'a> {
+    schema_version: u32,
+    check: &'a str,
+    path: &'a str,
+    lines: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileRecord<'a> {
+    schema_version: u32,
+    path: &'a str,
+}
+
+fn json_output<'a>(
+    sections: &'a [CheckRunErrors],
+    head_sha: &'a str,
+    outcome: JsonOutcome,
+) -> JsonOutput<'a> {
+    JsonOutput {
+        schema_version: JSON_SCHEMA_VERSION,
+        head_sha,
+        outcome,
+        checks: sections
+            .iter()
+            .filter(|section| !section.errors.is_empty())
+            .map(|section| JsonCheck {
+                name: &section.check_run.name,
+                url: section.check_run.url.as_deref(),
+                errors: section
+                    .errors
+                    .iter()
+                    .map(|error| JsonError {
+                        path: &error.path,
+                        lines: error.lines.iter().map(|line| strip_ansi(line)).collect(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Paths of files with errors, sorted so that output is the same from run to run
+fn errored_files(sections: &[CheckRunErrors]) -> Vec<&str> {
+    let files: BTreeSet<&str> = sections
+        .iter()
+        .flat_map(|section| section.errors.iter().map(|error| error.path.as_str()))
+        .collect();
+    files.into_iter().collect()
+}
+
+fn write_errors_json(
+    sections: &[CheckRunErrors],
+    head_sha: &str,
+    outcome: JsonOutcome,
+) -> Result<()> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json_output(sections, head_sha, outcome))?
+    );
+    Ok(())
+}
+
+fn write_errored_files_json(sections: &[CheckRunErrors], outcome: JsonOutcome) -> Result<()> {
+    let output = JsonFilesOutput {
+        schema_version: JSON_SCHEMA_VERSION,
+        outcome,
+        files: errored_files(sections),
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+/// With --format json, write the document of a run that has no errors to print, e.g. because all
+/// checks passed, so that the output can be parsed like that of one with errors
+fn write_no_errors_json(
+    cli: &Cli,
+    output_mode: OutputMode,
+    head_sha: &str,
+    outcome: JsonOutcome,
+) -> Result<()> {
+    if cli.format != OutputFormat::Json || cli.output_template.is_some() {
+        return Ok(());
+    }
+    match output_mode {
+        OutputMode::Files | OutputMode::ErrorFiles => write_errored_files_json(&[], outcome),
+        _ => write_errors_json(&[], head_sha, outcome),
+    }
+}
+
+/// Write each error as a JSON object on its own line, without ANSI escapes. Output is flushed
+/// after every line so that consumers can process errors one at a time.
+fn write_errors_ndjson(sections: &[CheckRunErrors]) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    for section in sections {
+        for error in &section.errors {
+            let record = ErrorRecord {
+                schema_version: JSON_SCHEMA_VERSION,
+                check: &section.check_run.name,
+                path: &error.path,
+                lines: error.lines.iter().map(|line| strip_ansi(line)).collect(),
+            };
+            write_ndjson_line(&mut stdout, &record)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_errored_files_ndjson(sections: &[CheckRunErrors]) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    for path in errored_files(sections) {
+        let record = FileRecord {
+            schema_version: JSON_SCHEMA_VERSION,
+            path,
+        };
+        write_ndjson_line(&mut stdout, &record)?;
+    }
+    Ok(())
+}
+
+fn write_ndjson_line(writer: &mut impl Write, record: &impl Serialize) -> Result<()> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn strip_ansi(line: &str) -> String {
+    String::from_utf8_lossy(&strip_ansi_escapes::strip(line.as_bytes())).into_owned()
+}
+
+/// Source maps are looked up in the checkout ghtool is run in
+fn resolve_section_sourcemaps(sections: &mut [CheckRunErrors]) -> Result<()> {
+    let repo_path = get_repo_path()
+        .wrap_err("--resolve-sourcemaps must be used in a checkout of the repository")?;
+    for section in sections {
+        resolve_sourcemaps(&mut section.errors, &repo_path);
+    }
+    Ok(())
+}
+
+/// Blame of the local checkout with --merge-base-context. None when the commits of the pull
+/// request aren't in it, e.g. in a shallow clone, in which case lines are left untagged.
+fn merge_base_blame(cli: &Cli, head_sha: &str) -> Result<Option<Blame>> {
+    if !cli.merge_base_context {
+        return Ok(None);
+    }
+    let repo_path = get_repo_path()
+        .wrap_err("--merge-base-context must be used in a checkout of the repository")?;
+    match Blame::new(&repo_path, head_sha) {
+        Ok(blame) => Ok(Some(blame)),
+        Err(e) => {
+            eprintln!("Warning: not telling introduced lines from pre-existing ones: {e:#}");
+            Ok(None)
+        }
+    }
+}
+
+fn annotate_section_blame(command: &dyn Command, sections: &mut [CheckRunErrors], blame: &Blame) {
+    for section in sections {
+        annotate_introduced_lines(command, &mut section.errors, blame);
+    }
+}
+
+/// Results as read back from a post_parse_command
+#[derive(Deserialize)]
+struct PostParseOutput {
+    checks: Vec<PostParseCheck>,
+}
+
+#[derive(Deserialize)]
+struct PostParseCheck {
+    name: String,
+    errors: Vec<CheckError>,
+}
+
+/// Pipe the sections through command in the --format json shape, and replace them with the checks
+/// in its output. ANSI escapes are stripped from the lines the command gets, so colors are lost.
+fn run_post_parse_command(
+    command: &str,
+    sections: Vec<CheckRunErrors>,
+    head_sha: &str,
+) -> Result<Vec<CheckRunErrors>> {
+    if sections.iter().all(|section| section.errors.is_empty()) {
+        return Ok(sections);
+    }
+
+    let input = serde_json::to_vec(&json_output(&sections, head_sha, JsonOutcome::Failed))?;
+    debug!(command, "running post_parse_command");
+    let mut process = std::process::Command::new("sh");
+    process
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped());
+    trace_command(&process);
+    let mut child = process
+        .spawn()
+        .wrap_err_with(|| format!("Failed to run post_parse_command {}", bold(command)))?;
+
+    // Written from another thread so that a command writing output before it has read all of its
+    // input can't block on a full pipe
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    match writer.join().expect("Failed to join stdin writer") {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+        _ => {}
+    }
+
+    if !output.status.success() {
+        eyre::bail!(
+            "post_parse_command {} failed with {}",
+            bold(command),
+            output.status
+        );
+    }
+
+    read_post_parse_output(sections, &output.stdout)
+}
+
+fn read_post_parse_output(
+    sections: Vec<CheckRunErrors>,
+    output: &[u8],
+) -> Result<Vec<CheckRunErrors>> {
+    let output: PostParseOutput = serde_json::from_slice(output)
+        .wrap_err("Output of post_parse_command is not valid JSON results")?;
+
+    output
+        .checks
+        .into_iter()
+        .map(|check| {
+            let section = sections
+                .iter()
+                .find(|section| section.check_run.name == check.name)
+                .ok_or_else(|| {
+                    eyre::eyre!(
+                        "Output of post_parse_command has unknown check {}",
+                        bold(&check.name)
+                    )
+                })?;
+            Ok(CheckRunErrors {
+                check_run: section.check_run.clone(),
+                errors: check.errors,
+            })
+        })
+        .collect()
+}
+
+type CheckRunId = u64;
+
+enum CommandMode {
+    Single(Arc<dyn Command + Send + Sync>),
+    Multiple {
+        // Used to provide command's parse log function
+        command_map: HashMap<CommandType, Arc<dyn Command + Send + Sync>>,
+        // Used to determine how check run's logs should be parsed
+        check_run_command_map: HashMap<CheckRunId, CommandType>,
+    },
+}
+
+impl CommandMode {
+    fn command_for(&self, check_run_id: CheckRunId) -> Arc<dyn Command + Send + Sync> {
+        match self {
             CommandMode::Single(single_command) => {
                 single_command.clone() // Single mode: use the same command for all check runs
             }
@@ -284,29 +2182,428 @@ async fn process_failed_check_runs(
                     .unwrap_or_else(|| panic!("Unknown check run id: {}", check_run_id));
                 command_map.get(command_type).unwrap().clone()
             }
-        };
+        }
+    }
+}
+
+/// Errors of failed check runs, and what is shown of the logs of those without any
+#[derive(Default)]
+struct ProcessedCheckRuns {
+    check_errors: HashMap<CheckRunId, Vec<CheckError>>,
+    unparsed_logs: UnparsedLogs,
+    /// Check runs left unread with --fail-fast, after one with errors
+    skipped: HashSet<CheckRunId>,
+}
+
+/// What is shown of the logs of failed check runs without errors
+#[derive(Default)]
+struct UnparsedLogs {
+    /// Last lines, if requested with --tail
+    tails: HashMap<CheckRunId, Vec<String>>,
+    /// Lines telling that a step failed, with the lines just before them
+    failure_lines: HashMap<CheckRunId, Vec<String>>,
+}
+
+impl UnparsedLogs {
+    fn extend(&mut self, other: UnparsedLogs) {
+        self.tails.extend(other.tails);
+        self.failure_lines.extend(other.failure_lines);
+    }
+}
+
+/// What is done with logs of failed check runs, from command line options
+struct LogOptions<'a> {
+    /// Read errors from check run annotations, falling back to logs
+    annotations: bool,
+    tail: Option<usize>,
+    max_download: u64,
+    save_logs: Option<&'a Path>,
+    cache_logs: bool,
+    /// Only read logs from the cache
+    reparse: bool,
+    fail_fast: bool,
+}
+
+impl<'a> LogOptions<'a> {
+    fn from_cli(cli: &'a Cli) -> Self {
+        LogOptions {
+            annotations: cli.annotations,
+            tail: cli.tail,
+            max_download: cli.max_download,
+            save_logs: cli.save_logs.as_deref(),
+            cache_logs: cli.cache_logs,
+            reparse: cli.reparse,
+            fail_fast: cli.fail_fast,
+        }
+    }
+}
+
+/// Get logs for each failed check run, and parse them into a map of command type to check errors
+async fn process_failed_check_runs(
+    client: &GithubClient,
+    repo: &Repository,
+    command_mode: CommandMode,
+    all_failed_check_runs: &[SimpleCheckRun],
+    log_options: LogOptions<'_>,
+) -> Result<ProcessedCheckRuns> {
+    if !log_options.fail_fast {
+        return process_check_runs(
+            client,
+            repo,
+            &command_mode,
+            all_failed_check_runs,
+            &log_options,
+        )
+        .await;
+    }
+
+    // One check run at a time, so that logs of the ones after the first with errors aren't
+    // downloaded
+    let mut processed = ProcessedCheckRuns::default();
+    for (i, check_run) in all_failed_check_runs.iter().enumerate() {
+        let ProcessedCheckRuns {
+            check_errors,
+            unparsed_logs,
+            ..
+        } = process_check_runs(
+            client,
+            repo,
+            &command_mode,
+            std::slice::from_ref(check_run),
+            &log_options,
+        )
+        .await?;
+        let has_errors = check_errors.values().any(|errors| !errors.is_empty());
+        processed.check_errors.extend(check_errors);
+        processed.unparsed_logs.extend(unparsed_logs);
+        if has_errors {
+            processed.skipped = all_failed_check_runs[i + 1..]
+                .iter()
+                .map(|check_run| check_run.id)
+                .collect();
+            break;
+        }
+    }
+    Ok(processed)
+}
+
+async fn process_check_runs(
+    client: &GithubClient,
+    repo: &Repository,
+    command_mode: &CommandMode,
+    all_failed_check_runs: &[SimpleCheckRun],
+    log_options: &LogOptions<'_>,
+) -> Result<ProcessedCheckRuns> {
+    let tail = log_options.tail;
+    let mut check_errors_map = HashMap::new();
+    let mut junit_reports = JunitReports::new();
+
+    // Check runs with errors from annotations or a JUnit report don't need their logs fetched
+    let mut log_check_runs = Vec::new();
+    for check_run in all_failed_check_runs {
+        // Check runs that didn't fail, e.g. ones requiring action, have no errors to read; they
+        // are pointed to like unparsed ones. Logs of timed out ones have the failures reached
+        // before the timeout.
+        if !matches!(
+            check_run.conclusion,
+            Some(CheckConclusionState::Failure | CheckConclusionState::TimedOut)
+        ) {
+            continue;
+        }
+        let command = command_mode.command_for(check_run.id);
+        let mut errors = None;
+        if log_options.annotations {
+            errors = fetch_annotation_errors(client, repo, check_run)
+                .await
+                .unwrap_or_else(|err| {
+                    warn!(name = ?check_run.name, ?err, "failed to read annotations");
+                    None
+                });
+        }
+        if errors.is_none() {
+            if let Some(artifact_name) = command.junit_artifact() {
+                let junit_errors =
+                    fetch_junit_errors(client, repo, check_run, artifact_name, &mut junit_reports)
+                        .await
+                        .unwrap_or_else(|err| {
+                            // The log has the same failures, so a broken report isn't fatal
+                            warn!(name = ?check_run.name, ?err, "failed to read test report");
+                            None
+                        });
+                errors = junit_errors.map(|mut check_errors| {
+                    // Paths in the reports are relative to where the tests ran, unlike annotations'
+                    // repo-relative ones
+                    for check_error in &mut check_errors {
+                        join_error_working_dir(command.as_ref(), check_error);
+                    }
+                    check_errors
+                });
+            }
+        }
+
+        match errors {
+            Some(mut check_errors) => {
+                for check_error in &mut check_errors {
+                    check_error.path = normalize_path(&check_error.path);
+                }
+                check_errors_map.insert(check_run.id, check_errors);
+            }
+            None => log_check_runs.push(check_run.clone()),
+        }
+    }
+
+    let log_map = get_check_run_logs(client, repo, &log_check_runs, log_options).await?;
+    if let Some(dir) = log_options.save_logs {
+        save_logs(dir, &log_check_runs, &log_map)?;
+    }
+    let mut parse_tasks = Vec::new();
+
+    for (check_run_id, log_bytes) in log_map.iter() {
+        let check_run_id = *check_run_id;
+        let log_bytes = log_bytes.clone();
+        let command = command_mode.command_for(check_run_id);
+
+        let handle = tokio::task::spawn_blocking(move || {
+            parse_log_bytes(command.as_ref(), &log_bytes, tail)
+        });
+        parse_tasks.push((check_run_id, handle));
+    }
+
+    let mut unparsed_logs = UnparsedLogs::default();
+    for (check_run_id, parsed_log) in join_parse_tasks(parse_tasks, all_failed_check_runs).await? {
+        check_errors_map
+            .entry(check_run_id)
+            .or_insert_with(Vec::new)
+            .extend(parsed_log.errors);
+        if let Some(tail) = parsed_log.tail {
+            unparsed_logs.tails.insert(check_run_id, tail);
+        }
+        if !parsed_log.failure_lines.is_empty() {
+            unparsed_logs
+                .failure_lines
+                .insert(check_run_id, parsed_log.failure_lines);
+        }
+    }
+
+    for check_run in all_failed_check_runs {
+        if check_errors_map
+            .get(&check_run.id)
+            .is_none_or(|errors| errors.is_empty())
+        {
+            debug!(
+                name = ?check_run.name,
+                id = check_run.id,
+                "failed check run produced no errors"
+            );
+        }
+    }
+
+    Ok(ProcessedCheckRuns {
+        check_errors: check_errors_map,
+        unparsed_logs,
+        skipped: HashSet::new(),
+    })
+}
+
+/// Logs of the check runs, from the cache with --cache-logs or --reparse, or downloaded
+async fn get_check_run_logs(
+    client: &GithubClient,
+    repo: &Repository,
+    check_runs: &[SimpleCheckRun],
+    log_options: &LogOptions<'_>,
+) -> Result<HashMap<CheckRunId, Bytes>> {
+    if !log_options.cache_logs && !log_options.reparse {
+        return fetch_check_run_logs(client, repo, check_runs, log_options.max_download).await;
+    }
+
+    let mut log_map = HashMap::new();
+    let mut uncached_check_runs = Vec::new();
+    for check_run in check_runs {
+        match log_cache::get(&repo.hostname, check_run.id)? {
+            Some(log) => {
+                log_map.insert(check_run.id, log);
+            }
+            None if log_options.reparse => eyre::bail!(
+                "No cached log of check {}; read it once with --cache-logs",
+                bold(&check_run.name)
+            ),
+            None => uncached_check_runs.push(check_run.clone()),
+        }
+    }
+
+    if uncached_check_runs.is_empty() {
+        return Ok(log_map);
+    }
+    let fetched =
+        fetch_check_run_logs(client, repo, &uncached_check_runs, log_options.max_download).await?;
+    for (check_run_id, log) in fetched {
+        // A log cut at --max-download would be parsed partially with --reparse
+        if client.is_job_log_truncated(check_run_id) {
+            debug!(check_run_id, "not caching truncated log");
+        } else {
+            log_cache::put(&repo.hostname, check_run_id, &log)?;
+        }
+        log_map.insert(check_run_id, log);
+    }
+    Ok(log_map)
+}
+
+/// Errors parsed from a log, and what is shown of it when there are none
+#[derive(Debug, PartialEq)]
+struct ParsedLog {
+    errors: Vec<CheckError>,
+    tail: Option<Vec<String>>,
+    failure_lines: Vec<String>,
+}
+
+/// Wait for the tasks parsing logs. When a parser panics on a log, the check run is left without
+/// errors, so that it's pointed to like an unparsed one, instead of losing the errors of the others.
+async fn join_parse_tasks(
+    parse_tasks: Vec<(CheckRunId, JoinHandle<Result<ParsedLog>>)>,
+    check_runs: &[SimpleCheckRun],
+) -> Result<Vec<(CheckRunId, ParsedLog)>> {
+    let (check_run_ids, handles): (Vec<_>, Vec<_>) = parse_tasks.into_iter().unzip();
+    let results = join_all(handles).await;
+
+    let mut parsed_logs = Vec::new();
+    for (check_run_id, result) in check_run_ids.into_iter().zip(results) {
+        match result {
+            Ok(parsed_log) => parsed_logs.push((check_run_id, parsed_log?)),
+            Err(e) if e.is_panic() => {
+                let name = check_runs
+                    .iter()
+                    .find(|check_run| check_run.id == check_run_id)
+                    .map_or("unknown", |check_run| check_run.name.as_str());
+                warn!(check_run_id, name, "parsing log panicked");
+                eprintln!("Warning: parsing the log of check {} failed", bold(name));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(parsed_logs)
+}
 
-        let handle = tokio::task::spawn_blocking(move || {
-            let log_str = std::str::from_utf8(&log_bytes)?;
-            Ok((check_run_id, command.parse_log(log_str)?))
-        });
-        parse_futures.push(handle);
+/// Make the error's path relative to the repository root if it's a file's
+fn join_error_working_dir(command: &dyn Command, check_error: &mut CheckError) {
+    if command.is_file_path(&check_error.path) {
+        check_error.path = join_working_dir(command.config().working_dir(), &check_error.path);
     }
+}
 
-    let results = try_join_all(parse_futures).await?;
-    let mut check_errors_map = HashMap::new();
-    for result in results {
-        let (command_type, check_errors) = result?;
-        check_errors_map
-            .entry(command_type)
-            .or_insert_with(Vec::new)
-            .extend(check_errors);
+/// Parse errors from a log, and get its failure marker lines and last tail lines if there are none.
+/// Logs may contain bytes that aren't valid UTF-8, e.g. raw terminal control bytes, which are
+/// replaced rather than fail parsing the whole log.
+fn parse_log_bytes(
+    command: &dyn Command,
+    log_bytes: &[u8],
+    tail: Option<usize>,
+) -> Result<ParsedLog> {
+    let log_str = String::from_utf8_lossy(log_bytes);
+    let mut check_errors = command.parse_log(&log_str)?;
+    for check_error in &mut check_errors {
+        join_error_working_dir(command, check_error);
+        check_error.path = normalize_path(&check_error.path);
+    }
+    let (tail, failure_lines) = if check_errors.is_empty() {
+        (tail.map(|n| log_tail(&log_str, n)), failure_lines(&log_str))
+    } else {
+        (None, Vec::new())
+    };
+    Ok(ParsedLog {
+        errors: check_errors,
+        tail,
+        failure_lines,
+    })
+}
+
+/// Lines that tell a step of a job failed, whatever tool ran in it
+const FAILURE_MARKERS: &[&str] = &[
+    "##[error]Process completed with exit code",
+    "ELIFECYCLE",
+    "Command failed",
+];
+
+/// Lines printed before each failure marker line, which often tell what failed
+const FAILURE_CONTEXT_LINES: usize = 2;
+
+/// Lines of the log with failure markers and the ones just before them, without timestamps, so
+/// that a failed job whose tool's output isn't parsed, e.g. a build task replayed from a cache,
+/// still shows why it failed
+fn failure_lines(log: &str) -> Vec<String> {
+    let lines: Vec<_> = log
+        .lines()
+        .map(|line| TIMESTAMP.replace(line, ""))
+        .collect();
+    let mut shown = vec![false; lines.len()];
+    for (i, line) in lines.iter().enumerate() {
+        let line_no_ansi = strip_ansi_escapes::strip_str(line);
+        if FAILURE_MARKERS
+            .iter()
+            .any(|marker| line_no_ansi.contains(marker))
+        {
+            let start = i.saturating_sub(FAILURE_CONTEXT_LINES);
+            shown[start..=i].iter_mut().for_each(|shown| *shown = true);
+        }
+    }
+    lines
+        .into_iter()
+        .zip(shown)
+        .filter(|(line, shown)| *shown && !line.trim().is_empty())
+        .map(|(line, _)| line.into_owned())
+        .collect()
+}
+
+/// Write each log to dir as <check run name>-<id>.log
+fn save_logs(
+    dir: &Path,
+    check_runs: &[SimpleCheckRun],
+    log_map: &HashMap<u64, Bytes>,
+) -> Result<()> {
+    fs::create_dir_all(dir)
+        .wrap_err_with(|| format!("Failed to create directory {}", dir.display()))?;
+
+    for check_run in check_runs {
+        if let Some(log) = log_map.get(&check_run.id) {
+            let path = dir.join(log_file_name(check_run));
+            fs::write(&path, log)
+                .wrap_err_with(|| format!("Failed to write log to {}", path.display()))?;
+        }
+    }
+
+    eprintln!("Saved {} logs to {}", log_map.len(), dir.display());
+    Ok(())
+}
+
+/// Check run names can contain anything, e.g. "Unit tests (1/4)"
+fn log_file_name(check_run: &SimpleCheckRun) -> String {
+    let mut name = String::new();
+    for c in check_run.name.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
     }
+    format!("{}-{}.log", name.trim_end_matches('-'), check_run.id)
+}
 
-    Ok(check_errors_map)
+fn log_tail(log: &str, n: usize) -> Vec<String> {
+    let lines: Vec<_> = log.lines().collect();
+    lines[lines.len().saturating_sub(n)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
 }
 
-fn get_token(hostname: &str) -> Result<String> {
+pub(crate) async fn get_token(cli: &Cli, hostname: &str) -> Result<String> {
+    if let Some(credentials) = app_credentials(cli)? {
+        return get_installation_token(&credentials).await;
+    }
+
+    if cli.token_from_gh {
+        return get_gh_token(hostname);
+    }
+
     // In development, macOS is constantly asking for password when token store is accessed with a
     // new binary
     if let Ok(token) = std::env::var("GH_TOKEN") {
@@ -314,26 +2611,65 @@ fn get_token(hostname: &str) -> Result<String> {
     }
 
     token_store::get_token(hostname).map_err(|err| match err {
-        keyring::Error::NoEntry => {
-            eyre::eyre!(
-                "No token found for {}. Have you logged in? Run {}",
-                bold(hostname),
-                bold("ghtool login")
-            )
+        keyring::Error::NoEntry => GhtoolError::NotLoggedIn {
+            hostname: hostname.to_string(),
         }
+        .into(),
         err => eyre::eyre!("Failed to get token for {}: {}", hostname, err),
     })
 }
 
+/// Credentials of the GitHub App given with --app-id or GHTOOL_APP_ID, if any
+fn app_credentials(cli: &Cli) -> Result<Option<AppCredentials>> {
+    let Some(app_id) = cli
+        .app_id
+        .clone()
+        .or_else(|| std::env::var("GHTOOL_APP_ID").ok())
+    else {
+        return Ok(None);
+    };
+
+    let installation_id = match cli.app_installation_id {
+        Some(installation_id) => installation_id,
+        None => std::env::var("GHTOOL_APP_INSTALLATION_ID")
+            .wrap_err("Error: --app-installation-id or GHTOOL_APP_INSTALLATION_ID must be given with --app-id")?
+            .parse()
+            .wrap_err("Error: GHTOOL_APP_INSTALLATION_ID is not a number")?,
+    };
+    let private_key = match &cli.app_private_key {
+        Some(path) => fs::read_to_string(path).wrap_err_with(|| {
+            format!(
+                "Error reading GitHub App private key from {}",
+                path.display()
+            )
+        })?,
+        None => std::env::var("GHTOOL_APP_PRIVATE_KEY").wrap_err(
+            "Error: --app-private-key or GHTOOL_APP_PRIVATE_KEY must be given with --app-id",
+        )?,
+    };
+
+    Ok(Some(AppCredentials {
+        app_id,
+        installation_id,
+        private_key,
+    }))
+}
+
 fn add_command_info(
     command: &dyn Command,
     command_type: CommandType,
     all_check_runs: &[SimpleCheckRun],
+    cli: &Cli,
     all_failed_check_runs: &mut Vec<SimpleCheckRun>,
     check_run_command_map: &mut HashMap<u64, CommandType>,
     command_check_run_map: &mut HashMap<CommandType, Vec<u64>>,
 ) {
-    let (failed, _, _) = filter_check_runs(command, all_check_runs);
+    let (failed, _, _) = filter_check_runs(
+        command,
+        all_check_runs,
+        cli.include_neutral,
+        cli.only_required_failing,
+    );
     all_failed_check_runs.extend_from_slice(&failed);
 
     for check_run in &failed {
@@ -344,3 +2680,1181 @@ fn add_command_info(
             .push(check_run.id);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::CheckStatusState;
+    use pretty_assertions::assert_eq;
+
+    fn check_run(id: u64, name: &str) -> SimpleCheckRun {
+        SimpleCheckRun {
+            id,
+            name: name.to_string(),
+            status: CheckStatusState::Completed,
+            conclusion: Some(CheckConclusionState::Failure),
+            url: None,
+            started_at: None,
+            completed_at: None,
+            app: Some(DEFAULT_APP.to_string()),
+            is_required: false,
+        }
+    }
+
+    fn check_error(path: &str) -> CheckError {
+        CheckError {
+            path: path.to_string(),
+            lines: vec![path.to_string()],
+        }
+    }
+
+    #[test]
+    fn test_filter_check_runs_reports_action_required() {
+        let repo_config: RepoConfig =
+            toml::from_str("[build]\njob_pattern = \"Build\"\ntool = \"tsc\"\n").unwrap();
+        let command = BuildCommand::from_repo_config(&repo_config).unwrap();
+        let with_conclusion = |id, conclusion| SimpleCheckRun {
+            conclusion: Some(conclusion),
+            ..check_run(id, "Build")
+        };
+        let check_runs = vec![
+            with_conclusion(1, CheckConclusionState::Failure),
+            with_conclusion(2, CheckConclusionState::ActionRequired),
+            with_conclusion(3, CheckConclusionState::Neutral),
+            with_conclusion(4, CheckConclusionState::Success),
+        ];
+
+        let ids = |include_neutral| {
+            let (failed, _, _) = filter_check_runs(&command, &check_runs, include_neutral, false);
+            failed.iter().map(|run| run.id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(false), vec![1, 2]);
+        assert_eq!(ids(true), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_filter_check_runs_only_required() {
+        let repo_config: RepoConfig =
+            toml::from_str("[build]\njob_pattern = \"Build\"\ntool = \"tsc\"\n").unwrap();
+        let command = BuildCommand::from_repo_config(&repo_config).unwrap();
+        let run = |id, conclusion, is_required| SimpleCheckRun {
+            conclusion: Some(conclusion),
+            is_required,
+            ..check_run(id, "Build")
+        };
+        let check_runs = vec![
+            run(1, CheckConclusionState::Failure, true),
+            run(2, CheckConclusionState::Failure, false),
+            run(3, CheckConclusionState::TimedOut, true),
+            run(4, CheckConclusionState::Success, true),
+        ];
+
+        let (failed, _, no_matching_runs) = filter_check_runs(&command, &check_runs, false, true);
+        assert_eq!(
+            failed.iter().map(|run| run.id).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert!(!no_matching_runs);
+    }
+
+    #[tokio::test]
+    async fn test_process_failed_check_runs_fail_fast() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let logs = [
+            (1, "All tests passed\n", 1),
+            (
+                2,
+                "2023-06-26T16:57:36.5365262Z ##[error]src/a.ts(1,7): error TS2322: Type 'number' is not assignable to type 'string'.\n",
+                1,
+            ),
+            (
+                3,
+                "2023-06-26T16:57:36.5365262Z ##[error]src/b.ts(1,7): error TS2322: Type 'number' is not assignable to type 'string'.\n",
+                0,
+            ),
+        ];
+        for (id, log, expected_requests) in logs {
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/org/repo/actions/jobs/{}/logs", id)))
+                .respond_with(ResponseTemplate::new(200).set_body_string(log))
+                .expect(expected_requests)
+                .mount(&server)
+                .await;
+        }
+
+        let repo_config: RepoConfig =
+            toml::from_str("[build]\njob_pattern = \"Build\"\ntool = \"tsc\"\n").unwrap();
+        let command = command_from_type(CommandType::Build, &repo_config).unwrap();
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let repo = Repository {
+            owner: "org".to_string(),
+            name: "repo".to_string(),
+            hostname: "github.com".to_string(),
+        };
+        let check_runs: Vec<_> = (1..=3).map(|id| check_run(id, "Build")).collect();
+
+        let processed = process_failed_check_runs(
+            &client,
+            &repo,
+            CommandMode::Single(command),
+            &check_runs,
+            LogOptions {
+                annotations: false,
+                tail: None,
+                max_download: u64::MAX,
+                save_logs: None,
+                cache_logs: false,
+                reparse: false,
+                fail_fast: true,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(processed.check_errors[&1].is_empty());
+        assert_eq!(processed.check_errors[&2].len(), 1);
+        assert!(!processed.check_errors.contains_key(&3));
+        assert_eq!(processed.skipped, HashSet::from([3]));
+    }
+
+    #[tokio::test]
+    async fn test_process_check_runs_falls_back_to_logs_without_junit_report() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/runs/10/artifacts"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "artifacts": [{ "id": 5, "name": "junit" }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/artifacts/5/zip"))
+            .respond_with(ResponseTemplate::new(410))
+            .expect(1)
+            .mount(&server)
+            .await;
+        for id in 1..=2 {
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/org/repo/actions/jobs/{}/logs", id)))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "2021-05-04T18:24:29.000Z FAIL src/a.test.ts\n\
+                     2021-05-04T18:24:29.000Z   ● a › works\n\
+                     2021-05-04T18:24:29.000Z PASS src/a.test.ts\n",
+                ))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let repo_config: RepoConfig = toml::from_str(
+            "[test]\njob_pattern = \"Test\"\ntool = \"jest\"\njunit_artifact = \"junit\"\n",
+        )
+        .unwrap();
+        let command = command_from_type(CommandType::Test, &repo_config).unwrap();
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let repo = Repository {
+            owner: "org".to_string(),
+            name: "repo".to_string(),
+            hostname: "github.com".to_string(),
+        };
+        let check_runs: Vec<_> = (1..=2)
+            .map(|id| SimpleCheckRun {
+                url: Some(format!(
+                    "https://github.com/org/repo/actions/runs/10/job/{}",
+                    id
+                )),
+                ..check_run(id, "Test")
+            })
+            .collect();
+
+        let processed = process_failed_check_runs(
+            &client,
+            &repo,
+            CommandMode::Single(command),
+            &check_runs,
+            LogOptions {
+                annotations: false,
+                tail: None,
+                max_download: u64::MAX,
+                save_logs: None,
+                cache_logs: false,
+                reparse: false,
+                fail_fast: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        for id in 1..=2 {
+            assert_eq!(processed.check_errors[&id][0].path, "src/a.test.ts");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_print_all_errors_of_read_checks() {
+        use clap::Parser;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // Only the log is served, so reading the checks again would fail
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/jobs/1/logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "2021-05-04T18:24:29.000Z FAIL src/a.test.ts\n\
+                 2021-05-04T18:24:29.000Z   ● a › works\n\
+                 2021-05-04T18:24:29.000Z PASS src/a.test.ts\n",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cli = Cli::parse_from(["ght", "check", "--watch-until-green"]);
+        let repo_config: RepoConfig =
+            toml::from_str("[test]\njob_pattern = \"Test\"\ntool = \"jest\"\n").unwrap();
+        let repo = Repository {
+            owner: "org".to_string(),
+            name: "repo".to_string(),
+            hostname: "github.com".to_string(),
+        };
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let context = CheckContext {
+            repo_config: &repo_config,
+            repo: &repo,
+            branch: "feature",
+            client: &client,
+        };
+        let pr_checks = PullRequestChecks {
+            head_sha: "abc123".to_string(),
+            check_runs: vec![check_run(1, "Test")],
+        };
+
+        let outcome = print_all_errors(&cli, false, OutputMode::Errors, &context, Some(pr_checks))
+            .await
+            .unwrap();
+        assert_eq!(outcome, CheckOutcome::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_process_required_timed_out_check_run() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/jobs/1/logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "2021-05-04T18:24:29.000Z FAIL src/a.test.ts\n\
+                 2021-05-04T18:24:29.000Z   ● a › works\n\
+                 2021-05-04T18:24:29.000Z PASS src/a.test.ts\n\
+                 2021-05-04T18:54:29.000Z ##[error]The job running on runner has exceeded the maximum execution time of 30 minutes.\n",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let repo_config: RepoConfig =
+            toml::from_str("[test]\njob_pattern = \"Test\"\ntool = \"jest\"\n").unwrap();
+        let command = command_from_type(CommandType::Test, &repo_config).unwrap();
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let repo = Repository {
+            owner: "org".to_string(),
+            name: "repo".to_string(),
+            hostname: "github.com".to_string(),
+        };
+        let timed_out = SimpleCheckRun {
+            conclusion: Some(CheckConclusionState::TimedOut),
+            is_required: true,
+            ..check_run(1, "Test")
+        };
+
+        let (failed_check_runs, _, _) =
+            filter_check_runs(command.as_ref(), &[timed_out], false, true);
+        let processed = process_failed_check_runs(
+            &client,
+            &repo,
+            CommandMode::Single(command),
+            &failed_check_runs,
+            LogOptions {
+                annotations: false,
+                tail: None,
+                max_download: u64::MAX,
+                save_logs: None,
+                cache_logs: false,
+                reparse: false,
+                fail_fast: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(processed.check_errors[&1][0].path, "src/a.test.ts");
+    }
+
+    #[test]
+    fn test_failed_checks_have_commands_without_all_sections() {
+        let repo_config: RepoConfig =
+            toml::from_str("[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n").unwrap();
+        let commands: Vec<_> = configured_commands(&repo_config)
+            .into_iter()
+            .map(|(command_type, _)| command_type)
+            .collect();
+        assert_eq!(commands, vec![CommandType::Lint]);
+
+        assert!(failed_checks_have_commands(
+            &repo_config,
+            &[check_run(1, "Lint")]
+        ));
+        assert!(!failed_checks_have_commands(
+            &repo_config,
+            &[check_run(1, "Test")]
+        ));
+    }
+
+    #[test]
+    fn test_check_run_pick_keeps_answer() {
+        use clap::Parser;
+
+        let cli = Cli::parse_from(["ght", "test"]);
+        let mut check_run_pick = CheckRunPick {
+            picked: Some(Some("Lint".to_string())),
+        };
+        // Check runs of a retry, read again after the job was picked
+        let mut check_runs = vec![check_run(3, "Build"), check_run(4, "Lint")];
+        check_run_pick.retain_picked(&cli, &mut check_runs).unwrap();
+        assert_eq!(
+            check_runs.iter().map(|run| run.id).collect::<Vec<_>>(),
+            vec![4]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_join_parse_tasks_skips_panicked() {
+        fn parse(log: &'static str) -> Result<ParsedLog> {
+            let path = log.split(':').nth(1).expect("no path in log");
+            Ok(ParsedLog {
+                errors: vec![check_error(path)],
+                tail: None,
+                failure_lines: Vec::new(),
+            })
+        }
+
+        let check_runs = vec![check_run(1, "Build"), check_run(2, "Lint")];
+        let parse_tasks = vec![
+            (1, tokio::task::spawn_blocking(|| parse("error:a.ts"))),
+            (2, tokio::task::spawn_blocking(|| parse("malformed"))),
+        ];
+
+        let parsed_logs = join_parse_tasks(parse_tasks, &check_runs).await.unwrap();
+        assert_eq!(
+            parsed_logs,
+            vec![(
+                1,
+                ParsedLog {
+                    errors: vec![check_error("a.ts")],
+                    tail: None,
+                    failure_lines: Vec::new(),
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_to_sections_sorted_by_name_then_id() {
+        let check_runs = vec![
+            check_run(3, "Unit tests (2)"),
+            check_run(2, "Lint"),
+            check_run(5, "Unit tests (1)"),
+            check_run(1, "Unit tests (1)"),
+        ];
+        let mut check_run_errors = HashMap::from([
+            (1, vec![check_error("a.test.ts")]),
+            (2, vec![check_error("b.ts")]),
+            (3, vec![check_error("c.test.ts")]),
+        ]);
+
+        let sections = to_sections(&check_runs, &mut check_run_errors);
+        let order: Vec<_> = sections
+            .iter()
+            .map(|s| (s.check_run.id, s.errors.len()))
+            .collect();
+        assert_eq!(order, vec![(2, 1), (1, 1), (5, 0), (3, 1)]);
+        assert!(check_run_errors.is_empty());
+    }
+
+    #[test]
+    fn test_apply_file_filter() {
+        let mut sections = vec![CheckRunErrors {
+            check_run: check_run(1, "Unit tests"),
+            errors: vec![
+                check_error("packages/web/src/a.test.ts"),
+                check_error("packages/api/src/b.test.ts"),
+                check_error("packages/web/c.test.ts"),
+            ],
+        }];
+        let file_filter =
+            build_file_filter(&["packages/web/**".to_string(), "**/b.test.ts".to_string()])
+                .unwrap()
+                .unwrap();
+
+        apply_file_filter(&mut sections, &file_filter);
+        let paths: Vec<_> = sections[0].errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "packages/web/src/a.test.ts",
+                "packages/api/src/b.test.ts",
+                "packages/web/c.test.ts"
+            ]
+        );
+
+        let file_filter = build_file_filter(&["packages/web/src/**".to_string()])
+            .unwrap()
+            .unwrap();
+        apply_file_filter(&mut sections, &file_filter);
+        assert_eq!(
+            sections[0].errors,
+            vec![check_error("packages/web/src/a.test.ts")]
+        );
+    }
+
+    #[test]
+    fn test_matching_command_names() {
+        let repo_config: RepoConfig = toml::from_str(
+            r#"
+            [test]
+            job_pattern = "(Unit|Integration) tests"
+            tool = "jest"
+
+            [lint]
+            job_pattern = "Lint|Typecheck and lint"
+            tool = "eslint"
+
+            [build]
+            job_pattern = "Typecheck"
+            tool = "tsc"
+            "#,
+        )
+        .unwrap();
+        let commands: Vec<_> = [CommandType::Test, CommandType::Lint, CommandType::Build]
+            .iter()
+            .map(|&command_type| command_from_type(command_type, &repo_config).unwrap())
+            .collect();
+        let commands: Vec<_> = commands
+            .iter()
+            .map(|c| c.as_ref() as &dyn Command)
+            .collect();
+
+        let names = |name: &str| matching_command_names(&check_run(1, name), &commands);
+        assert_eq!(names("Unit tests (1)"), vec!["test"]);
+        assert_eq!(names("Typecheck and lint"), vec!["lint", "build"]);
+        assert!(names("Deploy preview").is_empty());
+    }
+
+    #[test]
+    fn test_matches_app() {
+        let mut repo_config: RepoConfig =
+            toml::from_str("[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n").unwrap();
+        let check_run_of_app = |app: Option<&str>| SimpleCheckRun {
+            app: app.map(String::from),
+            is_required: false,
+            ..check_run(1, "Lint")
+        };
+        let matches = |repo_config: &RepoConfig, app: Option<&str>| {
+            LintCommand::from_repo_config(repo_config)
+                .unwrap()
+                .config()
+                .matches_check_run(&check_run_of_app(app))
+        };
+
+        assert!(matches(&repo_config, Some("github-actions")));
+        assert!(!matches(&repo_config, Some("circleci-checks")));
+        assert!(matches(&repo_config, None));
+
+        repo_config.set_app("circleci-checks");
+        assert!(!matches(&repo_config, Some("github-actions")));
+        assert!(matches(&repo_config, Some("circleci-checks")));
+
+        repo_config.set_app(ANY_APP);
+        assert!(matches(&repo_config, Some("circleci-checks")));
+    }
+
+    #[test]
+    fn test_unparsed_check_runs() {
+        let sections = vec![
+            CheckRunErrors {
+                check_run: check_run(1, "Unit tests (1)"),
+                errors: vec![check_error("src/a.test.ts")],
+            },
+            CheckRunErrors {
+                check_run: check_run(2, "Unit tests (2)"),
+                errors: vec![],
+            },
+        ];
+        let ids: Vec<_> = unparsed_check_runs(&sections)
+            .iter()
+            .map(|cr| cr.id)
+            .collect();
+        assert_eq!(ids, vec![2]);
+    }
+
+    #[test]
+    fn test_log_tail() {
+        let log = "line 1\nline 2\r\nline 3\n";
+        assert_eq!(log_tail(log, 2), vec!["line 2", "line 3"]);
+        assert_eq!(log_tail(log, 10), vec!["line 1", "line 2", "line 3"]);
+        assert!(log_tail(log, 0).is_empty());
+    }
+
+    #[test]
+    fn test_write_ndjson_line() {
+        let record = ErrorRecord {
+            schema_version: JSON_SCHEMA_VERSION,
+            check: "Unit tests",
+            path: "src/a.test.ts",
+            lines: vec![strip_ansi("\x1b[1m● a > fails\x1b[22m"), "".to_string()],
+        };
+        let mut output = Vec::new();
+        write_ndjson_line(&mut output, &record).unwrap();
+        let record = FileRecord {
+            schema_version: JSON_SCHEMA_VERSION,
+            path: "src/b.ts",
+        };
+        write_ndjson_line(&mut output, &record).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                serde_json::json!({
+                    "schemaVersion": 2,
+                    "check": "Unit tests",
+                    "path": "src/a.test.ts",
+                    "lines": ["● a > fails", ""]
+                }),
+                serde_json::json!({ "schemaVersion": 2, "path": "src/b.ts" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_output() {
+        let sections = vec![
+            CheckRunErrors {
+                check_run: check_run(1, "Unit tests (1)"),
+                errors: vec![check_error("src/a.test.ts"), check_error("src/b.test.ts")],
+            },
+            CheckRunErrors {
+                check_run: check_run(2, "Unit tests (2)"),
+                errors: vec![],
+            },
+            CheckRunErrors {
+                check_run: check_run(3, "Unit tests (3)"),
+                errors: vec![check_error("src/a.test.ts")],
+            },
+        ];
+
+        let output =
+            serde_json::to_value(json_output(&sections, "abc123", JsonOutcome::Failed)).unwrap();
+        assert_eq!(
+            output,
+            serde_json::json!({
+                "schemaVersion": 2,
+                "headSha": "abc123",
+                "outcome": "failed",
+                "checks": [
+                    {
+                        "name": "Unit tests (1)",
+                        "url": null,
+                        "errors": [
+                            { "path": "src/a.test.ts", "lines": ["src/a.test.ts"] },
+                            { "path": "src/b.test.ts", "lines": ["src/b.test.ts"] }
+                        ]
+                    },
+                    {
+                        "name": "Unit tests (3)",
+                        "url": null,
+                        "errors": [{ "path": "src/a.test.ts", "lines": ["src/a.test.ts"] }]
+                    }
+                ]
+            })
+        );
+        assert_eq!(
+            errored_files(&sections),
+            vec!["src/a.test.ts", "src/b.test.ts"]
+        );
+    }
+
+    #[test]
+    fn test_json_output_without_errors() {
+        let output =
+            serde_json::to_value(json_output(&[], "abc123", JsonOutcome::NoMatchingChecks))
+                .unwrap();
+        assert_eq!(
+            output,
+            serde_json::json!({
+                "schemaVersion": 2,
+                "headSha": "abc123",
+                "outcome": "noMatchingChecks",
+                "checks": []
+            })
+        );
+    }
+
+    #[test]
+    fn test_errored_files_sorted() {
+        let sections = vec![
+            CheckRunErrors {
+                check_run: check_run(1, "Unit tests (1)"),
+                errors: vec![check_error("src/z.test.ts"), check_error("src/b.test.ts")],
+            },
+            CheckRunErrors {
+                check_run: check_run(2, "Unit tests (2)"),
+                errors: vec![check_error("src/a.test.ts"), check_error("src/z.test.ts")],
+            },
+        ];
+        assert_eq!(
+            errored_files(&sections),
+            vec!["src/a.test.ts", "src/b.test.ts", "src/z.test.ts"]
+        );
+    }
+
+    #[test]
+    fn test_markdown_errors() {
+        let sections = vec![
+            CheckRunErrors {
+                check_run: SimpleCheckRun {
+                    url: Some("https://github.com/org/repo/actions/runs/1/job/2".to_string()),
+                    ..check_run(2, "Build <web>")
+                },
+                errors: vec![CheckError {
+                    path: "src/index.ts".to_string(),
+                    lines: vec![
+                        "\u{1b}[31msrc/index.ts(3,21): error TS2322\u{1b}[0m".to_string(),
+                        "```".to_string(),
+                    ],
+                }],
+            },
+            CheckRunErrors {
+                check_run: check_run(3, "Lint"),
+                errors: vec![CheckError {
+                    path: "src/a.ts".to_string(),
+                    lines: vec!["src/a.ts".to_string()],
+                }],
+            },
+        ];
+
+        assert_eq!(
+            markdown_errors(&sections),
+            r#"<details>
+<summary>Build &lt;web&gt; (<a href="https://github.com/org/repo/actions/runs/1/job/2">logs</a>)</summary>
+
+````
+src/index.ts(3,21): error TS2322
+```
+````
+
+</details>
+
+<details>
+<summary>Lint</summary>
+
+```
+src/a.ts
+```
+
+</details>
+
+"#
+        );
+    }
+
+    #[test]
+    fn test_keep_first_lines() {
+        let mut sections = vec![CheckRunErrors {
+            check_run: check_run(1, "Build"),
+            errors: vec![CheckError {
+                path: "src/index.ts".to_string(),
+                lines: vec![
+                    "src/index.ts(3,21): error TS2769: No overload matches this call.".to_string(),
+                    "  Overload 1 of 2, '(value: string): void', gave the following error."
+                        .to_string(),
+                    "    Argument of type 'number' is not assignable to parameter of type 'string'."
+                        .to_string(),
+                ],
+            }],
+        }];
+        keep_first_lines(&mut sections);
+        assert_eq!(
+            sections[0].errors[0].lines,
+            vec!["src/index.ts(3,21): error TS2769: No overload matches this call."]
+        );
+    }
+
+    #[test]
+    fn test_file_failure_counts() {
+        let repo_config: RepoConfig =
+            toml::from_str("[test]\njob_pattern = \"Test\"\ntool = \"jest\"\n").unwrap();
+        let command = TestCommand::from_repo_config(&repo_config).unwrap();
+        let error = |path: &str, lines: &[&str]| CheckError {
+            path: path.to_string(),
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+        };
+        let sections = vec![
+            CheckRunErrors {
+                check_run: check_run(1, "Unit tests (1)"),
+                errors: vec![
+                    error("src/a.test.ts", &["FAIL  src/a.test.ts", " ● a › fails"]),
+                    error(
+                        "src/b.test.ts",
+                        &["FAIL  src/b.test.ts", " ● b › fails", " ● b › fails too"],
+                    ),
+                ],
+            },
+            CheckRunErrors {
+                check_run: check_run(2, "Unit tests (2)"),
+                errors: vec![
+                    error("src/c.test.ts", &["FAIL  src/c.test.ts", " ● c › fails"]),
+                    error(
+                        "src/a.test.ts",
+                        &["FAIL  src/a.test.ts", " ● a › fails too"],
+                    ),
+                ],
+            },
+        ];
+
+        assert_eq!(
+            file_failure_counts(&command, &sections),
+            vec![
+                ("src/a.test.ts", 2),
+                ("src/b.test.ts", 2),
+                ("src/c.test.ts", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_templated_issues() {
+        let repo_config: RepoConfig =
+            toml::from_str("[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n").unwrap();
+        let command = LintCommand::from_repo_config(&repo_config).unwrap();
+        let sections = vec![CheckRunErrors {
+            check_run: check_run(1, "Lint"),
+            errors: vec![
+                CheckError {
+                    path: "src/b.ts".to_string(),
+                    lines: vec![
+                        "src/b.ts".to_string(),
+                        "  10:3  error  'b' is defined but never used  no-unused-vars".to_string(),
+                        "\u{1b}[33m  2:7  warning  Unexpected any  @typescript-eslint/no-explicit-any\u{1b}[0m"
+                            .to_string(),
+                    ],
+                },
+                CheckError {
+                    path: "src/a.ts".to_string(),
+                    lines: vec!["src/a.ts".to_string()],
+                },
+            ],
+        }];
+        let template = OutputTemplate::parse("{path}:{line}:{col}: {message} [{check}]").unwrap();
+
+        assert_eq!(
+            templated_issues(&command, &sections, &template),
+            vec![
+                "src/b.ts:10:3: 'b' is defined but never used  no-unused-vars [Lint]",
+                "src/b.ts:2:7: Unexpected any  @typescript-eslint/no-explicit-any [Lint]",
+                "src/a.ts::: src/a.ts [Lint]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_locations() {
+        let repo_config: RepoConfig =
+            toml::from_str("[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n").unwrap();
+        let command = LintCommand::from_repo_config(&repo_config).unwrap();
+        let sections = vec![CheckRunErrors {
+            check_run: check_run(1, "Lint"),
+            errors: vec![
+                CheckError {
+                    path: "src/b.ts".to_string(),
+                    lines: vec![
+                        "src/b.ts".to_string(),
+                        "  10:3  error  'b' is defined but never used  no-unused-vars".to_string(),
+                        "  2:7  warning  Unexpected any  @typescript-eslint/no-explicit-any"
+                            .to_string(),
+                    ],
+                },
+                CheckError {
+                    path: "src/a.ts".to_string(),
+                    lines: vec!["src/a.ts".to_string()],
+                },
+                CheckError {
+                    path: "src/b.ts".to_string(),
+                    lines: vec![
+                        "  2:7  warning  Unexpected any  @typescript-eslint/no-explicit-any"
+                            .to_string(),
+                    ],
+                },
+            ],
+        }];
+
+        let locations: Vec<_> = error_locations(&command, &sections)
+            .iter()
+            .map(|location| location.to_string())
+            .collect();
+        assert_eq!(locations, vec!["src/a.ts", "src/b.ts:2:7", "src/b.ts:10:3"]);
+    }
+
+    #[test]
+    fn test_group_by_suite() {
+        let repo_config: RepoConfig =
+            toml::from_str("[test]\njob_pattern = \"Test\"\ntool = \"jest\"\n").unwrap();
+        let command = command_from_type(CommandType::Test, &repo_config).unwrap();
+        let error = |path: &str, lines: &[&str]| CheckError {
+            path: path.to_string(),
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+        };
+        let sections = vec![
+            CheckRunErrors {
+                check_run: check_run(1, "Test (1/2)"),
+                errors: vec![
+                    error(
+                        "src/a.test.ts",
+                        &[
+                            "FAIL src/a.test.ts",
+                            "  ● parse › rejects empty input",
+                            "  ● format › dates › uses the locale",
+                            "  ● parse › rejects invalid input",
+                        ],
+                    ),
+                    error(
+                        "src/b.test.ts",
+                        &["FAIL src/b.test.ts", "  ● Test suite failed to run"],
+                    ),
+                ],
+            },
+            CheckRunErrors {
+                check_run: check_run(2, "Test (2/2)"),
+                errors: vec![error(
+                    "src/a.test.ts",
+                    &["FAIL src/a.test.ts", "  ● parse › rejects empty input"],
+                )],
+            },
+        ];
+
+        assert_eq!(
+            group_by_suite(command.as_ref(), &sections),
+            vec![
+                SuiteGroups {
+                    path: "src/a.test.ts".to_string(),
+                    suites: vec![
+                        (
+                            Some("parse".to_string()),
+                            vec![
+                                "rejects empty input".to_string(),
+                                "rejects invalid input".to_string()
+                            ]
+                        ),
+                        (
+                            Some("format › dates".to_string()),
+                            vec!["uses the locale".to_string()]
+                        ),
+                    ],
+                },
+                SuiteGroups {
+                    path: "src/b.test.ts".to_string(),
+                    suites: vec![(None, vec!["Test suite failed to run".to_string()])],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_rule() {
+        let repo_config: RepoConfig =
+            toml::from_str("[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n").unwrap();
+        let command = command_from_type(CommandType::Lint, &repo_config).unwrap();
+        let error = |path: &str, lines: &[&str]| CheckError {
+            path: path.to_string(),
+            lines: std::iter::once(path)
+                .chain(lines.iter().copied())
+                .map(String::from)
+                .collect(),
+        };
+        let sections = vec![CheckRunErrors {
+            check_run: check_run(1, "Lint"),
+            errors: vec![
+                error(
+                    "src/a.ts",
+                    &[
+                        "  1:10  error  'foo' is defined but never used  no-unused-vars",
+                        "  2:1   error  Unexpected any. Specify a different type  @typescript-eslint/no-explicit-any",
+                        "  8:3   error  Unexpected any. Specify a different type  @typescript-eslint/no-explicit-any",
+                    ],
+                ),
+                error(
+                    "src/b.ts",
+                    &["  3:7  warning  Unexpected any. Specify a different type  @typescript-eslint/no-explicit-any"],
+                ),
+            ],
+        }];
+
+        assert_eq!(
+            group_by_rule(command.as_ref(), &sections),
+            vec![
+                RuleGroup {
+                    rule: "@typescript-eslint/no-explicit-any".to_string(),
+                    count: 3,
+                    files: vec!["src/a.ts".to_string(), "src/b.ts".to_string()],
+                },
+                RuleGroup {
+                    rule: "no-unused-vars".to_string(),
+                    count: 1,
+                    files: vec!["src/a.ts".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_shards() {
+        let sections = vec![
+            CheckRunErrors {
+                check_run: check_run(1, "Lint"),
+                errors: vec![check_error("src/lint.ts")],
+            },
+            CheckRunErrors {
+                check_run: check_run(2, "Unit tests (1/4)"),
+                errors: vec![check_error("src/a.test.ts"), check_error("src/b.test.ts")],
+            },
+            CheckRunErrors {
+                check_run: check_run(3, "Unit tests (3/4)"),
+                errors: vec![check_error("src/b.test.ts"), check_error("src/c.test.ts")],
+            },
+        ];
+        let shard_pattern = Regex::new(r" \(\d+/\d+\)").unwrap();
+
+        let merged = merge_shards(sections, &shard_pattern);
+        let names: Vec<_> = merged.iter().map(|s| s.check_run.name.as_str()).collect();
+        assert_eq!(names, vec!["Lint", "Unit tests"]);
+        assert_eq!(merged[1].check_run.id, 2);
+        assert_eq!(
+            merged[1].errors,
+            vec![
+                check_error("src/a.test.ts"),
+                check_error("src/b.test.ts"),
+                check_error("src/c.test.ts")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_file_filter() {
+        assert!(build_file_filter(&[]).unwrap().is_none());
+        assert!(build_file_filter(&["src/[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_run_post_parse_command() {
+        let sections = vec![CheckRunErrors {
+            check_run: check_run(1, "Unit tests"),
+            errors: vec![check_error("dist/a.test.js")],
+        }];
+
+        let sections = run_post_parse_command(
+            r#"sed -e 's/dist\/\([a-z.]*\)\.js/src\/\1.ts/g'"#,
+            sections,
+            "abc",
+        )
+        .unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].check_run.id, 1);
+        assert_eq!(sections[0].errors, vec![check_error("src/a.test.ts")]);
+    }
+
+    #[test]
+    fn test_read_post_parse_output() {
+        let sections = || {
+            vec![CheckRunErrors {
+                check_run: check_run(1, "Unit tests"),
+                errors: vec![check_error("src/a.test.ts")],
+            }]
+        };
+
+        let output = br#"{"checks": [{"name": "Unit tests", "errors": []}]}"#;
+        let sections_out = read_post_parse_output(sections(), output).unwrap();
+        assert!(sections_out[0].errors.is_empty());
+
+        assert!(read_post_parse_output(sections(), b"not json").is_err());
+        assert!(
+            read_post_parse_output(sections(), br#"{"checks": [{"name": "Unit tests"}]}"#).is_err()
+        );
+        let output = br#"{"checks": [{"name": "Lint", "errors": []}]}"#;
+        assert!(read_post_parse_output(sections(), output).is_err());
+    }
+
+    #[test]
+    fn test_check_outcome() {
+        let mut check_run = check_run(1, "Unit tests");
+        assert_eq!(check_outcome(&check_run), CheckOutcome::Failed);
+        check_run.conclusion = Some(CheckConclusionState::Skipped);
+        assert_eq!(check_outcome(&check_run), CheckOutcome::Passed);
+        check_run.conclusion = Some(CheckConclusionState::TimedOut);
+        assert_eq!(check_outcome(&check_run), CheckOutcome::Failed);
+        check_run.conclusion = None;
+        assert_eq!(check_outcome(&check_run), CheckOutcome::Pending);
+    }
+
+    #[test]
+    fn test_check_max_age() {
+        let now = Utc::now();
+        let completed = |hours_ago: i64| SimpleCheckRun {
+            completed_at: Some(now - chrono::Duration::hours(hours_ago)),
+            ..check_run(1, "Unit tests")
+        };
+        let pr_checks = |check_runs| PullRequestChecks {
+            head_sha: "abc".to_string(),
+            check_runs,
+        };
+        let max_age = chrono::Duration::hours(24);
+
+        let fresh = pr_checks(vec![completed(72), completed(2)]);
+        assert!(check_max_age(&fresh, max_age, false, now).is_ok());
+
+        let stale = pr_checks(vec![completed(72), completed(48)]);
+        let err = check_max_age(&stale, max_age, false, now).unwrap_err();
+        assert!(err.to_string().contains("last completed 2 days ago"));
+        assert!(check_max_age(&stale, max_age, true, now).is_ok());
+
+        let pending = pr_checks(vec![check_run(1, "Unit tests")]);
+        assert!(check_max_age(&pending, max_age, false, now).is_ok());
+    }
+
+    #[test]
+    fn test_summarize_errors() {
+        let repo_config: RepoConfig = toml::from_str(
+            "[test]\njob_pattern = \"Test\"\ntool = \"jest\"\n[lint]\njob_pattern = \"Lint\"\ntool = \"eslint\"\n",
+        )
+        .unwrap();
+        let lint_error = CheckError {
+            path: "src/a.ts".to_string(),
+            lines: vec![
+                "src/a.ts".to_string(),
+                "  1:7  error  'a' is assigned a value but never used  no-unused-vars".to_string(),
+                "  2:7  warning  Unexpected console statement  no-console".to_string(),
+            ],
+        };
+        let errors = [&lint_error, &lint_error];
+
+        let test_command = TestCommand::from_repo_config(&repo_config).unwrap();
+        assert_eq!(test_command.summarize_errors(&errors), "1 file");
+        let lint_command = LintCommand::from_repo_config(&repo_config).unwrap();
+        assert_eq!(lint_command.summarize_errors(&errors), "4 issues");
+    }
+
+    #[test]
+    fn test_is_logs_not_found() {
+        let not_found: eyre::Report =
+            GithubApiError::ErrorResponse(reqwest::StatusCode::NOT_FOUND, String::new()).into();
+        let forbidden: eyre::Report =
+            GithubApiError::ErrorResponse(reqwest::StatusCode::FORBIDDEN, String::new()).into();
+        assert!(is_logs_not_found(&not_found));
+        assert!(!is_logs_not_found(&forbidden));
+        assert!(!is_logs_not_found(&eyre::eyre!("other error")));
+    }
+
+    #[test]
+    fn test_parse_log_bytes_with_invalid_utf8() {
+        let repo_config: RepoConfig =
+            toml::from_str("[build]\njob_pattern = \"Build\"\ntool = \"tsc\"\n").unwrap();
+        let command = BuildCommand::from_repo_config(&repo_config).unwrap();
+        let mut log = b"2023-06-26T16:57:36.5365262Z \xff\x1b(B progress\n".to_vec();
+        log.extend_from_slice(b"2023-06-26T16:57:36.5365262Z ##[error]src/index.ts(10,3): error TS2322: Type 'number' is not assignable to type 'string'.\n");
+
+        let parsed_log = parse_log_bytes(&command, &log, Some(5)).unwrap();
+        assert_eq!(
+            parsed_log.errors,
+            vec![CheckError {
+                path: "src/index.ts".to_string(),
+                lines: vec!["src/index.ts(10,3): error TS2322: Type 'number' is not assignable to type 'string'.".to_string()],
+            }]
+        );
+        assert_eq!(parsed_log.tail, None);
+    }
+
+    #[test]
+    fn test_parse_log_bytes_joins_working_dir_to_file_paths_only() {
+        let repo_config: RepoConfig = toml::from_str(
+            "[build]\njob_pattern = \"Build\"\ntool = \"tsc\"\nworking_dir = \"web\"\n\
+             [test]\njob_pattern = \"Test\"\ntool = \"gradle\"\nworking_dir = \"android\"\n",
+        )
+        .unwrap();
+        let build = command_from_type(CommandType::Build, &repo_config).unwrap();
+        let log = b"2023-06-26T16:57:36.5365262Z ##[error]src/index.ts(10,3): error TS2322: Type 'number' is not assignable to type 'string'.\n";
+        let paths: Vec<_> = parse_log_bytes(build.as_ref(), log, None)
+            .unwrap()
+            .errors
+            .into_iter()
+            .map(|error| error.path)
+            .collect();
+        assert_eq!(paths, vec!["web/src/index.ts"]);
+
+        let test = command_from_type(CommandType::Test, &repo_config).unwrap();
+        let log = b"2024-03-01T10:00:00.2000000Z * What went wrong:\n\
+            2024-03-01T10:00:00.2000000Z Execution failed for task ':app:test'.\n\
+            2024-03-01T10:00:00.2000000Z\n";
+        let paths: Vec<_> = parse_log_bytes(test.as_ref(), log, None)
+            .unwrap()
+            .errors
+            .into_iter()
+            .map(|error| error.path)
+            .collect();
+        assert_eq!(paths, vec![":app:test"]);
+    }
+
+    #[test]
+    fn test_parse_log_bytes_with_failure_markers() {
+        let repo_config: RepoConfig =
+            toml::from_str("[build]\njob_pattern = \"Build\"\ntool = \"tsc\"\n").unwrap();
+        let command = BuildCommand::from_repo_config(&repo_config).unwrap();
+        let log = b"2023-06-26T16:57:36.5365262Z > turbo run build
+2023-06-26T16:57:36.5365262Z web:build: cache hit, replaying logs
+2023-06-26T16:57:36.5365262Z \x1b[31mweb:build: \xe2\x80\x89ELIFECYCLE\xe2\x80\x89 Command failed with exit code 1.\x1b[0m
+2023-06-26T16:57:36.5365262Z ##[error]Process completed with exit code 1.
+";
+
+        let parsed_log = parse_log_bytes(&command, log, Some(2)).unwrap();
+        assert!(parsed_log.errors.is_empty());
+        assert_eq!(
+            parsed_log.failure_lines,
+            vec![
+                "> turbo run build".to_string(),
+                "web:build: cache hit, replaying logs".to_string(),
+                "\x1b[31mweb:build: \u{2009}ELIFECYCLE\u{2009} Command failed with exit code 1.\x1b[0m".to_string(),
+                "##[error]Process completed with exit code 1.".to_string(),
+            ]
+        );
+        assert_eq!(parsed_log.tail.map(|tail| tail.len()), Some(2));
+    }
+
+    #[test]
+    fn test_log_file_name() {
+        assert_eq!(
+            log_file_name(&check_run(12, "Unit tests (1/4)")),
+            "Unit-tests-1-4-12.log"
+        );
+        assert_eq!(log_file_name(&check_run(3, "../lint")), "..-lint-3.log");
+    }
+}
@@ -0,0 +1,157 @@
+use eyre::Result;
+use regex::Regex;
+
+use crate::{
+    cli::Cli,
+    repo_config::{repo_config_schema, BuildTool, LintTool, RepoConfig, TestRunner, Tool},
+    setup::get_local_repo_config,
+    term::{bold, green, red},
+};
+
+pub fn print_config_schema() -> Result<()> {
+    let schema = repo_config_schema();
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+/// Names and descriptions of the tools of a section
+fn tool_descriptions<T: Tool>() -> Vec<(&'static str, &'static str)> {
+    T::NAMES
+        .iter()
+        .map(|(name, tool)| (*name, tool.description()))
+        .collect()
+}
+
+/// Print the values `tool` can have in each section of .ghtool.toml
+pub fn print_tools() -> Result<()> {
+    let sections = [
+        ("test", tool_descriptions::<TestRunner>()),
+        ("lint", tool_descriptions::<LintTool>()),
+        ("build", tool_descriptions::<BuildTool>()),
+    ];
+    let name_width = sections
+        .iter()
+        .flat_map(|(_, tools)| tools.iter().map(|(name, _)| name.len()))
+        .max()
+        .unwrap_or(0);
+
+    for (i, (section, tools)) in sections.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", bold(&format!("[{section}]")));
+        for (name, description) in tools {
+            println!("  {name:<name_width$}  {description}");
+        }
+    }
+    Ok(())
+}
+
+/// Whether the job_pattern of a config section matches a check name, and the shard part of the
+/// name if the section has a shard_pattern that matches too
+#[derive(Debug)]
+struct PatternMatch<'a> {
+    section: &'static str,
+    job_pattern: &'a Regex,
+    matches: bool,
+    shard: Option<&'a str>,
+}
+
+fn pattern_matches<'a>(repo_config: &'a RepoConfig, check: &'a str) -> Vec<PatternMatch<'a>> {
+    let sections = [
+        repo_config
+            .test
+            .as_ref()
+            .map(|c| ("test", &c.section.job_pattern, c.shard_pattern.as_ref())),
+        repo_config
+            .lint
+            .as_ref()
+            .map(|c| ("lint", &c.section.job_pattern, None)),
+        repo_config
+            .build
+            .as_ref()
+            .map(|c| ("build", &c.section.job_pattern, None)),
+    ];
+
+    sections
+        .into_iter()
+        .flatten()
+        .map(|(section, job_pattern, shard_pattern)| {
+            let matches = job_pattern.is_match(check);
+            PatternMatch {
+                section,
+                job_pattern,
+                matches,
+                shard: shard_pattern
+                    .filter(|_| matches)
+                    .and_then(|shard_pattern| shard_pattern.find(check))
+                    .map(|shard| shard.as_str()),
+            }
+        })
+        .collect()
+}
+
+/// Print which sections' job_pattern matches the check name. Only names are matched, as finding
+/// the app of a check run needs the GitHub API.
+pub fn test_pattern(cli: &Cli, check: &str) -> Result<()> {
+    let repo_config = get_local_repo_config(cli)?;
+    let pattern_matches = pattern_matches(&repo_config, check);
+    if pattern_matches.is_empty() {
+        eyre::bail!("No test, lint or build sections in config");
+    }
+
+    for pattern_match in &pattern_matches {
+        let status = if pattern_match.matches {
+            green("matches")
+        } else {
+            red("doesn't match")
+        };
+        let mut line = format!(
+            "{}: job_pattern {:?} {}",
+            bold(pattern_match.section),
+            pattern_match.job_pattern.as_str(),
+            status
+        );
+        if let Some(shard) = pattern_match.shard {
+            line.push_str(&format!(", shard {shard:?}"));
+        }
+        println!("{line}");
+    }
+
+    if !pattern_matches.iter().any(|m| m.matches) {
+        eprintln!("No job_pattern matches {check:?}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_pattern_matches() {
+        let repo_config: RepoConfig = toml::from_str(
+            r#"
+[test]
+job_pattern = "^Unit tests"
+tool = "jest"
+shard_pattern = " \\(\\d+/\\d+\\)"
+
+[lint]
+job_pattern = "^Lint"
+tool = "eslint"
+"#,
+        )
+        .unwrap();
+
+        let summary: Vec<_> = pattern_matches(&repo_config, "Unit tests (1/3)")
+            .into_iter()
+            .map(|m| (m.section, m.matches, m.shard))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("test", true, Some(" (1/3)")), ("lint", false, None)]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use eyre::Result;
+use http::StatusCode;
+
+use crate::{
+    cli::Cli,
+    github::{GithubApiError, GithubClient},
+    setup::{get_local_repo_config, get_repo_path, get_repository},
+    term::{bold, green, red},
+    token_store,
+};
+
+use super::get_token;
+
+/// Outcome of one check of the environment ghtool runs in
+#[derive(Debug, PartialEq)]
+enum Diagnosis {
+    Ok(String),
+    /// What is wrong, and how to fix it
+    Failed(String, String),
+    /// Not checked, e.g. because an earlier check failed
+    Skipped(String),
+}
+
+impl Diagnosis {
+    fn print(&self, name: &str) {
+        match self {
+            Diagnosis::Ok(description) => {
+                println!("{} {}: {}", green("✓"), bold(name), description)
+            }
+            Diagnosis::Failed(description, hint) => {
+                println!("{} {}: {}", red("✗"), bold(name), description);
+                println!("  {}", hint);
+            }
+            Diagnosis::Skipped(reason) => println!("- {}: {}", bold(name), reason),
+        }
+    }
+}
+
+/// Check what ghtool needs to work, printing a line for each check and how to fix the ones that
+/// fail
+pub async fn doctor(cli: &Cli) -> Result<()> {
+    let mut diagnoses = vec![("Git repository", git_repository(cli))];
+
+    let repository = get_repository(cli);
+    let hostname = repository
+        .as_ref()
+        .map_or(cli.host.clone(), |repo| repo.hostname.clone());
+    diagnoses.push((
+        "Remote",
+        match &repository {
+            Ok(repo) => Diagnosis::Ok(format!("{} on {}", repo, repo.hostname)),
+            Err(e) => Diagnosis::Failed(
+                e.to_string(),
+                "Add a remote named origin pointing to GitHub, or pass --repo owner/name".into(),
+            ),
+        },
+    ));
+
+    diagnoses.push((
+        "Config",
+        match get_local_repo_config(cli) {
+            Ok(_) => Diagnosis::Ok("valid".into()),
+            Err(e) => Diagnosis::Failed(
+                format!("{:#}", e),
+                "Add a .ghtool.toml to the repository root; see ght config schema for its fields"
+                    .into(),
+            ),
+        },
+    ));
+
+    diagnoses.push(("Keyring", keyring(&hostname)));
+
+    let token = get_token(cli, &hostname).await;
+    let (token_diagnosis, api_diagnosis) = match token {
+        Ok(token) => {
+            let client = GithubClient::new(&token, &hostname)?;
+            current_user_diagnoses(
+                client
+                    .get_current_user()
+                    .await
+                    .map(|user| user.viewer.login),
+            )
+        }
+        Err(e) => (
+            Diagnosis::Failed(
+                e.to_string(),
+                format!(
+                    "Run {}, set GH_TOKEN, or pass --token-from-gh to use the token of gh",
+                    bold("ght login")
+                ),
+            ),
+            Diagnosis::Skipped("no token to make requests with".into()),
+        ),
+    };
+    diagnoses.push(("Token", token_diagnosis));
+    diagnoses.push(("GitHub API", api_diagnosis));
+
+    for (name, diagnosis) in &diagnoses {
+        diagnosis.print(name);
+    }
+
+    let failed = diagnoses
+        .iter()
+        .filter(|(_, diagnosis)| matches!(diagnosis, Diagnosis::Failed(..)))
+        .count();
+    if failed > 0 {
+        eyre::bail!("{} of {} checks failed", failed, diagnoses.len());
+    }
+    Ok(())
+}
+
+fn git_repository(cli: &Cli) -> Diagnosis {
+    match get_repo_path() {
+        Ok(path) => Diagnosis::Ok(path.display().to_string()),
+        // Commands work outside of a repository with --repo
+        Err(_) if cli.repo.is_some() => Diagnosis::Skipped("not needed with --repo".into()),
+        Err(e) => Diagnosis::Failed(
+            e.to_string(),
+            "Run ght in a clone of the repository, or pass --repo owner/name and --branch".into(),
+        ),
+    }
+}
+
+/// Whether the system keyring, where ght login stores the token, can be read
+fn keyring(hostname: &str) -> Diagnosis {
+    match token_store::get_token(hostname) {
+        Ok(_) => Diagnosis::Ok(format!("has a token for {}", hostname)),
+        Err(keyring::Error::NoEntry) => Diagnosis::Ok(format!("no token for {}", hostname)),
+        Err(e) => Diagnosis::Failed(
+            format!("not available: {}", e),
+            "Set GH_TOKEN or pass --token-from-gh instead of storing the token with ght login"
+                .into(),
+        ),
+    }
+}
+
+/// Diagnoses of the token and of reaching the API, from getting the user the token is of
+fn current_user_diagnoses(current_user: Result<String, GithubApiError>) -> (Diagnosis, Diagnosis) {
+    let reachable = || Diagnosis::Ok("reachable".into());
+    match current_user {
+        Ok(login) => (Diagnosis::Ok(format!("valid, for {}", login)), reachable()),
+        Err(GithubApiError::ErrorResponse(StatusCode::UNAUTHORIZED, _)) => (
+            Diagnosis::Failed(
+                "invalid or expired".into(),
+                format!("Run {} again, or replace GH_TOKEN", bold("ght login")),
+            ),
+            reachable(),
+        ),
+        Err(e @ GithubApiError::ReqwestError(_)) => (
+            Diagnosis::Skipped("GitHub API can't be reached".into()),
+            Diagnosis::Failed(
+                e.to_string(),
+                "Check the network connection; behind a proxy, pass --proxy and --cainfo".into(),
+            ),
+        ),
+        Err(e) => (
+            Diagnosis::Failed(e.to_string(), "Check the token's permissions".into()),
+            reachable(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_current_user_diagnoses() {
+        assert_eq!(
+            current_user_diagnoses(Ok("octocat".to_string())),
+            (
+                Diagnosis::Ok("valid, for octocat".to_string()),
+                Diagnosis::Ok("reachable".to_string())
+            )
+        );
+
+        let (token, api) = current_user_diagnoses(Err(GithubApiError::ErrorResponse(
+            StatusCode::UNAUTHORIZED,
+            "Bad credentials".to_string(),
+        )));
+        assert!(matches!(token, Diagnosis::Failed(..)));
+        assert_eq!(api, Diagnosis::Ok("reachable".to_string()));
+    }
+}
@@ -0,0 +1,206 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::debug;
+
+use crate::commands::CheckError;
+
+lazy_static! {
+    /// Regex to match a timestamp and single space after it, if the line goes on
+    static ref TIMESTAMP: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z(?:\s|$)").unwrap();
+
+    /// Regex to match the first line of a diagnostic, with its file, position and category. Format
+    /// diagnostics have no position.
+    /// Example: src/index.ts:3:7 lint/correctness/noUnusedVariables  FIXABLE  ━━━━━━━━━━━━━━━━━━━━
+    /// Example: src/index.ts format ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+    static ref DIAGNOSTIC: Regex = Regex::new(
+        r"^(?P<path>[^\s:]+)(?::(?P<line>\d+):(?P<col>\d+))? (?P<category>[\w/]+)(?:\s+[A-Z]+)*\s+━+\s*$",
+    )
+    .unwrap();
+
+    /// Regex to match the lines after the diagnostics, with the counts and the command's status
+    /// Example: Checked 12 files in 5ms. No fixes applied.
+    /// Example: ci ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+    static ref SUMMARY: Regex =
+        Regex::new(r"^(?:Checked \d+ files? in |Found \d+ \w+\.|(?:ci|check|lint|format) ━+)")
+            .unwrap();
+}
+
+/// Line and column of the first line of a diagnostic
+pub fn issue_position(line: &str) -> Option<(u32, u32)> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = DIAGNOSTIC.captures(&line_no_ansi)?;
+    Some((
+        caps.name("line")?.as_str().parse().ok()?,
+        caps["col"].parse().ok()?,
+    ))
+}
+
+/// Rule of the first line of a lint diagnostic, e.g. lint/style/useConst
+pub fn issue_rule(line: &str) -> Option<String> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = DIAGNOSTIC.captures(&line_no_ansi)?;
+    Some(caps["category"].to_string()).filter(|category| category.starts_with("lint/"))
+}
+
+/// Category of a diagnostic's first line, which has no message of its own, e.g.
+/// lint/style/useConst
+pub fn issue_message(line: &str) -> Option<String> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = DIAGNOSTIC.captures(&line_no_ansi)?;
+    Some(caps["category"].to_string())
+}
+
+/// Whether the line is the message of a diagnostic of error severity, marked with ✖ rather than
+/// ⚠ or ℹ
+pub fn is_error_issue(line: &str) -> bool {
+    strip_ansi_escapes::strip_str(line)
+        .trim_start()
+        .starts_with('✖')
+}
+
+#[derive(Debug)]
+pub struct BiomeLogParser {
+    /// Lines of the diagnostic being parsed: its first line, message and code frame
+    current_error: Option<CheckError>,
+    all_errors: Vec<CheckError>,
+}
+
+impl BiomeLogParser {
+    pub fn new() -> Self {
+        BiomeLogParser {
+            current_error: None,
+            all_errors: Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, full_line: &str) {
+        let line = TIMESTAMP.replace(full_line, "");
+        let line_no_ansi = strip_ansi_escapes::strip_str(&line);
+
+        if let Some(caps) = DIAGNOSTIC.captures(&line_no_ansi) {
+            self.finish_error();
+            self.current_error = Some(CheckError {
+                path: caps["path"].to_string(),
+                lines: vec![line.to_string()],
+            });
+        } else if SUMMARY.is_match(&line_no_ansi) {
+            self.finish_error();
+        } else if let Some(error) = self.current_error.as_mut() {
+            error.lines.push(line.to_string());
+        }
+    }
+
+    /// Diagnostics are followed by empty lines, which aren't part of them
+    fn finish_error(&mut self) {
+        if let Some(mut error) = self.current_error.take() {
+            while error
+                .lines
+                .last()
+                .is_some_and(|line| line.trim().is_empty())
+            {
+                error.lines.pop();
+            }
+            self.all_errors.push(error);
+        }
+    }
+
+    pub fn parse(log: &str) -> Vec<CheckError> {
+        let mut parser = BiomeLogParser::new();
+
+        for line in log.lines() {
+            parser.parse_line(line);
+        }
+        parser.finish_error();
+
+        debug!(errors = parser.all_errors.len(), "parsed biome log");
+        parser.all_errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let logs = r#"2024-03-01T10:00:00.1000000Z > biome ci .
+2024-03-01T10:00:01.1000000Z src/index.ts:3:7 lint/correctness/noUnusedVariables  FIXABLE  ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z   ⚠ This variable is unused.
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z   > 3 │ const unused = 1;
+2024-03-01T10:00:01.1000000Z       │       ^^^^^^
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z src/utils/date.ts:12:3 lint/style/useConst  FIXABLE  ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z   ✖ This let declares a variable that is only assigned once.
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z   > 12 │   let offset = 0;
+2024-03-01T10:00:01.1000000Z        │   ^^^
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z src/utils/date.ts format ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z   ✖ File content differs from formatting output
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.2000000Z ci ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+2024-03-01T10:00:01.2000000Z
+2024-03-01T10:00:01.2000000Z   ✖ Some errors were emitted while running checks.
+2024-03-01T10:00:01.2000000Z
+2024-03-01T10:00:01.2000000Z Checked 24 files in 18ms. No fixes applied.
+2024-03-01T10:00:01.2000000Z Found 2 errors.
+2024-03-01T10:00:01.2000000Z Found 1 warning.
+2024-03-01T10:00:01.3000000Z ##[error]Process completed with exit code 1."#;
+
+        assert_eq!(
+            BiomeLogParser::parse(logs),
+            vec![
+                CheckError {
+                    path: "src/index.ts".to_string(),
+                    lines: vec![
+                        "src/index.ts:3:7 lint/correctness/noUnusedVariables  FIXABLE  ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".to_string(),
+                        "".to_string(),
+                        "  ⚠ This variable is unused.".to_string(),
+                        "".to_string(),
+                        "  > 3 │ const unused = 1;".to_string(),
+                        "      │       ^^^^^^".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "src/utils/date.ts".to_string(),
+                    lines: vec![
+                        "src/utils/date.ts:12:3 lint/style/useConst  FIXABLE  ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".to_string(),
+                        "".to_string(),
+                        "  ✖ This let declares a variable that is only assigned once.".to_string(),
+                        "".to_string(),
+                        "  > 12 │   let offset = 0;".to_string(),
+                        "       │   ^^^".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "src/utils/date.ts".to_string(),
+                    lines: vec![
+                        "src/utils/date.ts format ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".to_string(),
+                        "".to_string(),
+                        "  ✖ File content differs from formatting output".to_string(),
+                    ]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_issue_position_and_rule() {
+        let line = "src/utils/date.ts:12:3 lint/style/useConst  FIXABLE  ━━━━━━━━━━━━━━━━━━━━━━";
+        assert_eq!(issue_position(line), Some((12, 3)));
+        assert_eq!(issue_rule(line), Some("lint/style/useConst".to_string()));
+        assert_eq!(issue_message(line), Some("lint/style/useConst".to_string()));
+
+        let line = "src/utils/date.ts format ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━";
+        assert_eq!(issue_position(line), None);
+        assert_eq!(issue_rule(line), None);
+    }
+}
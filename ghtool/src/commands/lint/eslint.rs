@@ -1,5 +1,8 @@
+use std::borrow::Cow;
+
 use lazy_static::lazy_static;
 use regex::Regex;
+use tracing::debug;
 
 use crate::commands::CheckError;
 
@@ -14,20 +17,71 @@ lazy_static! {
     static ref TIMESTAMP: Regex =
         Regex::new(r"(?P<timestamp>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z)\s").unwrap();
 
-    /// Regex to match a path at the end of line
+    /// Regex to match an absolute path at the end of line, e.g. /home/runner/work/repo/repo/a.ts
+    /// or D:\a\repo\repo\a.ts on Windows
     static ref PATH: Regex = Regex::new(
-        r"\s(?P<path>/[a-zA-Z0-9._-]*/[a-zA-Z0-9./_-]*)$",
+        r"(?:^|\s)(?P<path>/[a-zA-Z0-9._-]*/[a-zA-Z0-9./_-]*|[a-zA-Z]:\\[a-zA-Z0-9._-]*\\[a-zA-Z0-9.\\_-]*)$",
+    )
+    .unwrap();
+
+    /// Regex to match a relative path making up the whole line
+    static ref RELATIVE_PATH: Regex =
+        Regex::new(r"^(?P<path>[a-zA-Z0-9_@-][a-zA-Z0-9./\\_@-]*\.[a-zA-Z0-9]+)$").unwrap();
+
+    /// Regex to match the package:task: prefix turbo adds to each line of a task's output,
+    /// optionally colored
+    static ref TASK_PREFIX: Regex = Regex::new(
+        r"^(?:\x1B\[\d+(?:;\d+)*m(?P<colored_package>[@\w./-]+):[\w.-]+:\s?\x1B\[\d+(?:;\d+)*m|(?P<package>[@\w./-]+):[\w.-]+:\s?)",
     )
     .unwrap();
 
+    /// Regex to match the rule id at the end of an eslint issue line
+    /// Example: 1:10 error Missing return type  @typescript-eslint/explicit-function-return-type
+    static ref ESLINT_RULE: Regex =
+        Regex::new(r"\s{2,}(?P<rule>(?:@[\w.-]+/)?[\w.-]+(?:/[\w.-]+)*)\s*$").unwrap();
+
     /// Regex to match eslint issue on a file line
     /// Example: 1:10 error Missing return type
     static ref ESLINT_ISSUE: Regex = Regex::new(
-        r"\d+:\d+\s+\b(warning|error)\b",
+        r"(?P<line>\d+):(?P<col>\d+)\s+\b(?P<severity>warning|error)\b",
     )
     .unwrap();
 }
 
+/// Id of the rule of an eslint issue line, if the line is one
+pub fn issue_rule(line: &str) -> Option<String> {
+    let line_no_ansi = String::from_utf8(strip_ansi_escapes::strip(line.as_bytes())).ok()?;
+    if !ESLINT_ISSUE.is_match(&line_no_ansi) {
+        return None;
+    }
+
+    ESLINT_RULE
+        .captures(&line_no_ansi)
+        .map(|caps| caps["rule"].to_string())
+}
+
+/// Whether the line is an eslint issue of error severity
+pub fn is_error_issue(line: &str) -> bool {
+    let line_no_ansi = strip_ansi_escapes::strip(line.as_bytes());
+    ESLINT_ISSUE
+        .captures(&String::from_utf8_lossy(&line_no_ansi))
+        .is_some_and(|caps| &caps["severity"] == "error")
+}
+
+/// Line and column of the issue on an eslint issue line
+pub fn issue_position(line: &str) -> Option<(u32, u32)> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let caps = ESLINT_ISSUE.captures(&line_no_ansi)?;
+    Some((caps["line"].parse().ok()?, caps["col"].parse().ok()?))
+}
+
+/// Message of an eslint issue line after its position and severity, with the rule
+pub fn issue_message(line: &str) -> Option<String> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(line);
+    let issue = ESLINT_ISSUE.find(&line_no_ansi)?;
+    Some(line_no_ansi[issue.end()..].trim().to_string())
+}
+
 #[derive(Debug)]
 pub struct EslintLogParser {
     state: State,
@@ -36,10 +90,15 @@ pub struct EslintLogParser {
     current_path_start_col: usize,
     seen_eslint_issue_for_current_path: bool,
     current_path_lines: usize,
+    path_lines_seen: usize,
+    /// Directory of workspace packages, with {package} in place of the package name
+    package_dir: Option<String>,
+    /// Package of the file currently being parsed
+    current_package: Option<String>,
 }
 
 impl EslintLogParser {
-    pub fn new() -> Self {
+    pub fn new(package_dir: Option<String>) -> Self {
         EslintLogParser {
             state: State::LookingForFile,
             current_path: None,
@@ -47,9 +106,35 @@ impl EslintLogParser {
             current_path_start_col: 0,
             current_path_lines: 0,
             seen_eslint_issue_for_current_path: false,
+            path_lines_seen: 0,
+            package_dir,
+            current_package: None,
         }
     }
 
+    /// Remove the timestamp and task prefix from a line of a workspace package's output. Returns
+    /// the package name without npm scope along with the line.
+    fn strip_task_prefix<'a>(&self, raw_line: &'a str) -> (Option<String>, Cow<'a, str>) {
+        let line = TIMESTAMP.replace(raw_line, "");
+        let Some(caps) = TASK_PREFIX.captures(&line) else {
+            return (None, line);
+        };
+
+        let package = caps
+            .name("colored_package")
+            .or_else(|| caps.name("package"))
+            .unwrap()
+            .as_str();
+        let package = package.rsplit('/').next().unwrap().to_string();
+        let prefix_end = caps.get(0).unwrap().end();
+        (Some(package), Cow::Owned(line[prefix_end..].to_string()))
+    }
+
+    fn package_path(&self, package: &str, path: &str) -> String {
+        let package_dir = self.package_dir.as_ref().unwrap();
+        format!("{}/{}", package_dir.replace("{package}", package), path)
+    }
+
     fn get_line_from_path_col(&self, line: &str) -> String {
         line.chars().skip(self.current_path_start_col).collect()
     }
@@ -60,19 +145,53 @@ impl EslintLogParser {
     }
 
     fn parse_line(&mut self, raw_line: &str) {
+        let (package, raw_line) = if self.package_dir.is_some() {
+            self.strip_task_prefix(raw_line)
+        } else {
+            (None, Cow::Borrowed(raw_line))
+        };
+
+        // Output of packages linted in parallel may be interleaved
+        if self.state == State::ParsingFile
+            && self.current_package.is_some()
+            && package != self.current_package
+        {
+            return;
+        }
+
         let line_no_ansi =
             String::from_utf8(strip_ansi_escapes::strip(raw_line.as_bytes())).unwrap();
 
         match self.state {
             State::LookingForFile => {
-                if let Some(caps) = PATH.captures(&line_no_ansi) {
+                let relative_path = package.as_ref().and_then(|package| {
+                    RELATIVE_PATH
+                        .captures(&line_no_ansi)
+                        .map(|caps| self.package_path(package, &caps["path"].replace('\\', "/")))
+                });
+
+                if let Some(path) = relative_path {
+                    self.path_lines_seen += 1;
+                    self.current_path_start_col = 0;
+                    self.current_path = Some(CheckError {
+                        lines: vec![path.clone()],
+                        path,
+                    });
+                    self.current_package = package;
+                    self.state = State::ParsingFile;
+                } else if let Some(caps) = PATH.captures(&line_no_ansi) {
+                    self.path_lines_seen += 1;
                     self.current_path_start_col = caps.name("path").unwrap().start();
-                    let path = self.get_line_from_path_col(&line_no_ansi);
-                    let line = TIMESTAMP.replace(raw_line, "");
+                    // eslint on Windows separates directories with backslashes
+                    let path = self
+                        .get_line_from_path_col(&line_no_ansi)
+                        .replace('\\', "/");
+                    let line = TIMESTAMP.replace(&raw_line, "");
                     self.current_path = Some(CheckError {
                         lines: vec![line.to_string()],
                         path,
                     });
+                    self.current_package = package;
                     self.state = State::ParsingFile;
                 }
             }
@@ -80,7 +199,7 @@ impl EslintLogParser {
                 self.current_path_lines += 1;
 
                 if ESLINT_ISSUE.is_match(&line_no_ansi) {
-                    let line = TIMESTAMP.replace(raw_line, "").to_string();
+                    let line = TIMESTAMP.replace(&raw_line, "").to_string();
                     let line = line.strip_prefix("##[error]").unwrap_or(&line);
                     let line = line.strip_prefix("##[warning]").unwrap_or(line);
                     self.current_path
@@ -95,6 +214,7 @@ impl EslintLogParser {
                     // back to looking for file state because some path is matched early in the
                     // logs.
                     self.state = State::LookingForFile;
+                    self.current_package = None;
                     self.seen_eslint_issue_for_current_path = false;
                     self.current_path_lines = 0;
                 } else if self.is_empty_line(&line_no_ansi) {
@@ -107,6 +227,7 @@ impl EslintLogParser {
                     // 2023-06-14T20:22:39.1790995Z /root_path/project_directory/module_2/setupModule2Test.ts
                     // 2023-06-14T20:22:39.1792493Z ##[warning]  166:58  warning  Missing return type on function  @typescript-eslint/explicit-module-boundary-types
                     self.state = State::LookingForFile;
+                    self.current_package = None;
 
                     if self.seen_eslint_issue_for_current_path {
                         let current_eslint_path = std::mem::take(&mut self.current_path);
@@ -122,8 +243,10 @@ impl EslintLogParser {
         }
     }
 
-    pub fn parse(log: &str) -> Vec<CheckError> {
-        let mut parser = EslintLogParser::new();
+    /// With package_dir, output of eslint run per package of a workspace is supported. Paths
+    /// relative to a package are prefixed with the package's directory, e.g. packages/{package}.
+    pub fn parse(log: &str, package_dir: Option<&str>) -> Vec<CheckError> {
+        let mut parser = EslintLogParser::new(package_dir.map(String::from));
 
         for line in log.lines() {
             parser.parse_line(line);
@@ -133,6 +256,11 @@ impl EslintLogParser {
     }
 
     pub fn get_output(self) -> Vec<CheckError> {
+        debug!(
+            path_lines_seen = self.path_lines_seen,
+            errors = self.all_paths.len(),
+            "parsed eslint log"
+        );
         self.all_paths
     }
 }
@@ -169,7 +297,7 @@ mod tests {
 2023-06-14T20:22:39.2063811Z ✖ 132 problems (4 errors, 128 warnings)
 2023-06-14T20:22:39.2064409Z   2 errors and 0 warnings potentially fixable with the `--fix` option."#;
 
-        let output = EslintLogParser::parse(log);
+        let output = EslintLogParser::parse(log, None);
         assert_eq!(
             output,
             vec![
@@ -215,6 +343,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_windows_paths() {
+        let log = r#"
+2023-06-14T20:22:39.1727281Z D:\a\repo\repo\src\components\Foo.tsx
+2023-06-14T20:22:39.1818449Z ##[error]  1:13  error  'fs' is defined but never used  @typescript-eslint/no-unused-vars
+2023-06-14T20:22:39.2063811Z
+2023-06-14T20:22:39.2063811Z ✖ 1 problem (1 error, 0 warnings)"#;
+
+        assert_eq!(
+            EslintLogParser::parse(log, None),
+            vec![CheckError {
+                path: "D:/a/repo/repo/src/components/Foo.tsx".to_string(),
+                lines: vec![
+                    "D:\\a\\repo\\repo\\src\\components\\Foo.tsx".to_string(),
+                    "  1:13  error  'fs' is defined but never used  @typescript-eslint/no-unused-vars"
+                        .to_string(),
+                ],
+            }]
+        );
+    }
+
     #[test]
     fn test_parse_corner_case() {
         let log = r#"
@@ -226,7 +375,7 @@ mod tests {
 2023-06-14T20:22:39.1789066Z ##[warning]  1:42  warning  Missing return type on function  @typescript-eslint/explicit-module-boundary-types
 2023-06-14T20:10:41.2671897Z
     "#;
-        let output = EslintLogParser::parse(log);
+        let output = EslintLogParser::parse(log, None);
         assert_eq!(
                 output,
                 vec![
@@ -262,7 +411,7 @@ mod tests {
 2023-06-16T15:54:54.4401725Z [34m@project/package:lint: [0m[0m[33m[1m✖ 4 problems (0 errors, 4 warnings)[22m[39m[0m
 2023-06-14T20:22:39.2063811Z ✖ 132 problems (4 errors, 128 warnings)"#;
 
-        let output = EslintLogParser::parse(log);
+        let output = EslintLogParser::parse(log, None);
         assert_eq!(output, vec![
             CheckError {
                 path: "/path/to/working/directory/src/components/ComponentWrapper.spec.tsx".to_string(),
@@ -282,4 +431,115 @@ mod tests {
             },
         ]);
     }
+
+    #[test]
+    fn test_parse_workspace_package_relative_paths() {
+        let log: &str = r#"
+2023-06-16T15:54:54.4381752Z @project/web:lint: > eslint src
+2023-06-16T15:54:54.4383282Z api:lint: > eslint src
+2023-06-16T15:54:54.4385037Z @project/web:lint: 
+2023-06-16T15:54:54.4387931Z @project/web:lint: src/index.ts
+2023-06-16T15:54:54.4389816Z @project/web:lint:   1:10  error  'foo' is defined but never used  @typescript-eslint/no-unused-vars
+2023-06-16T15:54:54.4390000Z api:lint: 
+2023-06-16T15:54:54.4391533Z @project/web:lint:   3:1   error  Unexpected console statement  no-console
+2023-06-16T15:54:54.4393248Z @project/web:lint: 
+2023-06-16T15:54:54.4394749Z api:lint: src/index.ts
+2023-06-16T15:54:54.4396497Z api:lint:   7:5  warning  Unexpected any. Specify a different type  @typescript-eslint/no-explicit-any
+2023-06-16T15:54:54.4398548Z api:lint: 
+2023-06-16T15:54:54.4401725Z api:lint: ✖ 1 problem (0 errors, 1 warning)"#;
+
+        let output = EslintLogParser::parse(log, Some("packages/{package}"));
+        assert_eq!(
+            output,
+            vec![
+                CheckError {
+                    path: "packages/web/src/index.ts".to_string(),
+                    lines: vec![
+                        "packages/web/src/index.ts".to_string(),
+                        "  1:10  error  'foo' is defined but never used  @typescript-eslint/no-unused-vars".to_string(),
+                        "  3:1   error  Unexpected console statement  no-console".to_string(),
+                    ],
+                },
+                CheckError {
+                    path: "packages/api/src/index.ts".to_string(),
+                    lines: vec![
+                        "packages/api/src/index.ts".to_string(),
+                        "  7:5  warning  Unexpected any. Specify a different type  @typescript-eslint/no-explicit-any".to_string(),
+                    ],
+                },
+            ]
+        );
+
+        // Without package_dir, relative paths aren't recognized
+        assert_eq!(EslintLogParser::parse(log, None), vec![]);
+    }
+
+    #[test]
+    fn test_parse_ansi_monorepo_with_package_dir() {
+        let log: &str = r#"
+2023-06-16T15:54:54.4387931Z [34m@project/package:lint: [0m[0m[4m/path/to/working/directory/src/hooks/useCustomHook.spec.ts[24m[0m
+2023-06-16T15:54:54.4398548Z [34m@project/package:lint: [0m[0m  [2m6:46[22m  [33mwarning[39m  Unexpected any. Specify a different type  [2m@typescript-eslint/no-explicit-any[22m[0m
+2023-06-16T15:54:54.4400116Z [34m@project/package:lint: [0m[0m
+2023-06-16T15:54:54.4387931Z [34m@project/package:lint: [0m[0m[4msrc/index.ts[24m[0m
+2023-06-16T15:54:54.4398548Z [34m@project/package:lint: [0m[0m  [2m1:1[22m  [31merror[39m  Missing return type  [2m@typescript-eslint/explicit-function-return-type[22m[0m
+2023-06-16T15:54:54.4400116Z [34m@project/package:lint: [0m[0m"#;
+
+        let paths: Vec<_> = EslintLogParser::parse(log, Some("apps/{package}"))
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/path/to/working/directory/src/hooks/useCustomHook.spec.ts",
+                "apps/package/src/index.ts"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_issue_rule() {
+        let test_cases = vec![
+            (
+                "  1:10  error  'foo' is defined but never used  @typescript-eslint/no-unused-vars",
+                Some("@typescript-eslint/no-unused-vars"),
+            ),
+            (
+                "  3:1   error  Unexpected console statement  no-console",
+                Some("no-console"),
+            ),
+            (
+                "\u{1b}[0m  \u{1b}[2m8:1\u{1b}[22m  \u{1b}[33mwarning\u{1b}[39m  Disabled test suite  \u{1b}[2mjest/no-disabled-tests\u{1b}[22m\u{1b}[0m",
+                Some("jest/no-disabled-tests"),
+            ),
+            ("  1:1  error  Parsing error: Unexpected token", None),
+            ("/root_path/project_directory/module_2/setupModule2Test.ts", None),
+        ];
+
+        for (line, expected) in test_cases {
+            assert_eq!(issue_rule(line).as_deref(), expected, "line: {}", line);
+        }
+    }
+
+    #[test]
+    fn test_issue_message() {
+        assert_eq!(
+            issue_message("  4:10  error  'foo' is defined but never used  no-unused-vars"),
+            Some("'foo' is defined but never used  no-unused-vars".to_string())
+        );
+        assert_eq!(issue_message("src/a.ts"), None);
+    }
+
+    #[test]
+    fn test_is_error_issue() {
+        assert!(is_error_issue(
+            "  1:10  error  'foo' is defined but never used  @typescript-eslint/no-unused-vars"
+        ));
+        assert!(!is_error_issue(
+            "\u{1b}[0m  \u{1b}[2m8:1\u{1b}[22m  \u{1b}[33mwarning\u{1b}[39m  Disabled test suite  \u{1b}[2mjest/no-disabled-tests\u{1b}[22m\u{1b}[0m"
+        ));
+        assert!(!is_error_issue(
+            "/root_path/project_directory/module_2/setupModule2Test.ts"
+        ));
+    }
 }
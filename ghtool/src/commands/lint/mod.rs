@@ -1,23 +1,23 @@
 use eyre::Result;
-use regex::Regex;
 
+use crate::error::GhtoolError;
 use crate::repo_config::LintConfig;
+use crate::repo_config::LintTool;
 use crate::repo_config::RepoConfig;
 
+use self::biome::BiomeLogParser;
 use self::eslint::EslintLogParser;
 
+use super::parse_with_most_errors;
+use super::pluralize;
+use super::strip_ansi;
 use super::CheckError;
 use super::Command;
 use super::ConfigPattern;
 
+mod biome;
 mod eslint;
 
-impl ConfigPattern for LintConfig {
-    fn job_pattern(&self) -> &Regex {
-        &self.job_pattern
-    }
-}
-
 #[derive(Clone)]
 pub struct LintCommand {
     config: LintConfig,
@@ -28,7 +28,7 @@ impl LintCommand {
         let lint_config = repo_config
             .lint
             .clone()
-            .ok_or_else(|| eyre::eyre!("Error: no lint section found in .ghtool.toml"))?;
+            .ok_or(GhtoolError::MissingConfigSection("lint"))?;
 
         Ok(Self {
             config: lint_config,
@@ -46,10 +46,113 @@ impl Command for LintCommand {
     }
 
     fn config(&self) -> &dyn ConfigPattern {
-        &self.config
+        &self.config.section
     }
 
     fn parse_log(&self, log: &str) -> Result<Vec<CheckError>> {
-        Ok(EslintLogParser::parse(log))
+        parse_with_most_errors(&self.config.tool, |tool| {
+            Ok(match tool {
+                LintTool::Eslint => EslintLogParser::parse(log, self.config.package_dir.as_deref()),
+                LintTool::Biome => BiomeLogParser::parse(log),
+            })
+        })
+    }
+
+    fn issue_rule(&self, line: &str) -> Option<String> {
+        self.config.tool.iter().find_map(|tool| match tool {
+            LintTool::Eslint => eslint::issue_rule(line),
+            LintTool::Biome => biome::issue_rule(line),
+        })
+    }
+
+    fn issue_position(&self, line: &str) -> Option<(u32, u32)> {
+        self.config.tool.iter().find_map(|tool| match tool {
+            LintTool::Eslint => eslint::issue_position(line),
+            LintTool::Biome => biome::issue_position(line),
+        })
+    }
+
+    fn issue_message(&self, line: &str) -> String {
+        self.config
+            .tool
+            .iter()
+            .find_map(|tool| match tool {
+                LintTool::Eslint => eslint::issue_message(line),
+                LintTool::Biome => biome::issue_message(line),
+            })
+            .unwrap_or_else(|| strip_ansi(line).trim().to_string())
+    }
+
+    fn summarize_errors(&self, errors: &[&CheckError]) -> String {
+        let issues = errors
+            .iter()
+            .flat_map(|error| &error.lines)
+            .filter(|line| self.issue_position(line).is_some())
+            .count();
+        pluralize(issues, "issue")
+    }
+
+    fn has_error_severity(&self, error: &CheckError) -> bool {
+        error.lines.iter().any(|line| {
+            self.config.tool.iter().any(|tool| match tool {
+                LintTool::Eslint => eslint::is_error_issue(line),
+                LintTool::Biome => biome::is_error_issue(line),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn lint_command(tool: &str) -> LintCommand {
+        let config = format!("[lint]\njob_pattern = \"Lint\"\ntool = {tool}\n");
+        LintCommand::from_repo_config(&toml::from_str(&config).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_log_detects_tool() {
+        let eslint_log = r#"2024-03-01T10:00:01.1000000Z /home/runner/work/app/app/src/index.ts
+2024-03-01T10:00:01.1000000Z ##[error]  3:7  error  'unused' is assigned a value but never used  no-unused-vars
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z ✖ 1 problem (1 error, 0 warnings)"#;
+        let biome_log = r#"2024-03-01T10:00:01.1000000Z src/index.ts:3:7 lint/correctness/noUnusedVariables  FIXABLE  ━━━━━━━━━━━━━━━━
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z   ✖ This variable is unused.
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.2000000Z Checked 24 files in 18ms. No fixes applied.
+2024-03-01T10:00:01.2000000Z Found 1 error."#;
+
+        let command = lint_command("[\"eslint\", \"biome\"]");
+        let errors = command.parse_log(eslint_log).unwrap();
+        assert_eq!(
+            errors,
+            vec![CheckError {
+                path: "/home/runner/work/app/app/src/index.ts".to_string(),
+                lines: vec![
+                    "/home/runner/work/app/app/src/index.ts".to_string(),
+                    "  3:7  error  'unused' is assigned a value but never used  no-unused-vars"
+                        .to_string(),
+                ],
+            }]
+        );
+        assert_eq!(
+            command.issue_rule(&errors[0].lines[1]),
+            Some("no-unused-vars".to_string())
+        );
+
+        let errors = command.parse_log(biome_log).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "src/index.ts");
+        assert_eq!(command.issue_position(&errors[0].lines[0]), Some((3, 7)));
+        assert!(command.has_error_severity(&errors[0]));
+
+        // With a single tool, the other's output isn't parsed
+        assert!(lint_command("\"eslint\"")
+            .parse_log(biome_log)
+            .unwrap()
+            .is_empty());
     }
 }
@@ -1,8 +1,20 @@
 pub mod auth;
+pub mod config;
+pub mod doctor;
+pub mod open;
+pub mod run;
+pub mod status;
 
+mod annotations;
+mod base_errors;
+mod blame;
 mod build;
+mod codeowners;
 mod command;
 mod lint;
+mod output_template;
+mod paths;
+mod sourcemaps;
 mod test;
 
 pub use build::*;
@@ -0,0 +1,143 @@
+use std::io::{self, IsTerminal};
+
+use dialoguer::Select;
+use eyre::Result;
+
+use crate::{
+    cli::Cli,
+    github::{GithubClient, SimpleCheckRun},
+    setup::get_repo_config,
+    term::bold,
+};
+
+use super::{get_checks, get_token, is_reported_conclusion};
+
+/// Failed check runs whose names contain the job, ignoring case, or all failed ones without it
+fn matching_failed_check_runs<'a>(
+    check_runs: &'a [SimpleCheckRun],
+    job: Option<&str>,
+    include_neutral: bool,
+) -> Vec<&'a SimpleCheckRun> {
+    let job = job.map(str::to_lowercase);
+    check_runs
+        .iter()
+        .filter(|check_run| is_reported_conclusion(check_run.conclusion, include_neutral))
+        .filter(|check_run| {
+            job.as_ref()
+                .is_none_or(|job| check_run.name.to_lowercase().contains(job))
+        })
+        .collect()
+}
+
+/// Open the page of a failed job of the pull request in the browser. When several match, ask which
+/// one in a terminal; with --yes or without a terminal, they're listed instead.
+pub async fn open(cli: &Cli, job: Option<&str>) -> Result<()> {
+    let (_, repo, branch) = get_repo_config(cli)?;
+    let token = get_token(cli, &repo.hostname).await?;
+    let client = GithubClient::new(&token, &repo.hostname)?;
+
+    let pr_checks = get_checks(
+        cli,
+        cli.default_branch,
+        &client,
+        &repo,
+        &branch,
+        |pull_request| {
+            let client = &client;
+            async move {
+                if cli.last {
+                    client
+                        .get_pr_last_completed_checks(&pull_request.id, None)
+                        .await
+                } else {
+                    client.get_pr_status_checks(&pull_request.id, true).await
+                }
+            }
+        },
+    )
+    .await?;
+
+    let mut check_runs =
+        matching_failed_check_runs(&pr_checks.check_runs, job, cli.include_neutral);
+    if cli.only_required_failing {
+        check_runs.retain(|check_run| check_run.is_required);
+    }
+
+    let check_run = match check_runs.as_slice() {
+        [] => match job {
+            Some(job) => eyre::bail!("No failed job matching {}", bold(job)),
+            None => {
+                eprintln!("No failed jobs");
+                return Ok(());
+            }
+        },
+        [check_run] => *check_run,
+        check_runs if cli.yes || !io::stdout().is_terminal() || !io::stderr().is_terminal() => {
+            for check_run in check_runs {
+                println!(
+                    "{}  {}",
+                    check_run.name,
+                    check_run.url.as_deref().unwrap_or("")
+                );
+            }
+            return Ok(());
+        }
+        check_runs => {
+            let items: Vec<_> = check_runs.iter().map(|check_run| &check_run.name).collect();
+            let selection = Select::new()
+                .with_prompt("Open")
+                .items(&items)
+                .default(0)
+                .interact()?;
+            check_runs[selection]
+        }
+    };
+
+    let url = check_run
+        .url
+        .as_deref()
+        .ok_or_else(|| eyre::eyre!("Check {} has no url", bold(&check_run.name)))?;
+    eprintln!("Opening {} in browser", url);
+    open::that(url)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::{CheckConclusionState, CheckStatusState};
+    use pretty_assertions::assert_eq;
+
+    fn check_run(name: &str, conclusion: CheckConclusionState) -> SimpleCheckRun {
+        SimpleCheckRun {
+            id: 1,
+            name: name.to_string(),
+            status: CheckStatusState::Completed,
+            conclusion: Some(conclusion),
+            url: None,
+            started_at: None,
+            completed_at: None,
+            app: None,
+            is_required: false,
+        }
+    }
+
+    #[test]
+    fn test_matching_failed_check_runs() {
+        let check_runs = vec![
+            check_run("Unit tests (1/2)", CheckConclusionState::Failure),
+            check_run("Unit tests (2/2)", CheckConclusionState::Success),
+            check_run("Lint", CheckConclusionState::Failure),
+        ];
+        let names = |job| -> Vec<&str> {
+            matching_failed_check_runs(&check_runs, job, false)
+                .iter()
+                .map(|check_run| check_run.name.as_str())
+                .collect()
+        };
+
+        assert_eq!(names(Some("unit")), vec!["Unit tests (1/2)"]);
+        assert_eq!(names(None), vec!["Unit tests (1/2)", "Lint"]);
+        assert!(names(Some("build")).is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use eyre::Result;
+
+/// Placeholder of an output template, replaced with a value of the issue
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Path,
+    Line,
+    Col,
+    Message,
+    Check,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("path", Field::Path),
+    ("line", Field::Line),
+    ("col", Field::Col),
+    ("message", Field::Message),
+    ("check", Field::Check),
+];
+
+#[derive(Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// Line printed for each issue with --output-template, e.g. "{path}:{line}:{col} {message}".
+/// Braces are written as {{ and }}.
+#[derive(Debug, PartialEq)]
+pub struct OutputTemplate {
+    parts: Vec<Part>,
+}
+
+/// What an issue's placeholders are replaced with
+pub struct TemplateValues<'a> {
+    pub path: &'a str,
+    /// Line and column, when the tool reports them; the placeholders are left empty otherwise
+    pub position: Option<(u32, u32)>,
+    /// The line reporting the issue, without colors and surrounding whitespace
+    pub message: &'a str,
+    /// Name of the check run
+    pub check: &'a str,
+}
+
+impl OutputTemplate {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        eyre::bail!("Unclosed {{ in output template; write {{{{ for a brace");
+                    }
+                    let field = FIELDS
+                        .iter()
+                        .find(|(field_name, _)| *field_name == name)
+                        .map(|(_, field)| *field)
+                        .ok_or_else(|| {
+                            eyre::eyre!(
+                                "Unknown placeholder {{{}}} in output template, expected one of \
+                                 {{path}}, {{line}}, {{col}}, {{message}} or {{check}}",
+                                name
+                            )
+                        })?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => eyre::bail!("Unmatched }} in output template; write }}}} for a brace"),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(OutputTemplate { parts })
+    }
+
+    pub fn render(&self, values: &TemplateValues) -> String {
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => output.push_str(text),
+                Part::Field(Field::Path) => output.push_str(values.path),
+                Part::Field(Field::Line) => {
+                    if let Some((line, _)) = values.position {
+                        output.push_str(&line.to_string());
+                    }
+                }
+                Part::Field(Field::Col) => {
+                    if let Some((_, col)) = values.position {
+                        output.push_str(&col.to_string());
+                    }
+                }
+                Part::Field(Field::Message) => output.push_str(values.message),
+                Part::Field(Field::Check) => output.push_str(values.check),
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_render() {
+        let template = OutputTemplate::parse("{path}:{line}:{col} {message} {{{check}}}").unwrap();
+        let values = TemplateValues {
+            path: "src/a.ts",
+            position: Some((3, 21)),
+            message:
+                "src/a.ts(3,21): error TS2322: Type 'string' is not assignable to type 'number'.",
+            check: "Build",
+        };
+        assert_eq!(
+            template.render(&values),
+            "src/a.ts:3:21 src/a.ts(3,21): error TS2322: Type 'string' is not assignable to type 'number'. {Build}"
+        );
+
+        let values = TemplateValues {
+            position: None,
+            ..values
+        };
+        assert!(template.render(&values).starts_with("src/a.ts:: "));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let err = OutputTemplate::parse("{path}:{row}").unwrap_err();
+        assert!(err.to_string().contains("Unknown placeholder {row}"));
+        assert!(OutputTemplate::parse("{path").is_err());
+        assert!(OutputTemplate::parse("path}").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// Regex to match the workspace directory of a GitHub Actions runner, e.g.
+    /// /home/runner/work/repo/repo/, /__w/repo/repo/ when the job runs in a container or
+    /// D:/a/repo/repo/ on Windows. Only the first work directory is the workspace, so that one in
+    /// the repository isn't taken for it.
+    static ref WORKSPACE_PREFIX: Regex = Regex::new(
+        r"^(?:[^ ]*?/work|/__w|[a-zA-Z]:/a)/(?P<name>[^/]+)/(?P<same_name>[^/]+)/",
+    )
+    .unwrap();
+
+    /// Regex to match an absolute path, e.g. /home/runner/a.ts or D:\a\repo\a.ts on Windows
+    static ref ABSOLUTE_PATH: Regex = Regex::new(r"^(?:[a-zA-Z]:)?[/\\]").unwrap();
+}
+
+/// The path reported by a tool run in working_dir, relative to the repository root. Absolute paths
+/// and errors without a path are left as they are.
+pub fn join_working_dir(working_dir: Option<&str>, path: &str) -> String {
+    match working_dir {
+        Some(working_dir) if !path.is_empty() && !ABSOLUTE_PATH.is_match(path) => {
+            format!("{}/{}", working_dir.trim_end_matches('/'), path)
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Make a path reported by a tool in CI repo-relative, so that paths to the same file reported
+/// in different ways are equal. Paths outside of the runner's workspace are only cleaned up.
+pub fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = match WORKSPACE_PREFIX.captures(&path) {
+        // The workspace is /work/<repo>/<repo>/ unless checked out to a custom path
+        Some(caps) if caps["name"] == caps["same_name"] => &path[caps.get(0).unwrap().end()..],
+        _ => &path,
+    };
+
+    let is_absolute = path.starts_with('/');
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." if components.last().is_some_and(|c| *c != "..") => {
+                components.pop();
+            }
+            ".." if is_absolute => {}
+            component => components.push(component),
+        }
+    }
+
+    let normalized = components.join("/");
+    if is_absolute {
+        format!("/{}", normalized)
+    } else {
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_normalize_path() {
+        let test_cases = vec![
+            ("/home/runner/work/repo/repo/src/a.test.ts", "src/a.test.ts"),
+            (
+                "/home/runner/work/app/app/src/work/x/x/a.ts",
+                "src/work/x/x/a.ts",
+            ),
+            (
+                "/__w/repo/repo/packages/web/src/a.ts",
+                "packages/web/src/a.ts",
+            ),
+            ("./src/a.test.ts", "src/a.test.ts"),
+            ("packages/web/../web/src/./a.ts", "packages/web/src/a.ts"),
+            ("../shared/a.ts", "../shared/a.ts"),
+            ("src/a.test.ts", "src/a.test.ts"),
+            ("/root_path/project/src/a.ts", "/root_path/project/src/a.ts"),
+            (
+                "/home/runner/work/repo/checkout/src/a.ts",
+                "/home/runner/work/repo/checkout/src/a.ts",
+            ),
+            ("/../a.ts", "/a.ts"),
+            ("D:\\a\\repo\\repo\\src\\a.ts", "src/a.ts"),
+            ("src\\components\\Foo.tsx", "src/components/Foo.tsx"),
+        ];
+
+        for (path, expected) in test_cases {
+            assert_eq!(normalize_path(path), expected, "path: {}", path);
+        }
+    }
+
+    #[test]
+    fn test_join_working_dir() {
+        let test_cases = vec![
+            (Some("packages/web"), "src/a.ts", "packages/web/src/a.ts"),
+            (
+                Some("packages/web/"),
+                "../shared/a.ts",
+                "packages/shared/a.ts",
+            ),
+            (
+                Some("packages/web"),
+                "/home/runner/work/repo/repo/packages/web/src/a.ts",
+                "packages/web/src/a.ts",
+            ),
+            (
+                Some("packages/web"),
+                "D:\\a\\repo\\repo\\src\\a.ts",
+                "src/a.ts",
+            ),
+            (Some("packages/web"), "", ""),
+            (None, "src/a.ts", "src/a.ts"),
+        ];
+
+        for (working_dir, path, expected) in test_cases {
+            assert_eq!(
+                normalize_path(&join_working_dir(working_dir, path)),
+                expected,
+                "path: {}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_collapses_duplicates() {
+        let paths = [
+            "/home/runner/work/repo/repo/src/a.test.ts",
+            "./src/a.test.ts",
+            "src/nested/../a.test.ts",
+            "src/a.test.ts",
+        ];
+        let mut normalized: Vec<_> = paths.iter().map(|p| normalize_path(p)).collect();
+        normalized.dedup();
+        assert_eq!(normalized, vec!["src/a.test.ts"]);
+    }
+}
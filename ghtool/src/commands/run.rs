@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use eyre::{Context, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    cli::Cli,
+    setup::get_repo_path,
+    term::{bold, confirm},
+    trace::trace_command,
+};
+
+lazy_static! {
+    /// Regex to match a GitHub Actions expression, e.g. ${{ matrix.shard }}
+    static ref EXPRESSION: Regex = Regex::new(r"\$\{\{[^}]*\}\}").unwrap();
+}
+
+#[derive(Deserialize, Debug)]
+struct Workflow {
+    #[serde(default)]
+    jobs: BTreeMap<String, Job>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Job {
+    name: Option<String>,
+    #[serde(default)]
+    steps: Vec<Step>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct Step {
+    name: Option<String>,
+    run: Option<String>,
+    #[serde(rename = "working-directory")]
+    working_directory: Option<String>,
+}
+
+/// A job of a workflow file whose name matches a check run's name
+#[derive(Debug)]
+struct MatchingJob {
+    workflow_path: PathBuf,
+    job_id: String,
+    steps: Vec<Step>,
+}
+
+/// Print the run steps of the workflow job matching a check name, and optionally run them
+pub fn run(cli: &Cli, check_name: &str, execute: bool) -> Result<()> {
+    let repo_path = get_repo_path()?;
+    let jobs = find_matching_jobs(&repo_path.join(".github").join("workflows"), check_name)?;
+
+    let job = match jobs.as_slice() {
+        [] => eyre::bail!(
+            "No job matching check {} found in .github/workflows",
+            bold(check_name)
+        ),
+        [job] => job,
+        jobs => {
+            let names: Vec<_> = jobs
+                .iter()
+                .map(|job| format!("{}:{}", job.workflow_path.display(), job.job_id))
+                .collect();
+            eyre::bail!(
+                "Multiple jobs match check {}: {}",
+                bold(check_name),
+                names.join(", ")
+            )
+        }
+    };
+
+    let run_steps: Vec<_> = job.steps.iter().filter(|step| step.run.is_some()).collect();
+    if run_steps.is_empty() {
+        eyre::bail!("Job {} has no run steps", bold(&job.job_id));
+    }
+
+    eprintln!(
+        "Job {} in {}:",
+        bold(&job.job_id),
+        job.workflow_path.display()
+    );
+    for step in &run_steps {
+        print_step(step);
+    }
+
+    if execute {
+        // Expressions are evaluated by GitHub Actions, running the steps without them would be
+        // a guess
+        if run_steps
+            .iter()
+            .any(|step| EXPRESSION.is_match(step.run.as_ref().unwrap()))
+        {
+            eyre::bail!("Steps use ${{{{ }}}} expressions, which can't be evaluated locally");
+        }
+
+        if !confirm(
+            cli.yes,
+            &format!("Run {} locally?", super::pluralize(run_steps.len(), "step")),
+        )? {
+            return Ok(());
+        }
+
+        for step in &run_steps {
+            execute_step(&repo_path, step)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_step(step: &Step) {
+    if let Some(name) = &step.name {
+        println!("# {}", name);
+    }
+    if let Some(working_directory) = &step.working_directory {
+        println!("cd {}", working_directory);
+    }
+    println!("{}", step.run.as_ref().unwrap().trim_end());
+    println!();
+}
+
+fn execute_step(repo_path: &Path, step: &Step) -> Result<()> {
+    let working_directory = match &step.working_directory {
+        Some(dir) => repo_path.join(dir),
+        None => repo_path.to_path_buf(),
+    };
+    let mut command = process::Command::new("sh");
+    command
+        .arg("-e")
+        .arg("-c")
+        .arg(step.run.as_ref().unwrap())
+        .current_dir(working_directory);
+    trace_command(&command);
+    let status = command.status().wrap_err("Failed to run step")?;
+
+    if !status.success() {
+        eyre::bail!(
+            "Step {} failed with {}",
+            bold(step.name.as_deref().unwrap_or("")),
+            status
+        );
+    }
+    Ok(())
+}
+
+fn find_matching_jobs(workflows_dir: &Path, check_name: &str) -> Result<Vec<MatchingJob>> {
+    let mut matching_jobs = Vec::new();
+    let entries = fs::read_dir(workflows_dir)
+        .wrap_err_with(|| format!("Error reading {}", workflows_dir.display()))?;
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "yml" || ext == "yaml")
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let workflow_str = fs::read_to_string(&path)?;
+        let workflow: Workflow = serde_yaml::from_str(&workflow_str)
+            .wrap_err_with(|| format!("Error parsing {}", path.display()))?;
+
+        for (job_id, job) in workflow.jobs {
+            if job_matches(&job_id, job.name.as_deref(), check_name) {
+                matching_jobs.push(MatchingJob {
+                    workflow_path: path.clone(),
+                    job_id,
+                    steps: job.steps,
+                });
+            }
+        }
+    }
+
+    Ok(matching_jobs)
+}
+
+/// A check run is named after the job's name, or its id if it has no name. Matrix jobs without
+/// an expression in the name get the matrix values appended, e.g. "Unit tests (1, 4)".
+fn job_matches(job_id: &str, job_name: Option<&str>, check_name: &str) -> bool {
+    let name = job_name.unwrap_or(job_id);
+    let pattern = EXPRESSION
+        .split(name)
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(".+");
+    Regex::new(&format!(r"^{}(?: \(.+\))?$", pattern))
+        .map(|re| re.is_match(check_name))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_job_matches() {
+        assert!(job_matches("build", None, "build"));
+        assert!(job_matches("test", Some("Unit tests"), "Unit tests"));
+        assert!(job_matches("test", Some("Unit tests"), "Unit tests (2, 4)"));
+        assert!(job_matches(
+            "test",
+            Some("Unit tests ${{ matrix.shard }}/${{ matrix.total }}"),
+            "Unit tests 2/4"
+        ));
+        assert!(!job_matches(
+            "test",
+            Some("Unit tests"),
+            "Integration tests"
+        ));
+        assert!(!job_matches("test", Some("Unit tests"), "test"));
+    }
+
+    #[test]
+    fn test_find_matching_jobs() {
+        let dir = std::env::temp_dir().join(format!("ghtool-run-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("ci.yml"),
+            r#"
+name: CI
+on: [push]
+jobs:
+  lint:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - run: npm run lint
+  test:
+    name: Unit tests
+    strategy:
+      matrix:
+        shard: [1, 2]
+    steps:
+      - uses: actions/checkout@v4
+      - name: Install
+        run: npm ci
+      - name: Test
+        run: npx jest --shard=${{ matrix.shard }}/2
+        working-directory: packages/web
+"#,
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "not a workflow").unwrap();
+
+        let jobs = find_matching_jobs(&dir, "Unit tests (1)").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].job_id, "test");
+        let runs: Vec<_> = jobs[0]
+            .steps
+            .iter()
+            .filter_map(|step| step.run.as_deref())
+            .collect();
+        assert_eq!(
+            runs,
+            vec!["npm ci", "npx jest --shard=${{ matrix.shard }}/2"]
+        );
+        assert_eq!(
+            jobs[0].steps[2].working_directory.as_deref(),
+            Some("packages/web")
+        );
+    }
+}
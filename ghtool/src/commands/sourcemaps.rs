@@ -0,0 +1,275 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use regex::Regex;
+use serde::Deserialize;
+use tracing::{debug, info};
+
+use super::{paths::normalize_path, CheckError};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    #[serde(default)]
+    source_root: Option<String>,
+    sources: Vec<String>,
+    mappings: String,
+}
+
+/// Position in a source file; all zero-based
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Mapping {
+    generated_column: u32,
+    source: usize,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Debug)]
+struct SourceMap {
+    /// Repo-relative paths of the sources, or None for sources that aren't local files
+    sources: Vec<Option<String>>,
+    /// Mappings of each generated line, sorted by generated column
+    lines: Vec<Vec<Mapping>>,
+}
+
+impl SourceMap {
+    /// Parse the source map of the generated file at path, which is relative to the repo root
+    fn parse(json: &str, path: &str) -> Option<SourceMap> {
+        let raw: RawSourceMap = serde_json::from_str(json).ok()?;
+        let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let source_root = raw.source_root.unwrap_or_default();
+
+        let sources = raw
+            .sources
+            .iter()
+            .map(|source| {
+                // e.g. webpack:///src/a.ts can't be mapped to a file
+                if source.contains("://") {
+                    return None;
+                }
+                let path = [dir, &source_root, source]
+                    .into_iter()
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                Some(normalize_path(&path))
+            })
+            .collect();
+
+        Some(SourceMap {
+            sources,
+            lines: decode_mappings(&raw.mappings)?,
+        })
+    }
+
+    /// Original position of the generated position; zero-based
+    fn lookup(&self, line: u32, column: u32) -> Option<(&str, u32, u32)> {
+        let mappings = self.lines.get(line as usize)?;
+        let mapping = mappings
+            .iter()
+            .rev()
+            .find(|mapping| mapping.generated_column <= column)
+            .or_else(|| mappings.first())?;
+        let source = self.sources.get(mapping.source)?.as_deref()?;
+        Some((source, mapping.line, mapping.column))
+    }
+
+    /// The only local source, if there is exactly one
+    fn single_source(&self) -> Option<&str> {
+        match self.sources.as_slice() {
+            [Some(source)] => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Decode the base64 VLQ encoded mappings of a source map version 3. Segments without a source
+/// are skipped.
+fn decode_mappings(mappings: &str) -> Option<Vec<Vec<Mapping>>> {
+    let mut lines = Vec::new();
+    // Apart from the generated column, fields are relative to the previous segment of any line
+    let (mut source, mut line, mut column) = (0i64, 0i64, 0i64);
+
+    for encoded_line in mappings.split(';') {
+        let mut generated_column = 0i64;
+        let mut line_mappings = Vec::new();
+
+        for segment in encoded_line.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            generated_column += fields.first()?;
+            if fields.len() < 4 {
+                continue;
+            }
+            source += fields[1];
+            line += fields[2];
+            column += fields[3];
+            line_mappings.push(Mapping {
+                generated_column: u32::try_from(generated_column).ok()?,
+                source: usize::try_from(source).ok()?,
+                line: u32::try_from(line).ok()?,
+                column: u32::try_from(column).ok()?,
+            });
+        }
+
+        line_mappings.sort_by_key(|mapping| mapping.generated_column);
+        lines.push(line_mappings);
+    }
+
+    Some(lines)
+}
+
+fn decode_vlq(segment: &str) -> Option<Vec<i64>> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0u32);
+
+    for c in segment.bytes() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        } as i64;
+        value += (digit & 0b11111) << shift;
+
+        if digit & 0b100000 == 0 {
+            // The lowest bit is the sign
+            let magnitude = value >> 1;
+            values.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 5;
+            if shift > 60 {
+                return None;
+            }
+        }
+    }
+
+    Some(values)
+}
+
+/// For errors in files with a source map next to them in the checkout at repo_path, rewrite
+/// path:line:column positions in the error's lines to those in the original sources, and the path
+/// to that of the source the first position maps to. Errors without source maps are left as is.
+pub fn resolve_sourcemaps(errors: &mut [CheckError], repo_path: &Path) {
+    let mut source_maps: HashMap<String, Option<SourceMap>> = HashMap::new();
+
+    for error in errors {
+        let source_map = source_maps
+            .entry(error.path.clone())
+            .or_insert_with(|| read_source_map(repo_path, &error.path));
+        let Some(source_map) = source_map else {
+            continue;
+        };
+
+        let position = Regex::new(&format!(
+            r"{}:(?P<line>\d+):(?P<column>\d+)",
+            regex::escape(&error.path)
+        ))
+        .expect("escaped path is a valid regex");
+
+        let mut first_source = None;
+        for line in &mut error.lines {
+            let rewritten = position.replace_all(line, |caps: &regex::Captures| {
+                let generated_line: u32 = caps["line"].parse().unwrap_or(0);
+                let generated_column: u32 = caps["column"].parse().unwrap_or(0);
+                match source_map.lookup(
+                    generated_line.saturating_sub(1),
+                    generated_column.saturating_sub(1),
+                ) {
+                    Some((source, line, column)) => {
+                        first_source.get_or_insert_with(|| source.to_string());
+                        format!("{}:{}:{}", source, line + 1, column + 1)
+                    }
+                    None => caps[0].to_string(),
+                }
+            });
+            *line = rewritten.into_owned();
+        }
+
+        if let Some(source) = first_source.or_else(|| source_map.single_source().map(String::from))
+        {
+            debug!(
+                from = error.path,
+                to = source,
+                "resolved path with source map"
+            );
+            error.path = source;
+        }
+    }
+}
+
+fn read_source_map(repo_path: &Path, path: &str) -> Option<SourceMap> {
+    let map_path = repo_path.join(format!("{path}.map"));
+    let json = fs::read_to_string(&map_path).ok()?;
+    let source_map = SourceMap::parse(&json, path);
+    if source_map.is_none() {
+        info!(?map_path, "failed to parse source map");
+    }
+    source_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_decode_vlq() {
+        assert_eq!(decode_vlq("AAAA"), Some(vec![0, 0, 0, 0]));
+        assert_eq!(decode_vlq("CADF"), Some(vec![1, 0, -1, -2]));
+        assert_eq!(decode_vlq("gB"), Some(vec![16]));
+        assert_eq!(decode_vlq("A!"), None);
+    }
+
+    #[test]
+    fn test_resolve_sourcemaps() {
+        let dir = std::env::temp_dir().join(format!("ghtool-sourcemaps-{}", std::process::id()));
+        fs::create_dir_all(dir.join("dist")).unwrap();
+        // Line 1 maps to line 1 and line 3 to line 5 of src/a.test.ts
+        fs::write(
+            dir.join("dist/a.test.js.map"),
+            r#"{"version":3,"sources":["../src/a.test.ts"],"names":[],"mappings":"AAAA;;AAIA"}"#,
+        )
+        .unwrap();
+
+        let mut errors = vec![
+            CheckError {
+                path: "dist/a.test.js".to_string(),
+                lines: vec![
+                    "● a › fails".to_string(),
+                    "    at Object.<anonymous> (dist/a.test.js:3:1)".to_string(),
+                ],
+            },
+            CheckError {
+                path: "dist/b.test.js".to_string(),
+                lines: vec!["    at Object.<anonymous> (dist/b.test.js:3:1)".to_string()],
+            },
+        ];
+        resolve_sourcemaps(&mut errors, &dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            errors,
+            vec![
+                CheckError {
+                    path: "src/a.test.ts".to_string(),
+                    lines: vec![
+                        "● a › fails".to_string(),
+                        "    at Object.<anonymous> (src/a.test.ts:5:1)".to_string(),
+                    ],
+                },
+                CheckError {
+                    path: "dist/b.test.js".to_string(),
+                    lines: vec!["    at Object.<anonymous> (dist/b.test.js:3:1)".to_string()],
+                },
+            ]
+        );
+    }
+}
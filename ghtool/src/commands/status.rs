@@ -0,0 +1,140 @@
+use std::io::{self, BufRead};
+
+use eyre::Result;
+use futures::{stream, StreamExt};
+
+use crate::{
+    cli::Cli,
+    git::Repository,
+    github::GithubClient,
+    setup::get_repository,
+    spinner::new_spinner,
+    term::{bold, green, red},
+};
+
+use super::{check_outcome, get_token, CheckOutcome};
+
+/// Branches whose pull request and checks are fetched at the same time
+const MAX_CONCURRENT_BRANCHES: usize = 4;
+
+#[derive(Debug, Default, PartialEq)]
+struct BranchStatus {
+    failed: usize,
+    passed: usize,
+    pending: usize,
+}
+
+/// Print a line with the counts of check outcomes of each branch's pull request. Branches are read
+/// from stdin, one per line, when none are given. The outcome is like ght check's: failed if any
+/// check failed, otherwise pending if any is pending.
+pub async fn status(cli: &Cli, branches: &[String]) -> Result<CheckOutcome> {
+    let branches = if branches.is_empty() {
+        read_branches(io::stdin().lock())?
+    } else {
+        branches.to_vec()
+    };
+    if branches.is_empty() {
+        eyre::bail!("No branches given; pass --branches or branch names on stdin");
+    }
+
+    let repo = get_repository(cli)?;
+    let token = get_token(cli, &repo.hostname).await?;
+    let client = GithubClient::new(&token, &repo.hostname)?;
+
+    let pb = new_spinner(format!("Fetching checks of {} branches...", branches.len()));
+    let statuses: Vec<_> = stream::iter(&branches)
+        .map(|branch| branch_status(&client, &repo, branch))
+        .buffered(MAX_CONCURRENT_BRANCHES)
+        .collect()
+        .await;
+    pb.finish_and_clear();
+
+    let max_branch_length = branches.iter().map(|b| b.len()).max().unwrap_or(0);
+    let (mut any_failed, mut any_pending) = (false, false);
+    for (branch, status) in branches.iter().zip(statuses) {
+        let (symbol, description) = match status {
+            Ok(Some(status)) => {
+                any_failed |= status.failed > 0;
+                any_pending |= status.pending > 0;
+                let symbol = if status.failed > 0 {
+                    red("X")
+                } else if status.pending > 0 {
+                    "-".to_string()
+                } else {
+                    green("✓")
+                };
+                let description = format!(
+                    "{} failed, {} passed, {} pending",
+                    status.failed, status.passed, status.pending
+                );
+                (symbol, description)
+            }
+            Ok(None) => (" ".to_string(), "no pull request".to_string()),
+            Err(e) => (red("!"), format!("error: {e}")),
+        };
+        // Padded before it's made bold, so that escapes don't count towards the width
+        let branch = format!("{:<width$}", branch, width = max_branch_length);
+        println!("{} {}  {}", symbol, bold(&branch), description);
+    }
+
+    if any_failed {
+        return Ok(CheckOutcome::Failed);
+    }
+    if any_pending {
+        return Ok(CheckOutcome::Pending);
+    }
+    Ok(CheckOutcome::Passed)
+}
+
+/// Counts of outcomes of the checks of the branch's pull request, or None if it has none
+async fn branch_status(
+    client: &GithubClient,
+    repo: &Repository,
+    branch: &str,
+) -> Result<Option<BranchStatus>> {
+    let Some(pull_request) = client
+        .get_pr_for_branch(&repo.owner, &repo.name, branch, false)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let pr_checks = client.get_pr_status_checks(&pull_request.id, false).await?;
+
+    let mut status = BranchStatus::default();
+    for check_run in &pr_checks.check_runs {
+        match check_outcome(check_run) {
+            CheckOutcome::Failed => status.failed += 1,
+            CheckOutcome::Passed => status.passed += 1,
+            CheckOutcome::Pending => status.pending += 1,
+        }
+    }
+    Ok(Some(status))
+}
+
+/// Branch names, one per line, skipping empty lines
+fn read_branches(reader: impl BufRead) -> Result<Vec<String>> {
+    let mut branches = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let branch = line.trim();
+        if !branch.is_empty() {
+            branches.push(branch.to_string());
+        }
+    }
+    Ok(branches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_read_branches() {
+        let input = "feature-a\n\n  feature-b  \nfeature-c";
+        assert_eq!(
+            read_branches(input.as_bytes()).unwrap(),
+            vec!["feature-a", "feature-b", "feature-c"]
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::debug;
+
+use crate::commands::CheckError;
+
+lazy_static! {
+    /// Regex to match a timestamp and single space after it, if the line goes on
+    static ref TIMESTAMP: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z(?:\s|$)").unwrap();
+
+    /// Regex to match a failed test or test step and where it's defined
+    /// Example: subtracts numbers => ./math_test.ts:5:6
+    /// Example: nested ... step two => ./math_test.ts:14:11
+    static ref FAILED_TEST: Regex =
+        Regex::new(r"^(?P<name>.+) => (?P<path>\S+?):(?P<line>\d+):(?P<col>\d+)$").unwrap();
+
+    /// Regex to match an error thrown outside of tests, e.g. a permission error at the top level
+    /// of a test module
+    /// Example: ./fetch_test.ts (uncaught error)
+    static ref UNCAUGHT_ERROR: Regex = Regex::new(r"^(?P<path>\S+) \(uncaught error\)$").unwrap();
+
+    /// Regex to match the error of a test failed only because some of its steps failed
+    /// Example: error: Error: 1 test step failed.
+    static ref STEPS_FAILED: Regex = Regex::new(r"^error: Error: \d+ test steps? failed\.?$").unwrap();
+}
+
+/// Name of a failed test from its first line, with the tests a step is in separated by " › ", e.g.
+/// "nested › step two". Uncaught errors aren't of any test.
+pub fn failed_test_name(error: &CheckError) -> Option<String> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(error.lines.first()?);
+    let caps = FAILED_TEST.captures(&line_no_ansi)?;
+    Some(caps["name"].replace(" ... ", " › "))
+}
+
+#[derive(Debug, PartialEq)]
+enum State {
+    LookingForFailures,
+    /// Errors of failed tests, under " ERRORS "
+    ParsingErrors,
+    /// Summary of failed tests, under " FAILURES "
+    ParsingFailures,
+}
+
+#[derive(Debug)]
+struct FailedTest {
+    name: String,
+    location: String,
+    error: CheckError,
+}
+
+#[derive(Debug)]
+pub struct DenoLogParser {
+    state: State,
+    failed_tests: Vec<FailedTest>,
+}
+
+impl DenoLogParser {
+    pub fn new() -> Self {
+        DenoLogParser {
+            state: State::LookingForFailures,
+            failed_tests: Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, full_line: &str) {
+        let line = TIMESTAMP.replace(full_line, "");
+        let line_no_ansi = strip_ansi_escapes::strip_str(&line);
+
+        // Older versions of deno head the sections with "errors:" and "failures:"
+        match line_no_ansi.trim() {
+            "ERRORS" | "errors:" => {
+                self.state = State::ParsingErrors;
+                return;
+            }
+            "FAILURES" | "failures:" => {
+                self.state = State::ParsingFailures;
+                return;
+            }
+            _ => {}
+        }
+
+        // The summary, e.g. "FAILED | 1 passed | 2 failed (20ms)", ends the sections
+        if line_no_ansi.starts_with("FAILED |")
+            || line_no_ansi.starts_with("ok |")
+            || line_no_ansi.starts_with("test result:")
+        {
+            self.state = State::LookingForFailures;
+            return;
+        }
+
+        match self.state {
+            State::LookingForFailures => {}
+            State::ParsingErrors => {
+                if let Some(failed_test) = failed_test(&line, &line_no_ansi) {
+                    self.failed_tests.push(failed_test);
+                } else if let Some(last) = self.failed_tests.last_mut() {
+                    last.error.lines.push(line.to_string());
+                }
+            }
+            State::ParsingFailures => {
+                // Failed tests are listed here again; ones without errors printed above are
+                // reported with the line alone
+                if let Some(failed_test) = failed_test(&line, &line_no_ansi) {
+                    if !self
+                        .failed_tests
+                        .iter()
+                        .any(|test| test.location == failed_test.location)
+                    {
+                        self.failed_tests.push(failed_test);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Tests that failed only because of their steps are left out, as the steps are reported
+    fn finish(self) -> Vec<CheckError> {
+        let names: HashSet<&str> = self
+            .failed_tests
+            .iter()
+            .map(|test| test.name.as_str())
+            .collect();
+        let has_failed_steps = |name: &str| {
+            names
+                .iter()
+                .any(|other| other.starts_with(&format!("{name} ... ")))
+        };
+
+        let mut errors = Vec::new();
+        for test in &self.failed_tests {
+            let only_steps_failed = test
+                .error
+                .lines
+                .iter()
+                .any(|line| STEPS_FAILED.is_match(&strip_ansi_escapes::strip_str(line)));
+            if only_steps_failed && has_failed_steps(&test.name) {
+                continue;
+            }
+
+            let mut error = test.error.clone();
+            while error
+                .lines
+                .last()
+                .is_some_and(|line| strip_ansi_escapes::strip_str(line).trim().is_empty())
+            {
+                error.lines.pop();
+            }
+            errors.push(error);
+        }
+        errors
+    }
+
+    pub fn parse(log: &str) -> Vec<CheckError> {
+        let mut parser = DenoLogParser::new();
+
+        for line in log.lines() {
+            parser.parse_line(line);
+        }
+
+        let errors = parser.finish();
+        debug!(errors = errors.len(), "parsed deno log");
+        errors
+    }
+}
+
+fn failed_test(line: &str, line_no_ansi: &str) -> Option<FailedTest> {
+    let (name, path, location) = if let Some(caps) = FAILED_TEST.captures(line_no_ansi) {
+        let location = format!("{}:{}:{}", &caps["path"], &caps["line"], &caps["col"]);
+        (caps["name"].to_string(), caps["path"].to_string(), location)
+    } else if let Some(caps) = UNCAUGHT_ERROR.captures(line_no_ansi) {
+        let path = caps["path"].to_string();
+        (path.clone(), path.clone(), path)
+    } else {
+        return None;
+    };
+
+    Some(FailedTest {
+        name,
+        location,
+        error: CheckError {
+            path: path.trim_start_matches("./").to_string(),
+            lines: vec![line.to_string()],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_failures() {
+        let logs = r#"2024-03-01T10:00:00.1000000Z running 4 tests from ./math_test.ts
+2024-03-01T10:00:00.1000000Z adds numbers ... ok (2ms)
+2024-03-01T10:00:00.1000000Z subtracts numbers ... FAILED (3ms)
+2024-03-01T10:00:00.1000000Z fetches data ... FAILED (1ms)
+2024-03-01T10:00:00.1000000Z nested ...
+2024-03-01T10:00:00.1000000Z   step one ... ok (1ms)
+2024-03-01T10:00:00.1000000Z   step two ... FAILED (1ms)
+2024-03-01T10:00:00.1000000Z nested ... FAILED (due to 1 failed step) (3ms)
+2024-03-01T10:00:00.1000000Z
+2024-03-01T10:00:00.2000000Z  ERRORS
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z subtracts numbers => ./math_test.ts:5:6
+2024-03-01T10:00:00.2000000Z error: AssertionError: Values are not equal.
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z     [Diff] Actual / Expected
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z -   3
+2024-03-01T10:00:00.2000000Z +   4
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z     at assertEquals (https://deno.land/std@0.200.0/assert/assert_equals.ts:189:9)
+2024-03-01T10:00:00.2000000Z     at file:///home/runner/work/app/app/math_test.ts:6:3
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z fetches data => ./math_test.ts:9:6
+2024-03-01T10:00:00.2000000Z error: PermissionDenied: Requires net access to "example.com", run again with the --allow-net flag
+2024-03-01T10:00:00.2000000Z     at file:///home/runner/work/app/app/math_test.ts:10:9
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z nested => ./math_test.ts:12:6
+2024-03-01T10:00:00.2000000Z error: Error: 1 test step failed.
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z nested ... step two => ./math_test.ts:14:11
+2024-03-01T10:00:00.2000000Z error: Error: boom
+2024-03-01T10:00:00.2000000Z     at file:///home/runner/work/app/app/math_test.ts:15:11
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z ./env_test.ts (uncaught error)
+2024-03-01T10:00:00.2000000Z error: (in promise) PermissionDenied: Requires env access to "API_KEY", run again with the --allow-env flag
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.3000000Z  FAILURES
+2024-03-01T10:00:00.3000000Z
+2024-03-01T10:00:00.3000000Z subtracts numbers => ./math_test.ts:5:6
+2024-03-01T10:00:00.3000000Z fetches data => ./math_test.ts:9:6
+2024-03-01T10:00:00.3000000Z nested => ./math_test.ts:12:6
+2024-03-01T10:00:00.3000000Z nested ... step two => ./math_test.ts:14:11
+2024-03-01T10:00:00.3000000Z ./env_test.ts (uncaught error)
+2024-03-01T10:00:00.3000000Z
+2024-03-01T10:00:00.3000000Z FAILED | 1 passed (1 step) | 4 failed (1 step) (20ms)
+2024-03-01T10:00:00.3000000Z
+2024-03-01T10:00:00.3000000Z error: Test failed"#;
+
+        assert_eq!(
+            DenoLogParser::parse(logs),
+            vec![
+                CheckError {
+                    path: "math_test.ts".to_string(),
+                    lines: vec![
+                        "subtracts numbers => ./math_test.ts:5:6".to_string(),
+                        "error: AssertionError: Values are not equal.".to_string(),
+                        "".to_string(),
+                        "    [Diff] Actual / Expected".to_string(),
+                        "".to_string(),
+                        "-   3".to_string(),
+                        "+   4".to_string(),
+                        "".to_string(),
+                        "    at assertEquals (https://deno.land/std@0.200.0/assert/assert_equals.ts:189:9)".to_string(),
+                        "    at file:///home/runner/work/app/app/math_test.ts:6:3".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "math_test.ts".to_string(),
+                    lines: vec![
+                        "fetches data => ./math_test.ts:9:6".to_string(),
+                        "error: PermissionDenied: Requires net access to \"example.com\", run again with the --allow-net flag".to_string(),
+                        "    at file:///home/runner/work/app/app/math_test.ts:10:9".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "math_test.ts".to_string(),
+                    lines: vec![
+                        "nested ... step two => ./math_test.ts:14:11".to_string(),
+                        "error: Error: boom".to_string(),
+                        "    at file:///home/runner/work/app/app/math_test.ts:15:11".to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "env_test.ts".to_string(),
+                    lines: vec![
+                        "./env_test.ts (uncaught error)".to_string(),
+                        "error: (in promise) PermissionDenied: Requires env access to \"API_KEY\", run again with the --allow-env flag".to_string(),
+                    ]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_failures_without_errors_section() {
+        let logs = r#"2024-03-01T10:00:00.1000000Z subtracts numbers ... FAILED (3ms)
+2024-03-01T10:00:00.1000000Z
+2024-03-01T10:00:00.3000000Z failures:
+2024-03-01T10:00:00.3000000Z
+2024-03-01T10:00:00.3000000Z subtracts numbers => ./src/math_test.ts:5:6
+2024-03-01T10:00:00.3000000Z
+2024-03-01T10:00:00.3000000Z test result: FAILED. 0 passed; 1 failed; 0 ignored"#;
+
+        assert_eq!(
+            DenoLogParser::parse(logs),
+            vec![CheckError {
+                path: "src/math_test.ts".to_string(),
+                lines: vec!["subtracts numbers => ./src/math_test.ts:5:6".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_failed_test_name() {
+        let error = |line: &str| CheckError {
+            path: "math_test.ts".to_string(),
+            lines: vec![line.to_string(), "error: Error: boom".to_string()],
+        };
+        assert_eq!(
+            failed_test_name(&error("nested ... step two => ./math_test.ts:14:11")),
+            Some("nested › step two".to_string())
+        );
+        assert_eq!(
+            failed_test_name(&error("./env_test.ts (uncaught error)")),
+            None
+        );
+    }
+}
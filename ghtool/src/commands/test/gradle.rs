@@ -0,0 +1,222 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use tracing::debug;
+
+use crate::commands::CheckError;
+
+lazy_static! {
+    /// Regex to match a timestamp and single space after it, if the line goes on
+    static ref TIMESTAMP: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z(?:\s|$)").unwrap();
+
+    /// Regex to match a failed test
+    /// Example: com.example.CalculatorTest > addsNumbers FAILED
+    static ref TEST_FAILED: Regex =
+        Regex::new(r"^(?P<class>[\w.$]+) > (?P<method>.+) FAILED$").unwrap();
+
+    /// Regex to match the link to the HTML report of failed tests
+    /// Example: > There were failing tests. See the report at: file:///home/runner/work/app/app/build/reports/tests/testDebugUnitTest/index.html
+    static ref REPORT: Regex = Regex::new(r"See the report at: (?P<report>\S+)").unwrap();
+
+    /// Regex to match the task in the "What went wrong" section
+    /// Example: Execution failed for task ':app:compileDebugKotlin'.
+    static ref FAILED_TASK: Regex =
+        Regex::new(r"Execution failed for task '(?P<task>[^']+)'").unwrap();
+}
+
+/// Name of the test method of a failed test, from its first line
+pub fn failed_test_name(error: &CheckError) -> Option<String> {
+    let line_no_ansi = strip_ansi_escapes::strip_str(error.lines.first()?);
+    let caps = TEST_FAILED.captures(line_no_ansi.trim_end())?;
+    Some(caps["method"].to_string())
+}
+
+#[derive(Debug, PartialEq)]
+enum State {
+    LookingForFailure,
+    ParsingTestFailure,
+    ParsingWhatWentWrong,
+}
+
+#[derive(Debug)]
+pub struct GradleLogParser {
+    state: State,
+    test_failures: Vec<CheckError>,
+    what_went_wrong: Vec<String>,
+    failed_task: Option<String>,
+    report: Option<String>,
+}
+
+impl GradleLogParser {
+    pub fn new() -> Self {
+        GradleLogParser {
+            state: State::LookingForFailure,
+            test_failures: Vec::new(),
+            what_went_wrong: Vec::new(),
+            failed_task: None,
+            report: None,
+        }
+    }
+
+    fn parse_line(&mut self, full_line: &str) {
+        let line = TIMESTAMP.replace(full_line, "");
+        let line_no_ansi = strip_ansi_escapes::strip_str(&line);
+
+        match self.state {
+            State::ParsingTestFailure => {
+                // The exception and stack trace of a failed test are indented
+                if line_no_ansi.starts_with(char::is_whitespace) && !line_no_ansi.trim().is_empty()
+                {
+                    if let Some(failure) = self.test_failures.last_mut() {
+                        failure.lines.push(line.to_string());
+                    }
+                    return;
+                }
+                self.state = State::LookingForFailure;
+            }
+            State::ParsingWhatWentWrong => {
+                // The section ends with an empty line before "* Try:"
+                if line_no_ansi.trim().is_empty() || line_no_ansi.starts_with("* ") {
+                    self.state = State::LookingForFailure;
+                } else {
+                    if let Some(caps) = FAILED_TASK.captures(&line_no_ansi) {
+                        self.failed_task
+                            .get_or_insert_with(|| caps["task"].to_string());
+                    }
+                    if let Some(caps) = REPORT.captures(&line_no_ansi) {
+                        self.report
+                            .get_or_insert_with(|| caps["report"].to_string());
+                    }
+                    self.what_went_wrong.push(line.to_string());
+                }
+                return;
+            }
+            State::LookingForFailure => {}
+        }
+
+        if let Some(caps) = TEST_FAILED.captures(&line_no_ansi) {
+            self.test_failures.push(CheckError {
+                path: caps["class"].to_string(),
+                lines: vec![line.to_string()],
+            });
+            self.state = State::ParsingTestFailure;
+        } else if line_no_ansi.trim_end() == "* What went wrong:" {
+            self.state = State::ParsingWhatWentWrong;
+        }
+    }
+
+    /// Failed tests are reported by class, with a link to the report after the last one. When no
+    /// test failed, e.g. because compilation failed, the "What went wrong" section is reported
+    /// under the failed task.
+    fn finish(mut self) -> Vec<CheckError> {
+        if self.test_failures.is_empty() {
+            if self.what_went_wrong.is_empty() {
+                return Vec::new();
+            }
+            return vec![CheckError {
+                path: self.failed_task.unwrap_or_else(|| "build".to_string()),
+                lines: self.what_went_wrong,
+            }];
+        }
+
+        if let (Some(report), Some(last)) = (self.report, self.test_failures.last_mut()) {
+            last.lines.push(format!("See the report at: {report}"));
+        }
+        self.test_failures
+    }
+
+    pub fn parse(log: &str) -> Vec<CheckError> {
+        let mut parser = GradleLogParser::new();
+
+        for line in log.lines() {
+            parser.parse_line(line);
+        }
+
+        let errors = parser.finish();
+        debug!(errors = errors.len(), "parsed gradle log");
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_test_failures() {
+        let logs = r#"2024-03-01T10:00:00.1000000Z > Task :app:testDebugUnitTest
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.1000000Z com.example.CalculatorTest > addsNumbers FAILED
+2024-03-01T10:00:01.1000000Z     java.lang.AssertionError: expected:<3> but was:<4>
+2024-03-01T10:00:01.1000000Z         at org.junit.Assert.fail(Assert.java:89)
+2024-03-01T10:00:01.1000000Z         at com.example.CalculatorTest.addsNumbers(CalculatorTest.kt:12)
+2024-03-01T10:00:01.1000000Z
+2024-03-01T10:00:01.2000000Z com.example.ParserTest > parses empty input FAILED
+2024-03-01T10:00:01.2000000Z     java.lang.NullPointerException at ParserTest.kt:20
+2024-03-01T10:00:01.2000000Z
+2024-03-01T10:00:01.3000000Z 12 tests completed, 2 failed
+2024-03-01T10:00:01.3000000Z
+2024-03-01T10:00:01.3000000Z > Task :app:testDebugUnitTest FAILED
+2024-03-01T10:00:01.3000000Z
+2024-03-01T10:00:01.3000000Z FAILURE: Build failed with an exception.
+2024-03-01T10:00:01.3000000Z
+2024-03-01T10:00:01.3000000Z * What went wrong:
+2024-03-01T10:00:01.3000000Z Execution failed for task ':app:testDebugUnitTest'.
+2024-03-01T10:00:01.3000000Z > There were failing tests. See the report at: file:///home/runner/work/app/app/build/reports/tests/testDebugUnitTest/index.html
+2024-03-01T10:00:01.3000000Z
+2024-03-01T10:00:01.3000000Z * Try:
+2024-03-01T10:00:01.3000000Z > Run with --scan to get full insights."#;
+
+        assert_eq!(
+            GradleLogParser::parse(logs),
+            vec![
+                CheckError {
+                    path: "com.example.CalculatorTest".to_string(),
+                    lines: vec![
+                        "com.example.CalculatorTest > addsNumbers FAILED".to_string(),
+                        "    java.lang.AssertionError: expected:<3> but was:<4>".to_string(),
+                        "        at org.junit.Assert.fail(Assert.java:89)".to_string(),
+                        "        at com.example.CalculatorTest.addsNumbers(CalculatorTest.kt:12)"
+                            .to_string(),
+                    ]
+                },
+                CheckError {
+                    path: "com.example.ParserTest".to_string(),
+                    lines: vec![
+                        "com.example.ParserTest > parses empty input FAILED".to_string(),
+                        "    java.lang.NullPointerException at ParserTest.kt:20".to_string(),
+                        "See the report at: file:///home/runner/work/app/app/build/reports/tests/testDebugUnitTest/index.html".to_string(),
+                    ]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_compilation_failure() {
+        let logs = r#"2024-03-01T10:00:00.1000000Z > Task :app:compileDebugKotlin FAILED
+2024-03-01T10:00:00.1000000Z e: file:///home/runner/work/app/app/src/main/java/com/example/Calculator.kt:5:9 Unresolved reference: foo
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z FAILURE: Build failed with an exception.
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z * What went wrong:
+2024-03-01T10:00:00.2000000Z Execution failed for task ':app:compileDebugKotlin'.
+2024-03-01T10:00:00.2000000Z > A failure occurred while executing org.jetbrains.kotlin.compilerRunner.GradleCompilerRunnerWithWorkers$GradleKotlinCompilerWorkAction
+2024-03-01T10:00:00.2000000Z    > Compilation error. See log for more details
+2024-03-01T10:00:00.2000000Z
+2024-03-01T10:00:00.2000000Z * Try:"#;
+
+        assert_eq!(
+            GradleLogParser::parse(logs),
+            vec![CheckError {
+                path: ":app:compileDebugKotlin".to_string(),
+                lines: vec![
+                    "Execution failed for task ':app:compileDebugKotlin'.".to_string(),
+                    "> A failure occurred while executing org.jetbrains.kotlin.compilerRunner.GradleCompilerRunnerWithWorkers$GradleKotlinCompilerWorkAction".to_string(),
+                    "   > Compilation error. See log for more details".to_string(),
+                ]
+            }]
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::commands::command::CheckError;
 use eyre::Result;
 use lazy_static::lazy_static;
 use regex::Regex;
+use tracing::debug;
 
 const TIMESTAMP_PATTERN: &str = r"(?P<timestamp>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d+Z)";
 
@@ -12,9 +13,104 @@ lazy_static! {
         Regex::new(r"(?P<fail>FAIL)\s+(?P<path>[a-zA-Z0-9._-]*/[a-zA-Z0-9./_-]*)").unwrap();
     static ref ESCAPE_SEQUENCE: Regex = Regex::new(r"\x1B\[\d+(;\d+)*m").unwrap();
     static ref FAIL_START: Regex = Regex::new(r"(\x1B\[\d+(;\d+)*m)+\s?FAIL").unwrap();
+    /// Regex to match a turbo/nx task prefix, e.g. `web:test: ` or `@org/web:test: `. When the
+    /// prefix is colored, the escape sequence that resets the color is part of the prefix.
+    static ref TASK_PREFIX: Regex = Regex::new(
+        r"^(?:\x1B\[\d+(;\d+)*m[@\w./-]+:[\w.-]+:\s?\x1B\[\d+(;\d+)*m|[@\w./-]+:[\w.-]+:\s)",
+    )
+    .unwrap();
+
+    /// Regex to match the first line of a failure that jest reports without a FAIL line, e.g.
+    /// when a worker crashed or setup failed before any test file ran
+    /// Example: A worker process has failed to exit gracefully and has been force exited...
+    /// Example: [UnhandledPromiseRejection: This error originated either by throwing inside of an async function without a catch block, ...
+    /// Example: Error: Jest: Got error running globalSetup - /home/runner/work/app/app/setup.ts, reason: connect ECONNREFUSED
+    static ref NON_FAIL_ERROR: Regex = Regex::new(
+        r"^(?:Jest did not exit|A worker process has failed|\[?UnhandledPromiseRejection|Unhandled promise rejection|Error: Jest: |● Test suite failed to run|● Validation Error)",
+    )
+    .unwrap();
+
+    /// Regex to match the lines of jest's summary and of GitHub Actions, which end a failure
+    /// without a FAIL line
+    /// Example: Test Suites: 12 passed, 12 of 13 total
+    /// Example: ##[error]Process completed with exit code 1.
+    static ref NON_FAIL_ERROR_END: Regex =
+        Regex::new(r"^(?:(?:Test Suites|Tests|Snapshots|Time):\s|Ran all test suites|##\[)").unwrap();
+}
+
+/// Path of errors that aren't of any test file
+const NON_FAIL_ERROR_PATH: &str = "jest";
+
+/// Whether the path is a test file's rather than that of errors outside test files
+pub fn is_file_path(path: &str) -> bool {
+    path != NON_FAIL_ERROR_PATH
+}
+
+/// Count the failed tests in the lines of a failed test file. Each failed test has a "●" heading
+/// with its error; when those are missing, the "✕" lines of the test summary are counted. A file
+/// that failed without failed tests, e.g. to compile, counts as one.
+pub fn failing_test_count(error: &CheckError) -> usize {
+    let count_starting_with = |bullet: &str| {
+        error
+            .lines
+            .iter()
+            .filter(|line| {
+                strip_ansi_escapes::strip_str(line)
+                    .trim_start()
+                    .starts_with(bullet)
+            })
+            .count()
+    };
+
+    [count_starting_with("●"), count_starting_with("✕")]
+        .into_iter()
+        .find(|&count| count > 0)
+        .unwrap_or(1)
+}
+
+/// Names of the failed tests in the lines of a failed test file, from their "●" headings, e.g.
+/// "Suite › fails". Tests that fail in several ways are named once.
+pub fn failed_test_names(error: &CheckError) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for line in &error.lines {
+        let line_no_ansi = strip_ansi_escapes::strip_str(line);
+        if let Some(name) = line_no_ansi.trim_start().strip_prefix('●') {
+            let name = name.trim().to_string();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// Remove the lines of the failed tests whose names don't match the pattern, keeping the lines
+/// before the first test. Returns whether any failed test is left.
+pub fn retain_failed_tests(error: &mut CheckError, pattern: &Regex) -> bool {
+    let mut keep = true;
+    let mut any_kept = false;
+    error.lines.retain(|line| {
+        let line_no_ansi = strip_ansi_escapes::strip_str(line);
+        if let Some(name) = line_no_ansi.trim_start().strip_prefix('●') {
+            keep = pattern.is_match(name.trim());
+            any_kept |= keep;
+        }
+        keep
+    });
+    any_kept
 }
 
 fn find_fail_start(log: &str) -> Option<usize> {
+    // With turbo/nx, jest output starts after the task prefix. Everything before the prefix end
+    // is discarded, so that the captured lines don't start with the prefix's escape sequences.
+    if let Some(prefix) = TASK_PREFIX.find(log) {
+        return find_fail_start_without_prefix(&log[prefix.end()..]).map(|i| prefix.end() + i);
+    }
+
+    find_fail_start_without_prefix(log)
+}
+
+fn find_fail_start_without_prefix(log: &str) -> Option<usize> {
     // First handle test_jest_in_docker case: ... |^[[0m FAIL src/b.test.ts
     // In this case, we should get the position where FAIL starts
     // Otherwise try to find left most escape sequence position before FAIL
@@ -43,6 +139,8 @@ pub struct JestLogParser {
     all_fails: Vec<CheckError>,
     current_fail_start_col: usize,
     current_fail_lines: Vec<String>,
+    fail_lines_seen: usize,
+    current_task_prefix: Option<String>,
 }
 
 impl JestLogParser {
@@ -53,6 +151,8 @@ impl JestLogParser {
             all_fails: Vec::new(),
             current_fail_start_col: 0,
             current_fail_lines: Vec::new(),
+            fail_lines_seen: 0,
+            current_task_prefix: None,
         }
     }
 
@@ -63,6 +163,7 @@ impl JestLogParser {
         match self.state {
             State::LookingForFail => {
                 if let Some(caps) = JEST_FAIL_LINE.captures(&line_no_ansi) {
+                    self.fail_lines_seen += 1;
                     // Attempt to find the column where the colored FAIL text starts.
                     // This column position will be used to determine where jest output starts.
                     // We can't just take everything after timestamp because there's possibility
@@ -70,6 +171,9 @@ impl JestLogParser {
                     // service name after timestamp.
                     // https://github.com/raine/ghtool/assets/11027/c349807a-cad1-45cb-b02f-4d5020bb3c23
                     self.current_fail_start_col = find_fail_start(&line_no_timestamp).unwrap();
+                    self.current_task_prefix = TASK_PREFIX
+                        .find(&line_no_timestamp)
+                        .map(|m| m.as_str().trim_end().to_string());
                     let path = caps.name("path").unwrap().as_str().to_string();
                     // Get line discarding things before the column where FAIL starts
                     let line = line_no_timestamp
@@ -84,6 +188,13 @@ impl JestLogParser {
                 }
             }
             State::ParsingFail => {
+                // Output of other turbo/nx tasks running in parallel may be interleaved
+                if let Some(task_prefix) = &self.current_task_prefix {
+                    if !line_no_timestamp.starts_with(task_prefix.as_str()) {
+                        return Ok(());
+                    }
+                }
+
                 let next_char_from_fail =
                     find_next_non_ansi_char(&line_no_timestamp, self.current_fail_start_col);
 
@@ -125,21 +236,68 @@ impl JestLogParser {
             parser.parse_line(line)?;
         }
 
-        Ok(parser.get_output())
+        let output = parser.get_output();
+        if output.is_empty() {
+            return Ok(parse_non_fail_errors(log));
+        }
+        Ok(output)
     }
 
     pub fn get_output(self) -> Vec<CheckError> {
-        self.all_fails
+        let output = self
+            .all_fails
             .into_iter()
             .fold(Vec::new(), |mut acc, fail| {
                 if !acc.contains(&fail) {
                     acc.push(fail);
                 }
                 acc
-            })
+            });
+        debug!(
+            fail_lines_seen = self.fail_lines_seen,
+            errors = output.len(),
+            "parsed jest log"
+        );
+        output
     }
 }
 
+/// Failures of a job without any FAIL line, so that a red job is explained even when no test file
+/// failed. Each is the paragraph starting with its first line.
+fn parse_non_fail_errors(log: &str) -> Vec<CheckError> {
+    let mut errors: Vec<CheckError> = Vec::new();
+    let mut current_error: Option<CheckError> = None;
+
+    for raw_line in log.lines() {
+        let line = TIMESTAMP.replace(raw_line, "");
+        let line = match TASK_PREFIX.find(&line) {
+            Some(prefix) => line[prefix.end()..].to_string(),
+            None => line.to_string(),
+        };
+        let line_no_ansi = strip_ansi_escapes::strip_str(&line);
+
+        if NON_FAIL_ERROR.is_match(line_no_ansi.trim_start()) {
+            errors.extend(current_error.take());
+            current_error = Some(CheckError {
+                path: NON_FAIL_ERROR_PATH.to_string(),
+                lines: vec![line],
+            });
+        } else if line_no_ansi.trim().is_empty() || NON_FAIL_ERROR_END.is_match(&line_no_ansi) {
+            errors.extend(current_error.take());
+        } else if let Some(error) = current_error.as_mut() {
+            error.lines.push(line);
+        }
+    }
+    errors.extend(current_error);
+
+    errors.into_iter().fold(Vec::new(), |mut acc, error| {
+        if !acc.contains(&error) {
+            acc.push(error);
+        }
+        acc
+    })
+}
+
 impl Default for JestLogParser {
     fn default() -> Self {
         Self::new()
@@ -221,6 +379,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_failing_test_count() {
+        let error = |lines: &[&str]| CheckError {
+            path: "src/a.test.ts".to_string(),
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+        };
+
+        assert_eq!(
+            failing_test_count(&error(&[
+                "FAIL  src/a.test.ts",
+                "   ✓ succeeds (3 ms)",
+                "   ✕ fails (5 ms)",
+                "   ✕ fails too (1 ms)",
+                " ● a › fails",
+                " ● a › fails too",
+            ])),
+            2
+        );
+        assert_eq!(
+            failing_test_count(&error(&["FAIL  src/a.test.ts", "   ✕ fails (5 ms)"])),
+            1
+        );
+        assert_eq!(
+            failing_test_count(&error(&["FAIL  src/a.test.ts", "  Cannot find module"])),
+            1
+        );
+    }
+
+    #[test]
+    fn test_failed_test_names() {
+        let error = CheckError {
+            path: "src/a.test.ts".to_string(),
+            lines: vec![
+                "FAIL  src/a.test.ts".to_string(),
+                "\u{1b}[1m\u{1b}[31m  \u{1b}[1m● \u{1b}[22m\u{1b}[1ma › fails\u{1b}[39m\u{1b}[22m"
+                    .to_string(),
+                "    expect(received).toBe(expected)".to_string(),
+                "  ● a › b › fails too".to_string(),
+                "  ● a › fails".to_string(),
+            ],
+        };
+
+        assert_eq!(
+            failed_test_names(&error),
+            vec!["a › fails".to_string(), "a › b › fails too".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_retain_failed_tests() {
+        let mut error = CheckError {
+            path: "src/a.test.ts".to_string(),
+            lines: vec![
+                "FAIL  src/a.test.ts".to_string(),
+                "  ● a › fails".to_string(),
+                "    expect(received).toBe(expected)".to_string(),
+                "  ● a › b › fails too".to_string(),
+                "    Expected: false".to_string(),
+            ],
+        };
+
+        assert!(retain_failed_tests(
+            &mut error,
+            &Regex::new("fails too").unwrap()
+        ));
+        assert_eq!(
+            error.lines,
+            vec![
+                "FAIL  src/a.test.ts".to_string(),
+                "  ● a › b › fails too".to_string(),
+                "    Expected: false".to_string(),
+            ]
+        );
+
+        assert!(!retain_failed_tests(&mut error, &Regex::new("^b").unwrap()));
+    }
+
     #[test]
     fn test_extract_failing_tests_2() {
         let logs = r#"
@@ -571,10 +806,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_turbo_prefix() {
+        let logs = r#"
+2024-05-11T20:45:16.0032874Z web:test: FAIL src/foo.test.ts
+2024-05-11T20:45:16.0034300Z web:test:   ● foo › fails
+2024-05-11T20:45:16.0034301Z web:test:
+2024-05-11T20:45:16.0034302Z web:test:     Expected: false
+2024-05-11T20:45:16.0034303Z api:build: Done in 3.2s
+2024-05-11T20:45:16.0034304Z web:test: PASS src/bar.test.ts"#;
+
+        let failing_tests = JestLogParser::parse(logs).unwrap();
+        assert_eq!(
+            failing_tests,
+            vec![CheckError {
+                path: "src/foo.test.ts".to_string(),
+                lines: vec![
+                    "FAIL src/foo.test.ts".to_string(),
+                    "  ● foo › fails".to_string(),
+                    "".to_string(),
+                    "    Expected: false".to_string(),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_turbo_colored_prefix() {
+        let logs = r#"
+2024-05-11T20:45:16.0032874Z [33mweb:test: [0mFAIL src/foo.test.ts
+2024-05-11T20:45:16.0034300Z [33mweb:test: [0m  ● foo › fails
+2024-05-11T20:45:16.0034301Z [33mweb:test: [0m
+2024-05-11T20:45:16.0034302Z [33mweb:test: [0m    Expected: false
+2024-05-11T20:45:16.0034304Z [33mweb:test: [0mPASS src/bar.test.ts"#;
+
+        let failing_tests = JestLogParser::parse(logs).unwrap();
+        assert_eq!(
+            failing_tests,
+            vec![CheckError {
+                path: "src/foo.test.ts".to_string(),
+                lines: vec![
+                    "FAIL src/foo.test.ts".to_string(),
+                    "  ● foo › fails".to_string(),
+                    "".to_string(),
+                    "    Expected: false".to_string(),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_nx_prefix_with_colored_jest() {
+        let logs = r#"
+2024-05-11T20:45:16.0032874Z [2m@org/web:test: [22m[0m[7m[1m[31m FAIL [39m[22m[27m[0m [2msrc/[22m[1mfoo.test.ts[22m
+2024-05-11T20:45:16.0034300Z [2m@org/web:test: [22m  ● foo › fails
+2024-05-11T20:45:16.0034301Z [2m@org/web:test: [22m
+2024-05-11T20:45:16.0034302Z [2m@org/web:test: [22m[1mTest Suites: [22m[1m[31m1 failed[39m[22m, 1 total"#;
+
+        let failing_tests = JestLogParser::parse(logs).unwrap();
+        assert_eq!(
+            failing_tests,
+            vec![CheckError {
+                path: "src/foo.test.ts".to_string(),
+                lines: vec![
+                    "[0m[7m[1m[31m FAIL [39m[22m[27m[0m [2msrc/[22m[1mfoo.test.ts[22m"
+                        .to_string(),
+                    "  ● foo › fails".to_string(),
+                ],
+            }]
+        );
+    }
+
     #[test]
     fn test_find_next_non_ansi_char() {
         let str = " \u{1b}[32m\u{1b}[31m ";
         let start_col = 1;
         assert_eq!(find_next_non_ansi_char(str, start_col), Some(' '));
     }
+
+    /// Parses each fixtures/*.log and compares the result to the matching .expected file, which
+    /// is the same output `parse_jest_log` in ghtool_devtools prints. Run with UPDATE_GOLDEN=1 to
+    /// regenerate the expected files.
+    #[test]
+    fn test_golden_fixtures() {
+        let fixtures_dir =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/commands/test/fixtures");
+        let mut log_paths = std::fs::read_dir(&fixtures_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+            .collect::<Vec<_>>();
+        log_paths.sort();
+        assert!(!log_paths.is_empty());
+
+        for log_path in log_paths {
+            let log = std::fs::read_to_string(&log_path).unwrap();
+            let parsed = JestLogParser::parse(&log).unwrap();
+            let actual = format!("{parsed:#?}\n");
+            let expected_path = log_path.with_extension("expected");
+
+            if std::env::var("UPDATE_GOLDEN").is_ok() {
+                std::fs::write(&expected_path, &actual).unwrap();
+            }
+
+            let expected = std::fs::read_to_string(&expected_path).unwrap();
+            assert_eq!(actual, expected, "fixture {}", log_path.display());
+        }
+    }
 }
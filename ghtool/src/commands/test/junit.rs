@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+};
+
+use eyre::Result;
+use serde::Deserialize;
+use tracing::{debug, info};
+
+use crate::{
+    commands::CheckError,
+    git::Repository,
+    github::{GithubClient, SimpleCheckRun},
+    spinner::new_spinner,
+};
+
+#[derive(Deserialize, Debug)]
+struct TestSuites {
+    #[serde(rename = "testsuite", default)]
+    test_suites: Vec<TestSuite>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TestSuite {
+    #[serde(rename = "@file")]
+    file: Option<String>,
+    #[serde(rename = "testcase", default)]
+    test_cases: Vec<TestCase>,
+    // Some reporters nest test suites
+    #[serde(rename = "testsuite", default)]
+    test_suites: Vec<TestSuite>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TestCase {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@classname")]
+    classname: Option<String>,
+    #[serde(rename = "@file")]
+    file: Option<String>,
+    #[serde(rename = "failure", default)]
+    failures: Vec<Failure>,
+    #[serde(rename = "error", default)]
+    errors: Vec<Failure>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Failure {
+    #[serde(rename = "@message")]
+    message: Option<String>,
+    #[serde(rename = "$text")]
+    text: Option<String>,
+}
+
+/// Parse failed test cases of a JUnit XML report. A test case's path is its file attribute,
+/// falling back to the test suite's file and then the test case's classname.
+pub fn parse_junit_xml(xml: &str) -> Result<Vec<CheckError>> {
+    let test_suites = if xml.contains("<testsuites") {
+        quick_xml::de::from_str::<TestSuites>(xml)?.test_suites
+    } else {
+        vec![quick_xml::de::from_str::<TestSuite>(xml)?]
+    };
+
+    let mut check_errors = Vec::new();
+    for test_suite in &test_suites {
+        collect_failures(test_suite, &mut check_errors);
+    }
+    Ok(check_errors)
+}
+
+fn collect_failures(test_suite: &TestSuite, check_errors: &mut Vec<CheckError>) {
+    for test_case in &test_suite.test_cases {
+        let failures: Vec<_> = test_case
+            .failures
+            .iter()
+            .chain(test_case.errors.iter())
+            .collect();
+        if failures.is_empty() {
+            continue;
+        }
+
+        let path = test_case
+            .file
+            .as_ref()
+            .or(test_suite.file.as_ref())
+            .or(test_case.classname.as_ref())
+            .cloned()
+            .unwrap_or_default();
+        let title = match &test_case.classname {
+            Some(classname) if !test_case.name.starts_with(classname.as_str()) => {
+                format!("● {} › {}", classname, test_case.name)
+            }
+            _ => format!("● {}", test_case.name),
+        };
+
+        let mut lines = vec![title, "".to_string()];
+        for failure in failures {
+            match failure.text.as_deref().map(str::trim) {
+                Some(text) if !text.is_empty() => {
+                    lines.extend(text.lines().map(|line| format!("    {}", line)))
+                }
+                _ => lines.extend(failure.message.iter().map(|m| format!("    {}", m))),
+            }
+            lines.push("".to_string());
+        }
+
+        check_errors.push(CheckError { path, lines });
+    }
+
+    for nested in &test_suite.test_suites {
+        collect_failures(nested, check_errors);
+    }
+}
+
+/// Parse all XML files in a zipped artifact as JUnit reports
+pub fn parse_junit_archive(bytes: &[u8]) -> Result<Vec<CheckError>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut check_errors = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let name = file.name()?.to_string();
+        if !file.is_file() || !name.ends_with(".xml") {
+            continue;
+        }
+
+        debug!(?name, "parsing junit report");
+        let mut xml = String::new();
+        file.read_to_string(&mut xml)?;
+        check_errors.extend(parse_junit_xml(&xml)?);
+    }
+
+    Ok(check_errors)
+}
+
+/// Errors of JUnit report artifacts by workflow run id and artifact name, so that the jobs of a
+/// workflow run share one download
+pub type JunitReports = HashMap<(u64, String), Option<Vec<CheckError>>>;
+
+/// Get errors from the JUnit report artifact of the check run's workflow run. Returns None if
+/// the workflow run has no artifact with the name.
+pub async fn fetch_junit_errors(
+    client: &GithubClient,
+    repo: &Repository,
+    check_run: &SimpleCheckRun,
+    artifact_name: &str,
+    reports: &mut JunitReports,
+) -> Result<Option<Vec<CheckError>>> {
+    let Some(run_id) = check_run.workflow_run_id() else {
+        info!(name = ?check_run.name, "no workflow run id for check run");
+        return Ok(None);
+    };
+    let key = (run_id, artifact_name.to_string());
+    if let Some(check_errors) = reports.get(&key) {
+        return Ok(check_errors.clone());
+    }
+    let check_errors = download_junit_errors(client, repo, check_run, run_id, artifact_name).await;
+    // A report that failed to download isn't retried for the workflow run's other jobs
+    reports.insert(key, check_errors.as_ref().ok().cloned().flatten());
+    check_errors
+}
+
+async fn download_junit_errors(
+    client: &GithubClient,
+    repo: &Repository,
+    check_run: &SimpleCheckRun,
+    run_id: u64,
+    artifact_name: &str,
+) -> Result<Option<Vec<CheckError>>> {
+    let pb = new_spinner(format!(
+        "Fetching test report for check: {}",
+        check_run.name
+    ));
+    let artifacts = client
+        .list_artifacts(&repo.owner, &repo.name, run_id)
+        .await?;
+    let Some(artifact) = artifacts.iter().find(|a| a.name == artifact_name) else {
+        pb.finish_and_clear();
+        info!(?run_id, ?artifact_name, "artifact not found");
+        return Ok(None);
+    };
+
+    let bytes = client
+        .download_artifact(&repo.owner, &repo.name, artifact.id)
+        .await?;
+    pb.finish_and_clear();
+
+    let check_errors = tokio::task::spawn_blocking(move || parse_junit_archive(&bytes)).await??;
+    Ok(Some(check_errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+
+    const JEST_JUNIT_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="jest tests" tests="3" failures="2" errors="0" time="1.2">
+  <testsuite name="sum" tests="2" failures="1" file="src/sum.test.ts">
+    <testcase classname="sum" name="sum adds numbers" time="0.01">
+    </testcase>
+    <testcase classname="sum" name="sum handles negatives" time="0.01">
+      <failure>Error: expect(received).toBe(expected)
+
+Expected: -1
+Received: 1</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name="api" tests="1" failures="1">
+    <testcase classname="api" name="fetches" file="src/api.test.ts" time="0.5">
+      <failure message="timeout"><![CDATA[Exceeded timeout of 5000 ms]]></failure>
+    </testcase>
+  </testsuite>
+</testsuites>"#;
+
+    #[test]
+    fn test_parse_junit_xml() {
+        let output = parse_junit_xml(JEST_JUNIT_XML).unwrap();
+        assert_eq!(
+            output,
+            vec![
+                CheckError {
+                    path: "src/sum.test.ts".to_string(),
+                    lines: vec![
+                        "● sum handles negatives".to_string(),
+                        "".to_string(),
+                        "    Error: expect(received).toBe(expected)".to_string(),
+                        "    ".to_string(),
+                        "    Expected: -1".to_string(),
+                        "    Received: 1".to_string(),
+                        "".to_string(),
+                    ],
+                },
+                CheckError {
+                    path: "src/api.test.ts".to_string(),
+                    lines: vec![
+                        "● api › fetches".to_string(),
+                        "".to_string(),
+                        "    Exceeded timeout of 5000 ms".to_string(),
+                        "".to_string(),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_junit_xml_single_test_suite() {
+        let xml = r#"<testsuite name="Tests">
+  <testcase classname="tests.test_math" name="test_div">
+    <error message="ZeroDivisionError: division by zero"/>
+  </testcase>
+</testsuite>"#;
+        let output = parse_junit_xml(xml).unwrap();
+        assert_eq!(
+            output,
+            vec![CheckError {
+                path: "tests.test_math".to_string(),
+                lines: vec![
+                    "● tests.test_math › test_div".to_string(),
+                    "".to_string(),
+                    "    ZeroDivisionError: division by zero".to_string(),
+                    "".to_string(),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_junit_archive() {
+        let mut zip_bytes = Vec::new();
+        {
+            let mut writer = zip::ZipWriter::new(Cursor::new(&mut zip_bytes));
+            let options = zip::write::SimpleFileOptions::default();
+            writer.start_file("junit.xml", options).unwrap();
+            writer.write_all(JEST_JUNIT_XML.as_bytes()).unwrap();
+            writer.start_file("README.txt", options).unwrap();
+            writer.write_all(b"not a report").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let paths: Vec<_> = parse_junit_archive(&zip_bytes)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        assert_eq!(paths, vec!["src/sum.test.ts", "src/api.test.ts"]);
+    }
+}
@@ -1,23 +1,24 @@
 use eyre::Result;
 use regex::Regex;
 
+use crate::error::GhtoolError;
 use crate::repo_config::RepoConfig;
 use crate::repo_config::TestConfig;
+use crate::repo_config::TestRunner;
 
+mod deno;
+mod gradle;
 pub mod jest;
+pub mod junit;
 
+use deno::DenoLogParser;
+use gradle::GradleLogParser;
 use jest::*;
 
 use super::command::CheckError;
 use super::command::Command;
 use super::command::ConfigPattern;
 
-impl ConfigPattern for TestConfig {
-    fn job_pattern(&self) -> &Regex {
-        &self.job_pattern
-    }
-}
-
 #[derive(Clone)]
 pub struct TestCommand {
     config: TestConfig,
@@ -28,7 +29,7 @@ impl TestCommand {
         let test_config = repo_config
             .test
             .clone()
-            .ok_or_else(|| eyre::eyre!("Error: no test section found in .ghtool.toml"))?;
+            .ok_or(GhtoolError::MissingConfigSection("test"))?;
 
         Ok(Self {
             config: test_config,
@@ -46,10 +47,57 @@ impl Command for TestCommand {
     }
 
     fn config(&self) -> &dyn ConfigPattern {
-        &self.config
+        &self.config.section
+    }
+
+    fn shard_pattern(&self) -> Option<&Regex> {
+        self.config.shard_pattern.as_ref()
     }
 
     fn parse_log(&self, log: &str) -> Result<Vec<CheckError>> {
-        JestLogParser::parse(log)
+        match self.config.tool {
+            TestRunner::Jest => JestLogParser::parse(log),
+            TestRunner::Gradle => Ok(GradleLogParser::parse(log)),
+            TestRunner::DenoTest => Ok(DenoLogParser::parse(log)),
+        }
+    }
+
+    fn junit_artifact(&self) -> Option<&str> {
+        self.config.junit_artifact.as_deref()
+    }
+
+    fn is_file_path(&self, path: &str) -> bool {
+        match self.config.tool {
+            TestRunner::Jest => jest::is_file_path(path),
+            // Errors are of test classes, or of the failed task
+            TestRunner::Gradle => false,
+            TestRunner::DenoTest => true,
+        }
+    }
+
+    fn failure_count(&self, error: &CheckError) -> usize {
+        match self.config.tool {
+            TestRunner::Jest => failing_test_count(error),
+            // Each failed test is an error of its own
+            TestRunner::Gradle | TestRunner::DenoTest => 1,
+        }
+    }
+
+    fn failed_tests(&self, error: &CheckError) -> Vec<String> {
+        match self.config.tool {
+            TestRunner::Jest => failed_test_names(error),
+            TestRunner::Gradle => gradle::failed_test_name(error).into_iter().collect(),
+            TestRunner::DenoTest => deno::failed_test_name(error).into_iter().collect(),
+        }
+    }
+
+    fn retain_failed_tests(&self, error: &mut CheckError, pattern: &Regex) -> bool {
+        match self.config.tool {
+            TestRunner::Jest => jest::retain_failed_tests(error, pattern),
+            TestRunner::Gradle | TestRunner::DenoTest => self
+                .failed_tests(error)
+                .iter()
+                .any(|name| pattern.is_match(name)),
+        }
     }
 }
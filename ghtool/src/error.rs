@@ -0,0 +1,162 @@
+use reqwest::StatusCode;
+
+use crate::github::GithubApiError;
+
+/// Failed checks were found
+pub const CHECK_ERRORS_EXIT_CODE: i32 = 1;
+pub const NO_PULL_REQUEST_EXIT_CODE: i32 = 2;
+/// .ghtool.toml is missing, invalid or lacks the command's section, or the repository can't be
+/// found
+pub const CONFIG_ERROR_EXIT_CODE: i32 = 3;
+/// No token was found, or GitHub didn't accept it
+pub const AUTH_ERROR_EXIT_CODE: i32 = 4;
+/// Requests to GitHub failed, timed out or were rate limited
+pub const NETWORK_ERROR_EXIT_CODE: i32 = 5;
+/// ghtool failed for a reason not covered by the other exit codes
+pub const INTERNAL_ERROR_EXIT_CODE: i32 = 6;
+/// The commit or workflow run has no checks and none were queued, so there's nothing to wait for
+pub const NO_CHECKS_EXIT_CODE: i32 = 7;
+
+/// Errors of ghtool itself, as opposed to e.g. errors from the GitHub API. Functions returning
+/// `eyre::Result` return these inside the report, so they can be told apart with
+/// `report.downcast_ref::<GhtoolError>()`. Messages are plain text; they're styled where printed.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum GhtoolError {
+    /// The section of the command, e.g. test, is missing from .ghtool.toml
+    #[error("No {0} section found in .ghtool.toml")]
+    MissingConfigSection(&'static str),
+
+    #[error("Not in a git repository")]
+    NotInGitRepository,
+
+    #[error("No remote named 'origin'; configure one or pass --repo")]
+    NoOriginRemote,
+
+    #[error("Couldn't read config from path {path}")]
+    ConfigNotReadable { path: String },
+
+    #[error("No pull request found for branch {branch}")]
+    NoPullRequest { branch: String },
+
+    /// No token has been stored for the host with ghtool login
+    #[error("No token found for {hostname}. Have you logged in? Run ghtool login")]
+    NotLoggedIn { hostname: String },
+
+    /// The commit has no check runs, e.g. in a repository without workflows
+    #[error("No checks configured for this commit")]
+    NoChecks,
+
+    /// The workflow run given with --run has no jobs, e.g. when all of them were skipped
+    #[error("Workflow run {run_id} has no jobs")]
+    NoWorkflowRunJobs { run_id: u64 },
+
+    /// The gh CLI has no token for the host in its hosts.yml
+    #[error("No gh CLI token found for {hostname}. Have you logged in with gh auth login?")]
+    NoGhToken { hostname: String },
+}
+
+/// Exit code for an error that ghtool failed with, so that scripts can tell e.g. a missing pull
+/// request from a network error
+pub fn exit_code(report: &eyre::Report) -> i32 {
+    if let Some(error) = report.downcast_ref::<GhtoolError>() {
+        return match error {
+            GhtoolError::NoPullRequest { .. } => NO_PULL_REQUEST_EXIT_CODE,
+            GhtoolError::MissingConfigSection(_)
+            | GhtoolError::NotInGitRepository
+            | GhtoolError::NoOriginRemote
+            | GhtoolError::ConfigNotReadable { .. } => CONFIG_ERROR_EXIT_CODE,
+            GhtoolError::NotLoggedIn { .. } | GhtoolError::NoGhToken { .. } => AUTH_ERROR_EXIT_CODE,
+            GhtoolError::NoChecks | GhtoolError::NoWorkflowRunJobs { .. } => NO_CHECKS_EXIT_CODE,
+        };
+    }
+    if report.downcast_ref::<toml::de::Error>().is_some() {
+        return CONFIG_ERROR_EXIT_CODE;
+    }
+    match report.downcast_ref::<GithubApiError>() {
+        Some(GithubApiError::ErrorResponse(status, body)) if is_rate_limited(*status, body) => {
+            return NETWORK_ERROR_EXIT_CODE
+        }
+        Some(GithubApiError::SsoRequired { .. })
+        | Some(GithubApiError::ErrorResponse(
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN,
+            _,
+        )) => return AUTH_ERROR_EXIT_CODE,
+        Some(GithubApiError::ReqwestError(_)) => return NETWORK_ERROR_EXIT_CODE,
+        _ => {}
+    }
+    if report.downcast_ref::<reqwest::Error>().is_some() {
+        return NETWORK_ERROR_EXIT_CODE;
+    }
+    INTERNAL_ERROR_EXIT_CODE
+}
+
+/// Whether GitHub refused the request for exceeding a rate limit. GitHub responds to those with a
+/// 403 like to an unaccepted token, or a 429, and says so in the message.
+fn is_rate_limited(status: StatusCode, body: &str) -> bool {
+    matches!(
+        status,
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+    ) && body.to_lowercase().contains("rate limit")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_downcast_from_report() {
+        let report: eyre::Report = GhtoolError::MissingConfigSection("lint").into();
+        assert_eq!(report.to_string(), "No lint section found in .ghtool.toml");
+        assert_eq!(
+            report.downcast_ref::<GhtoolError>(),
+            Some(&GhtoolError::MissingConfigSection("lint"))
+        );
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let no_pull_request = GhtoolError::NoPullRequest {
+            branch: "feature".to_string(),
+        };
+        assert_eq!(
+            exit_code(&eyre::Report::from(no_pull_request)),
+            NO_PULL_REQUEST_EXIT_CODE
+        );
+        assert_eq!(
+            exit_code(&eyre::Report::from(GhtoolError::NoOriginRemote)),
+            CONFIG_ERROR_EXIT_CODE
+        );
+        let not_readable = eyre::eyre!("No such file").wrap_err(GhtoolError::ConfigNotReadable {
+            path: ".ghtool.toml".to_string(),
+        });
+        assert_eq!(exit_code(&not_readable), CONFIG_ERROR_EXIT_CODE);
+        let unauthorized =
+            GithubApiError::ErrorResponse(StatusCode::UNAUTHORIZED, "Bad credentials".to_string());
+        assert_eq!(
+            exit_code(&eyre::Report::from(unauthorized)),
+            AUTH_ERROR_EXIT_CODE
+        );
+        assert_eq!(
+            exit_code(&eyre::Report::from(GhtoolError::NoChecks)),
+            NO_CHECKS_EXIT_CODE
+        );
+        let forbidden = GithubApiError::ErrorResponse(
+            StatusCode::FORBIDDEN,
+            "Resource not accessible by integration".to_string(),
+        );
+        assert_eq!(
+            exit_code(&eyre::Report::from(forbidden)),
+            AUTH_ERROR_EXIT_CODE
+        );
+        let rate_limited = GithubApiError::ErrorResponse(
+            StatusCode::FORBIDDEN,
+            r#"{"message":"API rate limit exceeded for user ID 1."}"#.to_string(),
+        );
+        assert_eq!(
+            exit_code(&eyre::Report::from(rate_limited)),
+            NETWORK_ERROR_EXIT_CODE
+        );
+        assert_eq!(exit_code(&eyre::eyre!("other")), INTERNAL_ERROR_EXIT_CODE);
+    }
+}
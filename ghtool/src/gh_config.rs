@@ -0,0 +1,91 @@
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use eyre::{Result, WrapErr};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::error::GhtoolError;
+
+/// Authentication of the gh CLI, read from its hosts.yml
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct GhConfig {
+    hosts: HashMap<String, GhHost>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhHost {
+    oauth_token: Option<String>,
+}
+
+impl GhConfig {
+    pub fn read() -> Result<GhConfig> {
+        let path = hosts_path().ok_or_else(|| eyre::eyre!("Error finding gh config directory"))?;
+        let contents = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Error reading gh config from {}", path.display()))?;
+        info!(?path, "read gh config");
+        GhConfig::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> Result<GhConfig> {
+        Ok(serde_yaml::from_str(contents)?)
+    }
+
+    /// The token that gh uses for the host. None when gh isn't logged in to the host, or keeps the
+    /// token in the system keyring instead of hosts.yml.
+    fn token(&self, hostname: &str) -> Option<&str> {
+        self.hosts
+            .get(hostname)
+            .and_then(|host| host.oauth_token.as_deref())
+            .filter(|token| !token.is_empty())
+    }
+}
+
+/// Get the token of the gh CLI for the host
+pub fn get_gh_token(hostname: &str) -> Result<String> {
+    let no_token = || GhtoolError::NoGhToken {
+        hostname: hostname.to_string(),
+    };
+    let config = GhConfig::read().wrap_err_with(no_token)?;
+    config
+        .token(hostname)
+        .map(String::from)
+        .ok_or_else(|| no_token().into())
+}
+
+/// Same lookup as gh: GH_CONFIG_DIR, then XDG_CONFIG_HOME/gh, then the platform's default
+fn hosts_path() -> Option<PathBuf> {
+    let config_dir = match (env::var_os("GH_CONFIG_DIR"), env::var_os("XDG_CONFIG_HOME")) {
+        (Some(dir), _) => PathBuf::from(dir),
+        (None, Some(dir)) => PathBuf::from(dir).join("gh"),
+        (None, None) if cfg!(windows) => dirs::config_dir()?.join("GitHub CLI"),
+        (None, None) => dirs::home_dir()?.join(".config").join("gh"),
+    };
+    Some(config_dir.join("hosts.yml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_token() {
+        let config = GhConfig::parse(
+            r#"
+github.com:
+    user: raine
+    oauth_token: gho_abc123
+    git_protocol: ssh
+github.example.com:
+    user: raine
+    git_protocol: https
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.token("github.com"), Some("gho_abc123"));
+        assert_eq!(config.token("github.example.com"), None);
+        assert_eq!(config.token("gitlab.com"), None);
+    }
+}
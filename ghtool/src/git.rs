@@ -1,6 +1,8 @@
 use eyre::Result;
 use std::path::PathBuf;
 
+use crate::{error::GhtoolError, trace::trace_command};
+
 #[derive(Debug, Clone)]
 pub struct Repository {
     pub owner: String,
@@ -18,40 +20,44 @@ pub struct Git {
     pub directory: PathBuf,
 }
 
-const GITHUB_HOSTNAME: &str = "github.com";
-
 // Example url: git@github.com:raine/tgreddit.git
 fn parse_repository(url: &str) -> Result<Repository> {
-    let mut parts = url.trim().split(':');
-    let host = parts.next();
-    let mut parts = parts.next().unwrap().split('/');
-    let owner = parts.next().unwrap().to_string();
-    let name = parts
-        .next()
-        .unwrap()
-        .strip_suffix(".git")
-        .unwrap()
-        .to_string();
-    let hostname = host.unwrap().split('@').nth(1).unwrap().to_string();
+    let url = url.trim();
+    if url.is_empty() {
+        return Err(GhtoolError::NoOriginRemote.into());
+    }
+    let invalid_url =
+        || eyre::eyre!("Unsupported remote url {url}, expected e.g. git@github.com:owner/name.git");
+
+    let (host, path) = url.split_once(':').ok_or_else(invalid_url)?;
+    let (owner, name) = path.split_once('/').ok_or_else(invalid_url)?;
+    let name = name.strip_suffix(".git").ok_or_else(invalid_url)?;
+    let hostname = host.split('@').nth(1).ok_or_else(invalid_url)?;
     Ok(Repository {
-        owner,
-        name,
-        hostname,
+        owner: owner.to_string(),
+        name: name.to_string(),
+        hostname: hostname.to_string(),
     })
 }
 
 // Example input: raine/tgreddit
-pub fn parse_repository_from_github(s: &str) -> Result<Repository> {
-    let mut parts = s.trim().split('/');
-    let owner = parts.next().unwrap().to_string();
-    let name = parts.next().unwrap().to_string();
-    let hostname = GITHUB_HOSTNAME.to_string();
+pub fn parse_repository_from_github(s: &str, hostname: &str) -> Result<Repository> {
+    match s.trim().split('/').collect::<Vec<_>>()[..] {
+        [owner, name] if !owner.is_empty() && !name.is_empty() => Ok(Repository {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            hostname: hostname.to_string(),
+        }),
+        _ => eyre::bail!("Invalid repository {}, expected format owner/name", s),
+    }
+}
 
-    Ok(Repository {
-        owner,
-        name,
-        hostname,
-    })
+/// Branch from the output of git rev-parse. The name is used as is, e.g. feature/foo#bar, since
+/// GitHub matches head refs by their literal name. Git doesn't allow whitespace in branch names, so
+/// trimming only removes the newline.
+fn parse_branch(stdout: Vec<u8>) -> Result<String> {
+    let branch = String::from_utf8(stdout)?;
+    Ok(branch.trim().to_string())
 }
 
 impl Git {
@@ -60,27 +66,26 @@ impl Git {
     }
 
     pub fn get_branch(&self) -> Result<String> {
-        let output = std::process::Command::new("git")
-            .arg("rev-parse")
-            .arg("--abbrev-ref")
-            .arg("HEAD")
-            .current_dir(&self.directory)
-            .output()?;
-        let branch = String::from_utf8(output.stdout)?;
-        Ok(branch.trim().to_string())
+        let output = self.run(&["rev-parse", "--abbrev-ref", "HEAD"])?;
+        parse_branch(output.stdout)
     }
 
     pub fn get_remote(&self) -> Result<Repository> {
-        let output = std::process::Command::new("git")
-            .arg("remote")
-            .arg("get-url")
-            .arg("origin")
-            .current_dir(&self.directory)
-            .output()?;
+        let output = self.run(&["remote", "get-url", "origin"])?;
+        if !output.status.success() {
+            return Err(GhtoolError::NoOriginRemote.into());
+        }
         let url = String::from_utf8(output.stdout)?;
         let repository = parse_repository(&url)?;
         Ok(repository)
     }
+
+    fn run(&self, args: &[&str]) -> Result<std::process::Output> {
+        let mut command = std::process::Command::new("git");
+        command.args(args).current_dir(&self.directory);
+        trace_command(&command);
+        Ok(command.output()?)
+    }
 }
 
 #[cfg(test)]
@@ -96,4 +101,43 @@ mod tests {
         assert_eq!(repository.name, "tgreddit");
         assert_eq!(repository.hostname, "github.com");
     }
+
+    #[test]
+    fn test_parse_repository_without_remote() {
+        for output in ["", "\n"] {
+            let err = parse_repository(output).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<GhtoolError>(),
+                Some(&GhtoolError::NoOriginRemote)
+            );
+        }
+        assert!(parse_repository("https://github.com/raine/ghtool").is_err());
+    }
+
+    #[test]
+    fn test_parse_branch() {
+        for branch in [
+            "main",
+            "feature/foo",
+            "feature/foo#bar",
+            "raine/fix-ä-ö",
+            "修正/テスト",
+        ] {
+            let stdout = format!("{}\n", branch).into_bytes();
+            assert_eq!(parse_branch(stdout).unwrap(), branch);
+        }
+    }
+
+    #[test]
+    fn test_parse_repository_from_github() {
+        let repository =
+            parse_repository_from_github("raine/ghtool", "github.example.com").unwrap();
+        assert_eq!(repository.owner, "raine");
+        assert_eq!(repository.name, "ghtool");
+        assert_eq!(repository.hostname, "github.example.com");
+
+        assert!(parse_repository_from_github("raine", "github.com").is_err());
+        assert!(parse_repository_from_github("raine/ghtool/extra", "github.com").is_err());
+        assert!(parse_repository_from_github("/ghtool", "github.com").is_err());
+    }
 }
@@ -0,0 +1,169 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use eyre::{Result, WrapErr};
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::trace;
+
+const GITHUB_API_URI: &str = "https://api.github.com";
+
+/// GitHub App whose installation is authenticated as, e.g. for bots, instead of a user
+#[derive(Debug, Clone)]
+pub struct AppCredentials {
+    pub app_id: String,
+    pub installation_id: u64,
+    /// Private key of the app in PEM format
+    pub private_key: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct InstallationToken {
+    token: String,
+}
+
+/// Exchange a JWT signed with the app's private key for an installation access token, which is
+/// valid for an hour
+pub async fn get_installation_token(credentials: &AppCredentials) -> Result<String> {
+    create_installation_token(GITHUB_API_URI, credentials, Utc::now()).await
+}
+
+async fn create_installation_token(
+    base_uri: &str,
+    credentials: &AppCredentials,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let jwt = app_jwt(&credentials.app_id, &credentials.private_key, now)?;
+    let client = super::http_config::client_builder()?
+        .user_agent("ghtool")
+        .build()
+        .map_err(|e| eyre::eyre!("Failed to build client: {}", e))?;
+
+    let url = format!(
+        "{base_uri}/app/installations/{}/access_tokens",
+        credentials.installation_id
+    );
+    info!(
+        installation_id = credentials.installation_id,
+        "Creating installation token"
+    );
+    let res = trace::send(
+        client
+            .post(url)
+            .bearer_auth(jwt)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json"),
+    )
+    .await?;
+
+    let status = res.status();
+    if !status.is_success() {
+        let body = res.text().await?;
+        eyre::bail!(
+            "Failed to create installation token for app {}: {} {}",
+            credentials.app_id,
+            status,
+            body
+        );
+    }
+    let installation_token: InstallationToken = res.json().await?;
+    Ok(installation_token.token)
+}
+
+/// JWT that authenticates as the app itself. It's issued a minute in the past to allow for clock
+/// drift, and expires before GitHub's limit of 10 minutes.
+fn app_jwt(app_id: &str, private_key: &str, now: DateTime<Utc>) -> Result<String> {
+    let header = serde_json::json!({ "alg": "RS256", "typ": "JWT" });
+    let claims = serde_json::json!({
+        "iat": (now - chrono::Duration::seconds(60)).timestamp(),
+        "exp": (now + chrono::Duration::seconds(540)).timestamp(),
+        "iss": app_id,
+    });
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+
+    let key = PKey::private_key_from_pem(private_key.as_bytes())
+        .wrap_err("Failed to read the private key of the GitHub App")?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    let signature = signer.sign_oneshot_to_vec(signing_input.as_bytes())?;
+
+    Ok(format!(
+        "{signing_input}.{}",
+        URL_SAFE_NO_PAD.encode(signature)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use openssl::{rsa::Rsa, sign::Verifier};
+    use pretty_assertions::assert_eq;
+    use wiremock::matchers::{header_regex, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn credentials() -> (AppCredentials, PKey<openssl::pkey::Private>) {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let private_key = String::from_utf8(key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+        let credentials = AppCredentials {
+            app_id: "12345".to_string(),
+            installation_id: 678,
+            private_key,
+        };
+        (credentials, key)
+    }
+
+    #[test]
+    fn test_app_jwt() {
+        let (credentials, key) = credentials();
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+        let jwt = app_jwt(&credentials.app_id, &credentials.private_key, now).unwrap();
+
+        let (signing_input, signature) = jwt.rsplit_once('.').unwrap();
+        let mut verifier = Verifier::new(MessageDigest::sha256(), &key).unwrap();
+        assert!(verifier
+            .verify_oneshot(
+                &URL_SAFE_NO_PAD.decode(signature).unwrap(),
+                signing_input.as_bytes()
+            )
+            .unwrap());
+
+        let claims = signing_input.split('.').nth(1).unwrap();
+        let claims: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(claims).unwrap()).unwrap();
+        assert_eq!(
+            claims,
+            serde_json::json!({
+                "iat": now.timestamp() - 60,
+                "exp": now.timestamp() + 540,
+                "iss": "12345",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_installation_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/app/installations/678/access_tokens"))
+            .and(header_regex(
+                "authorization",
+                r"^Bearer [\w-]+\.[\w-]+\.[\w-]+$",
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "token": "ghs_installation",
+                "expires_at": "2024-03-01T11:00:00Z"
+            })))
+            .mount(&server)
+            .await;
+
+        let (credentials, _) = credentials();
+        let token = create_installation_token(&server.uri(), &credentials, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(token, "ghs_installation");
+    }
+}
@@ -3,6 +3,8 @@ use http::HeaderMap;
 use serde::Deserialize;
 use tracing::{error, info};
 
+use crate::trace;
+
 pub struct GithubAuthClient {
     client: reqwest::Client,
 }
@@ -42,7 +44,7 @@ pub enum AccessTokenResponse {
 
 impl GithubAuthClient {
     pub fn new() -> Result<Self> {
-        let client = reqwest::Client::builder()
+        let client = super::http_config::client_builder()?
             .user_agent(USER_AGENT)
             .default_headers(make_headers())
             .build()
@@ -55,7 +57,7 @@ impl GithubAuthClient {
         let params = [("client_id", CLIENT_ID), ("scope", "repo")];
         let url = format!("{}/login/device/code", GITHUB_BASE_URI);
         info!("Requesting device code from {}", url);
-        let res = self.client.post(url).form(&params).send().await?;
+        let res = trace::send(self.client.post(url).form(&params)).await?;
         let code_response: CodeResponse = res.json().await?;
         info!("Received device code: {:?}", code_response);
         Ok(code_response)
@@ -69,7 +71,7 @@ impl GithubAuthClient {
         ];
         let url = format!("{}/login/oauth/access_token", GITHUB_BASE_URI);
         info!("Requesting access token from {}", url);
-        let res = self.client.post(url).form(&params).send().await?;
+        let res = trace::send(self.client.post(url).form(&params)).await?;
 
         if res.status().is_success() {
             let bytes = res.bytes().await?;
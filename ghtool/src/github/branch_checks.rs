@@ -0,0 +1,39 @@
+use eyre::Result;
+
+use super::default_branch_checks::{extract_ref_check_runs, Ref};
+use super::PullRequestChecks;
+
+use cynic_github_schema as schema;
+
+pub fn extract_check_runs(branch_checks: BranchChecks, branch: &str) -> Result<PullRequestChecks> {
+    let branch_ref = branch_checks
+        .repository
+        .ok_or_else(|| eyre::eyre!("Repository not found"))?
+        .branch_ref
+        .ok_or_else(|| eyre::eyre!("Branch {} not found", branch))?;
+    extract_ref_check_runs(branch_ref)
+}
+
+// Below is generated with https://generator.cynic-rs.dev using ./branch_checks.graphql, reusing
+// the fragments of ./default_branch_checks.graphql below the ref.
+#[derive(cynic::QueryVariables, Debug)]
+pub struct BranchChecksVariables<'a> {
+    pub owner: &'a str,
+    pub qualified_name: &'a str,
+    pub repo: &'a str,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query", variables = "BranchChecksVariables")]
+pub struct BranchChecks {
+    #[arguments(owner: $owner, name: $repo)]
+    pub repository: Option<Repository>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(variables = "BranchChecksVariables")]
+pub struct Repository {
+    #[arguments(qualifiedName: $qualified_name)]
+    #[cynic(rename = "ref")]
+    pub branch_ref: Option<Ref>,
+}
@@ -5,32 +5,48 @@
 // 3. Insert graphql query to query builder.
 // 4. On the right, copy the generated Rust and create a new file with it.
 
-use std::borrow::Cow;
-use std::time::Duration;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
 
-use cynic::http::CynicReqwestError;
-use cynic::QueryBuilder;
+use cynic::{GraphQlResponse, OperationBuilder, QueryBuilder};
 use eyre::Result;
 use futures::{Future, StreamExt};
-use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::header::HeaderMap;
-use tracing::info;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::Deserialize;
+use tracing::{info, warn};
 
 use crate::github::current_user::CurrentUser;
-use crate::spinner::make_spinner_style;
+use crate::spinner::new_spinner;
 use crate::{
     cache,
+    error::GhtoolError,
+    git::Repository,
     github::{
+        branch_checks::{self, BranchChecks, BranchChecksVariables},
+        default_branch_checks::{self, DefaultBranchChecks, DefaultBranchChecksVariables},
         pull_request_for_branch::{
             extract_pull_request, PullRequestForBranch, PullRequestForBranchVariables,
         },
         pull_request_status_checks::{
-            extract_check_runs, Node, PullRequestStatusChecks, PullRequestStatusChecksVariables,
+            extract_check_runs, extract_last_completed_check_runs, Node, PullRequest,
+            PullRequestStatusChecks, PullRequestStatusChecksVariables, IS_REQUIRED_FEATURE,
         },
     },
+    trace,
 };
 
-use super::{types::SimpleCheckRun, SimplePullRequest};
+use super::{
+    types::{Annotation, Artifact, ArtifactList, PullRequestChecks, Release, WorkflowJobs},
+    SimpleCheckRun, SimplePullRequest,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum GithubApiError {
@@ -45,18 +61,105 @@ pub enum GithubApiError {
     // No data in response
     #[error("No data in response")]
     NoDataInResponse,
+
+    /// The node queried by id doesn't exist (anymore)
+    #[error("No node in response")]
+    NodeNotFound,
+
+    /// The token is not authorized for an organization that enforces SAML SSO
+    #[error("Your token needs SSO authorization for {organization}: {url}")]
+    SsoRequired { organization: String, url: String },
+
+    /// The query doesn't match GitHub's GraphQL schema, e.g. after a field was changed
+    #[error("Query doesn't match GitHub's GraphQL schema: {0}")]
+    SchemaError(String),
+}
+
+/// Codes of GraphQL errors from validating a query against the schema
+const SCHEMA_ERROR_CODES: &[&str] = &[
+    "undefinedField",
+    "undefinedType",
+    "argumentNotAccepted",
+    "missingRequiredArguments",
+];
+
+#[derive(Debug, Deserialize)]
+struct GraphQlErrorExtensions {
+    code: Option<String>,
 }
 
 pub struct GithubClient {
     client: reqwest::Client,
+    base_uri: String,
+    graphql_uri: String,
+    /// Set once the status checks query has failed schema validation, so that it's not tried again
+    /// on every poll
+    status_checks_without_required: AtomicBool,
+    /// Logs of jobs downloaded by this process, so that each is downloaded once however many
+    /// times it's read, e.g. for a job of several commands in ght all or when retrying
+    job_logs: Mutex<HashMap<u64, bytes::Bytes>>,
+    /// Jobs whose logs were cut at the download limit
+    truncated_job_logs: Mutex<HashSet<u64>>,
 }
 
 const GITHUB_BASE_URI: &str = "https://api.github.com";
 
+/// How many of a pull request's most recent commits are looked at for completed check runs
+const LAST_COMPLETED_COMMITS: i32 = 10;
+
+/// Logs of a job can 404 for a moment after the job has completed, so getting them is retried
+const LOGS_NOT_FOUND_RETRIES: u32 = 3;
+const LOGS_NOT_FOUND_RETRY_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(10)
+} else {
+    Duration::from_secs(2)
+};
+
+/// REST and GraphQL API roots of a host; GitHub Enterprise Server has them under /api of the host
+fn api_uris(hostname: &str) -> (String, String) {
+    if hostname == "github.com" {
+        (
+            GITHUB_BASE_URI.to_string(),
+            format!("{GITHUB_BASE_URI}/graphql"),
+        )
+    } else {
+        enterprise_api_uris(&format!("https://{hostname}"))
+    }
+}
+
+fn enterprise_api_uris(origin: &str) -> (String, String) {
+    (format!("{origin}/api/v3"), format!("{origin}/api/graphql"))
+}
+
 impl GithubClient {
-    pub fn new(oauth_token: &str) -> Result<Self> {
+    /// Create a client for the API of the host, e.g. github.com or a GitHub Enterprise Server
+    pub fn new(oauth_token: &str, hostname: &str) -> Result<Self> {
+        let (base_uri, graphql_uri) = api_uris(hostname);
+        Self::with_api_uris(oauth_token, &base_uri, &graphql_uri)
+    }
+
+    /// Create a client against a different API root, e.g. a mock server in tests
+    pub fn with_base_uri(oauth_token: &str, base_uri: &str) -> Result<Self> {
+        let base_uri = base_uri.trim_end_matches('/');
+        Self::with_api_uris(oauth_token, base_uri, &format!("{base_uri}/graphql"))
+    }
+
+    fn with_api_uris(oauth_token: &str, base_uri: &str, graphql_uri: &str) -> Result<Self> {
         let client = Self::make_base_client(oauth_token)?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            base_uri: base_uri.trim_end_matches('/').to_string(),
+            graphql_uri: graphql_uri.to_string(),
+            status_checks_without_required: AtomicBool::new(false),
+            job_logs: Mutex::new(HashMap::new()),
+            truncated_job_logs: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Whether the checks read so far tell which of them are required; false if GitHub didn't
+    /// accept querying it
+    pub fn knows_required_checks(&self) -> bool {
+        !self.status_checks_without_required.load(Ordering::Relaxed)
     }
 
     fn make_headers(oauth_token: &str) -> HeaderMap {
@@ -73,7 +176,7 @@ impl GithubClient {
     }
 
     fn make_base_client(oauth_token: &str) -> Result<reqwest::Client> {
-        reqwest::Client::builder()
+        super::http_config::client_builder()?
             .user_agent("ghtool")
             .default_headers(Self::make_headers(oauth_token))
             .build()
@@ -88,10 +191,7 @@ impl GithubClient {
     where
         F: Future<Output = Result<T, GithubApiError>>,
     {
-        let pb = ProgressBar::new_spinner();
-        pb.enable_steady_tick(Duration::from_millis(100));
-        pb.set_style(make_spinner_style());
-        pb.set_message(message);
+        let pb = new_spinner(message);
         let result = future.await;
         pb.finish_and_clear();
 
@@ -106,20 +206,51 @@ impl GithubClient {
         T: serde::de::DeserializeOwned + 'static,
         K: serde::Serialize,
     {
-        use cynic::http::ReqwestExt;
-        let graphql_endpoint = format!("{}/graphql", GITHUB_BASE_URI);
+        let response = self
+            .send(self.client.post(&self.graphql_uri).json(&operation))
+            .await?;
+        let response: GraphQlResponse<T, GraphQlErrorExtensions> = response.json().await?;
+        if let Some(data) = response.data {
+            return Ok(data);
+        }
 
-        self.client
-            .post(graphql_endpoint)
-            .run_graphql(operation)
-            .await
-            .map_err(|e| match e {
-                CynicReqwestError::ReqwestError(err) => GithubApiError::ReqwestError(err),
-                CynicReqwestError::ErrorResponse(status, body) => {
-                    GithubApiError::ErrorResponse(status, body)
-                }
+        let schema_errors: Vec<_> = response
+            .errors
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|error| {
+                error
+                    .extensions
+                    .as_ref()
+                    .and_then(|extensions| extensions.code.as_deref())
+                    .is_some_and(|code| SCHEMA_ERROR_CODES.contains(&code))
             })
-            .and_then(|response| response.data.ok_or(GithubApiError::NoDataInResponse))
+            .map(|error| error.message)
+            .collect();
+        if schema_errors.is_empty() {
+            Err(GithubApiError::NoDataInResponse)
+        } else {
+            Err(GithubApiError::SchemaError(schema_errors.join("; ")))
+        }
+    }
+
+    /// Send a request, turning error statuses into errors
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, GithubApiError> {
+        let response = trace::send(request).await?;
+        if let Some(error) = sso_required_error(response.headers()) {
+            return Err(error);
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await?;
+            return Err(GithubApiError::ErrorResponse(status, body));
+        }
+
+        Ok(response)
     }
 
     pub async fn get_pr_for_branch(
@@ -127,6 +258,7 @@ impl GithubClient {
         owner: &str,
         repo: &str,
         branch: &str,
+        with_spinner: bool,
     ) -> Result<Option<SimplePullRequest>> {
         info!(?owner, ?repo, ?branch, "Getting pr for branch");
         let query = PullRequestForBranch::build(PullRequestForBranchVariables {
@@ -136,12 +268,15 @@ impl GithubClient {
             states: None,
         });
 
-        let pr_for_branch = self
-            .run_with_spinner(
+        let pr_for_branch = if with_spinner {
+            self.run_with_spinner(
                 "Fetching pull request...".into(),
                 self.run_graphql_query(query),
             )
-            .await?;
+            .await?
+        } else {
+            self.run_graphql_query(query).await?
+        };
 
         info!(?pr_for_branch, "Got pr");
         let pr = extract_pull_request(pr_for_branch);
@@ -150,36 +285,176 @@ impl GithubClient {
 
     pub async fn get_pr_for_branch_memoized(
         &self,
-        owner: &str,
-        repo: &str,
+        repo: &Repository,
         branch: &str,
     ) -> Result<Option<SimplePullRequest>> {
-        let key = format!("pr_for_branch_{}_{}", repo, branch);
-        cache::memoize(key, || self.get_pr_for_branch(owner, repo, branch)).await
+        let key = pr_for_branch_cache_key(repo, branch);
+        let cached: Option<Option<SimplePullRequest>> = cache::get(&key)?;
+        // Pull requests cached before their base was stored are looked up again to know it
+        if let Some(pr) =
+            cached.filter(|pr| pr.as_ref().is_none_or(|pr| pr.base_ref_name.is_some()))
+        {
+            return Ok(pr);
+        }
+
+        let pr = self
+            .get_pr_for_branch(&repo.owner, &repo.name, branch, true)
+            .await?;
+        // A draft is looked up again the next time, so that it's seen when marked ready for review
+        if !pr.as_ref().is_some_and(|pr| pr.is_draft) {
+            cache::put(&key, &pr)?;
+        }
+        Ok(pr)
+    }
+
+    /// Remove the cached pull request of a branch, so that it's looked up again
+    pub fn forget_pr_for_branch(&self, repo: &Repository, branch: &str) -> Result<()> {
+        cache::remove(pr_for_branch_cache_key(repo, branch))
     }
 
     pub async fn get_pr_status_checks(
         &self,
         id: &cynic::Id,
         with_spinner: bool,
-    ) -> Result<Vec<SimpleCheckRun>> {
+    ) -> Result<PullRequestChecks> {
         info!(?id, "Getting checks for pr");
-        let query = PullRequestStatusChecks::build(PullRequestStatusChecksVariables { id });
+        let pull_request = self.query_pr_status_checks(id, 1, with_spinner).await?;
+        extract_check_runs(pull_request)
+    }
 
-        let pr_checks = if with_spinner {
-            self.run_with_spinner("Fetching checks...".into(), self.run_graphql_query(query))
+    /// Get the most recent completed check run of each job, looking back at the pull request's
+    /// last few commits
+    pub async fn get_pr_last_completed_checks(
+        &self,
+        id: &cynic::Id,
+        match_check_run: Option<&dyn Fn(&SimpleCheckRun) -> bool>,
+    ) -> Result<PullRequestChecks> {
+        info!(?id, "Getting last completed checks for pr");
+        let pull_request = self
+            .query_pr_status_checks(id, LAST_COMPLETED_COMMITS, true)
+            .await?;
+        extract_last_completed_check_runs(pull_request, match_check_run)
+    }
+
+    /// Get check runs of the latest commit of the repository's default branch
+    pub async fn get_default_branch_checks(
+        &self,
+        owner: &str,
+        repo: &str,
+    ) -> Result<PullRequestChecks> {
+        info!(?owner, ?repo, "Getting checks for default branch");
+        let query = DefaultBranchChecks::build(DefaultBranchChecksVariables { owner, repo });
+        let default_branch_checks = self
+            .run_with_spinner("Fetching checks...".into(), self.run_graphql_query(query))
+            .await?;
+        default_branch_checks::extract_check_runs(default_branch_checks)
+    }
+
+    /// Get check runs of the latest commit of a branch
+    pub async fn get_branch_checks(
+        &self,
+        owner: &str,
+        repo: &str,
+        branch: &str,
+    ) -> Result<PullRequestChecks> {
+        info!(?owner, ?repo, ?branch, "Getting checks for branch");
+        let qualified_name = format!("refs/heads/{}", branch);
+        let query = BranchChecks::build(BranchChecksVariables {
+            owner,
+            qualified_name: &qualified_name,
+            repo,
+        });
+        let branch_checks = self
+            .run_with_spinner("Fetching checks...".into(), self.run_graphql_query(query))
+            .await?;
+        branch_checks::extract_check_runs(branch_checks, branch)
+    }
+
+    /// Get the jobs of a workflow run as check runs, without going through its pull request
+    pub async fn get_workflow_run_checks(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: u64,
+    ) -> Result<PullRequestChecks> {
+        info!(?owner, ?repo, ?run_id, "Getting jobs of workflow run");
+        let mut url = Some(format!(
+            "{}/repos/{owner}/{repo}/actions/runs/{run_id}/jobs?per_page=100",
+            self.base_uri
+        ));
+        let mut jobs = Vec::new();
+        // Runs of large matrices have more jobs than fit on a page
+        while let Some(page_url) = url {
+            let response = self
+                .run_with_spinner(
+                    "Fetching checks...".into(),
+                    self.send(self.client.get(page_url)),
+                )
+                .await?;
+            url = next_page_url(response.headers());
+            let workflow_jobs: WorkflowJobs = response.json().await?;
+            jobs.extend(workflow_jobs.jobs);
+        }
+        let head_sha = jobs
+            .first()
+            .map(|job| job.head_sha.clone())
+            .ok_or(GhtoolError::NoWorkflowRunJobs { run_id })?;
+        Ok(PullRequestChecks {
+            head_sha,
+            check_runs: jobs.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    async fn query_pr_status_checks(
+        &self,
+        id: &cynic::Id,
+        commits: i32,
+        with_spinner: bool,
+    ) -> Result<PullRequest> {
+        let variables = || PullRequestStatusChecksVariables { id, commits };
+        let pr_checks = if self.status_checks_without_required.load(Ordering::Relaxed) {
+            self.run_status_checks_query(PullRequestStatusChecks::build(variables()), with_spinner)
                 .await?
         } else {
-            self.run_graphql_query(query).await?
+            let query = OperationBuilder::query()
+                .with_variables(variables())
+                .with_feature_enabled(IS_REQUIRED_FEATURE)
+                .build()?;
+            match self.run_status_checks_query(query, with_spinner).await {
+                Err(GithubApiError::SchemaError(message)) => {
+                    warn!(
+                        message,
+                        "status checks query failed, retrying without isRequired"
+                    );
+                    self.status_checks_without_required
+                        .store(true, Ordering::Relaxed);
+                    self.run_status_checks_query(
+                        PullRequestStatusChecks::build(variables()),
+                        with_spinner,
+                    )
+                    .await?
+                }
+                result => result?,
+            }
         };
 
         match pr_checks.node {
-            Some(Node::PullRequest(pull_request)) => {
-                let check_runs = extract_check_runs(pull_request)?;
-                Ok(check_runs.into_iter().map(SimpleCheckRun::from).collect()) // convert check runs
-            }
+            Some(Node::PullRequest(pull_request)) => Ok(pull_request),
             Some(Node::Unknown) => eyre::bail!("Unknown node type"),
-            None => eyre::bail!("No node in response"),
+            None => Err(GithubApiError::NodeNotFound.into()),
+        }
+    }
+
+    async fn run_status_checks_query(
+        &self,
+        query: cynic::Operation<PullRequestStatusChecks, PullRequestStatusChecksVariables<'_>>,
+        with_spinner: bool,
+    ) -> Result<PullRequestStatusChecks, GithubApiError> {
+        if with_spinner {
+            self.run_with_spinner("Fetching checks...".into(), self.run_graphql_query(query))
+                .await
+        } else {
+            self.run_graphql_query(query).await
         }
     }
 
@@ -188,16 +463,38 @@ impl GithubClient {
         owner: &str,
         repo: &str,
         job_id: u64,
+        max_bytes: u64,
         progress_bar: &ProgressBar,
     ) -> Result<bytes::Bytes> {
+        if let Some(log) = self.job_logs.lock().unwrap().get(&job_id) {
+            info!(?job_id, "Job logs already downloaded");
+            progress_bar.finish_and_clear();
+            return Ok(log.clone());
+        }
         info!(?owner, ?repo, ?job_id, "Getting job logs");
 
         let mut got_first_chunk = false;
-        let url = format!("{GITHUB_BASE_URI}/repos/{owner}/{repo}/actions/jobs/{job_id}/logs",);
-        let response = self.client.get(url).send().await?.error_for_status()?;
+        let url = format!(
+            "{}/repos/{owner}/{repo}/actions/jobs/{job_id}/logs",
+            self.base_uri
+        );
+        let mut retries = 0;
+        let response = loop {
+            match self.send(self.client.get(&url)).await {
+                Err(GithubApiError::ErrorResponse(StatusCode::NOT_FOUND, _))
+                    if retries < LOGS_NOT_FOUND_RETRIES =>
+                {
+                    retries += 1;
+                    info!(?job_id, retries, "logs not found, retrying");
+                    tokio::time::sleep(LOGS_NOT_FOUND_RETRY_DELAY).await;
+                }
+                result => break result?,
+            }
+        };
         let content_length = response.content_length().unwrap_or(0);
         progress_bar.set_length(content_length);
-        let mut result = bytes::BytesMut::with_capacity(content_length as usize);
+        let max_len = usize::try_from(max_bytes).unwrap_or(usize::MAX);
+        let mut result = bytes::BytesMut::with_capacity(content_length.min(max_bytes) as usize);
         let mut stream = response.bytes_stream();
         while let Some(chunk) = stream.next().await {
             // Start showing bytes in the progress bar only after first chunk is received
@@ -212,10 +509,108 @@ impl GithubClient {
             got_first_chunk = true;
             let chunk = chunk?;
             progress_bar.inc(chunk.len() as u64);
+
+            // A job stuck in a loop can produce gigabytes of logs, so the download is stopped
+            // once over the limit. What was read is cut at the last full line.
+            let remaining = max_len - result.len();
+            if chunk.len() > remaining {
+                result.extend_from_slice(&chunk[..remaining]);
+                if let Some(last_newline) = result.iter().rposition(|&b| b == b'\n') {
+                    result.truncate(last_newline + 1);
+                }
+                self.truncated_job_logs.lock().unwrap().insert(job_id);
+                progress_bar.suspend(|| {
+                    eprintln!(
+                        "Warning: stopped downloading logs of job {} at {} (see --max-download)",
+                        job_id,
+                        HumanBytes(max_bytes)
+                    )
+                });
+                break;
+            }
             result.extend_from_slice(&chunk);
         }
         progress_bar.finish_and_clear();
-        Ok(result.freeze())
+        let log = result.freeze();
+        self.job_logs.lock().unwrap().insert(job_id, log.clone());
+        Ok(log)
+    }
+
+    /// Whether the logs of the job downloaded by this process were cut at the download limit
+    pub fn is_job_log_truncated(&self, job_id: u64) -> bool {
+        self.truncated_job_logs.lock().unwrap().contains(&job_id)
+    }
+
+    pub async fn list_artifacts(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: u64,
+    ) -> Result<Vec<Artifact>> {
+        info!(?owner, ?repo, ?run_id, "Listing artifacts");
+        let url = format!(
+            "{}/repos/{owner}/{repo}/actions/runs/{run_id}/artifacts?per_page=100",
+            self.base_uri
+        );
+        let response = self.send(self.client.get(url)).await?;
+        let artifact_list: ArtifactList = response.json().await?;
+        Ok(artifact_list.artifacts)
+    }
+
+    /// Download an artifact as a zip archive
+    pub async fn download_artifact(
+        &self,
+        owner: &str,
+        repo: &str,
+        artifact_id: u64,
+    ) -> Result<bytes::Bytes> {
+        info!(?owner, ?repo, ?artifact_id, "Downloading artifact");
+        let url = format!(
+            "{}/repos/{owner}/{repo}/actions/artifacts/{artifact_id}/zip",
+            self.base_uri
+        );
+        let response = self.send(self.client.get(url)).await?;
+        Ok(response.bytes().await?)
+    }
+
+    pub async fn get_check_run_annotations(
+        &self,
+        owner: &str,
+        repo: &str,
+        check_run_id: u64,
+    ) -> Result<Vec<Annotation>> {
+        info!(
+            ?owner,
+            ?repo,
+            ?check_run_id,
+            "Getting check run annotations"
+        );
+        let url = format!(
+            "{}/repos/{owner}/{repo}/check-runs/{check_run_id}/annotations",
+            self.base_uri
+        );
+        let mut annotations = Vec::new();
+        for page in 1.. {
+            let request = self
+                .client
+                .get(&url)
+                .query(&[("per_page", ANNOTATIONS_PER_PAGE), ("page", page)]);
+            let response = self.send(request).await?;
+            let page_annotations: Vec<Annotation> = response.json().await?;
+            let is_last_page = page_annotations.len() < ANNOTATIONS_PER_PAGE;
+            annotations.extend(page_annotations);
+            if is_last_page {
+                break;
+            }
+        }
+        Ok(annotations)
+    }
+
+    pub async fn get_latest_release(&self, owner: &str, repo: &str) -> Result<Release> {
+        info!(?owner, ?repo, "Getting latest release");
+        let url = format!("{}/repos/{owner}/{repo}/releases/latest", self.base_uri);
+        let response = self.send(self.client.get(url)).await?;
+        Ok(response.json().await?)
     }
 
     pub async fn get_current_user(&self) -> Result<CurrentUser, GithubApiError> {
@@ -232,3 +627,704 @@ impl GithubClient {
         Ok(current_user)
     }
 }
+
+/// Annotations of a check run fetched per request, the most GitHub allows
+const ANNOTATIONS_PER_PAGE: usize = 100;
+
+/// Cached values are shared by all repositories, so keys have the host and owner of the
+/// repository as well as its name
+fn pr_for_branch_cache_key(repo: &Repository, branch: &str) -> String {
+    format!(
+        "pr_for_branch_{}/{}/{}_{}",
+        repo.hostname, repo.owner, repo.name, branch
+    )
+}
+
+/// GitHub responds to requests for resources of an organization enforcing SAML SSO, with a token
+/// not authorized for it, with e.g.
+/// X-GitHub-SSO: required; url=https://github.com/orgs/<org>/sso?authorization_request=<id>
+/// URL of the next page of a paginated REST response, from its Link header
+fn next_page_url(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|link| {
+        let (url, params) = link.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#)
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+fn sso_required_error(headers: &HeaderMap) -> Option<GithubApiError> {
+    let value = headers.get("x-github-sso")?.to_str().ok()?;
+    let url = value
+        .strip_prefix("required;")?
+        .trim()
+        .strip_prefix("url=")?
+        .to_string();
+    let organization = url
+        .split_once("/orgs/")
+        .and_then(|(_, rest)| rest.split('/').next())
+        .unwrap_or("the organization")
+        .to_string();
+    Some(GithubApiError::SsoRequired { organization, url })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::matchers::{
+        body_partial_json, body_string_contains, header, method, path, query_param,
+    };
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::github::{CheckConclusionState, CheckStatusState};
+
+    #[test]
+    fn test_pr_for_branch_cache_key() {
+        let repo = |hostname: &str, owner: &str| Repository {
+            owner: owner.to_string(),
+            name: "app".to_string(),
+            hostname: hostname.to_string(),
+        };
+        let key = |repo| pr_for_branch_cache_key(&repo, "feature");
+
+        assert_eq!(
+            key(repo("github.com", "org")),
+            "pr_for_branch_github.com/org/app_feature"
+        );
+        assert_ne!(
+            key(repo("github.com", "org")),
+            key(repo("github.com", "other-org"))
+        );
+        assert_ne!(
+            key(repo("github.com", "org")),
+            key(repo("github.example.com", "org"))
+        );
+    }
+
+    fn status_checks_response() -> serde_json::Value {
+        serde_json::json!({
+            "data": {
+                "node": {
+                    "__typename": "PullRequest",
+                    "commits": {
+                        "nodes": [{
+                            "commit": {
+                                "oid": "0123456789abcdef0123456789abcdef01234567",
+                                "statusCheckRollup": {
+                                    "id": "SCR_1",
+                                    "contexts": {
+                                        "nodes": [{
+                                            "__typename": "CheckRun",
+                                            "id": "CR_1",
+                                            "url": "https://github.com/org/repo/runs/1",
+                                            "externalId": null,
+                                            "name": "Unit tests",
+                                            "status": "COMPLETED",
+                                            "conclusion": "FAILURE",
+                                            "startedAt": "2023-09-01T10:00:00Z",
+                                            "completedAt": "2023-09-01T10:05:00Z",
+                                            "detailsUrl": "https://github.com/org/repo/actions/runs/1/job/1",
+                                            "isRequired": true,
+                                            "databaseId": 1,
+                                            "checkSuite": { "app": { "slug": "github-actions" } }
+                                        }],
+                                        "pageInfo": { "hasNextPage": false, "endCursor": null }
+                                    }
+                                }
+                            }
+                        }]
+                    }
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_get_pr_status_checks() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .and(header("authorization", "token secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(status_checks_response()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let pr_checks = client
+            .get_pr_status_checks(&cynic::Id::new("PR_1"), false)
+            .await
+            .unwrap();
+        let check_runs = pr_checks.check_runs;
+
+        assert_eq!(check_runs.len(), 1);
+        assert_eq!(check_runs[0].id, 1);
+        assert_eq!(check_runs[0].name, "Unit tests");
+        assert_eq!(
+            check_runs[0].conclusion,
+            Some(CheckConclusionState::Failure)
+        );
+    }
+
+    #[test]
+    fn test_api_uris() {
+        assert_eq!(
+            api_uris("github.com"),
+            (
+                "https://api.github.com".to_string(),
+                "https://api.github.com/graphql".to_string()
+            )
+        );
+        assert_eq!(
+            api_uris("github.example.com"),
+            (
+                "https://github.example.com/api/v3".to_string(),
+                "https://github.example.com/api/graphql".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enterprise_host_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(status_checks_response()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/org/repo/releases/latest"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "tag_name": "v1.0.0" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let (base_uri, graphql_uri) = enterprise_api_uris(&server.uri());
+        let client = GithubClient::with_api_uris("secret", &base_uri, &graphql_uri).unwrap();
+        let pr_checks = client
+            .get_pr_status_checks(&cynic::Id::new("PR_1"), false)
+            .await
+            .unwrap();
+        assert_eq!(pr_checks.check_runs.len(), 1);
+        let release = client.get_latest_release("org", "repo").await.unwrap();
+        assert_eq!(release.tag_name, "v1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_get_pr_status_checks_without_rollup() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {
+                    "node": {
+                        "__typename": "PullRequest",
+                        "commits": {
+                            "nodes": [{
+                                "commit": {
+                                    "oid": "0123456789abcdef0123456789abcdef01234567",
+                                    "statusCheckRollup": null
+                                }
+                            }]
+                        }
+                    }
+                }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let pr_checks = client
+            .get_pr_status_checks(&cynic::Id::new("PR_1"), false)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            pr_checks.head_sha,
+            "0123456789abcdef0123456789abcdef01234567"
+        );
+        assert!(pr_checks.check_runs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_pr_status_checks_without_is_required() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .and(body_string_contains("isRequired"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "errors": [{
+                    "message": "Field 'isRequired' doesn't accept argument 'pullRequestId'",
+                    "extensions": { "code": "argumentNotAccepted" }
+                }]
+            })))
+            .with_priority(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        // The query without isRequired gets a response without it
+        let mut response = status_checks_response();
+        response
+            .pointer_mut("/data/node/commits/nodes/0/commit/statusCheckRollup/contexts/nodes/0")
+            .and_then(|check_run| check_run.as_object_mut())
+            .unwrap()
+            .remove("isRequired");
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(response))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        for _ in 0..2 {
+            let pr_checks = client
+                .get_pr_status_checks(&cynic::Id::new("PR_1"), false)
+                .await
+                .unwrap();
+            assert_eq!(pr_checks.check_runs.len(), 1);
+            assert!(pr_checks.check_runs.iter().all(|run| !run.is_required));
+            assert!(!client.knows_required_checks());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_pr_for_branch_draft() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {
+                    "repository": {
+                        "pullRequests": {
+                            "nodes": [{
+                                "number": 1,
+                                "headRefName": "feature",
+                                "id": "PR_1",
+                                "state": "OPEN",
+                                "baseRefName": "main",
+                                "isCrossRepository": false,
+                                "isDraft": true,
+                                "headRepositoryOwner": null
+                            }]
+                        },
+                        "defaultBranchRef": { "name": "main" }
+                    }
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let pull_request = client
+            .get_pr_for_branch("org", "repo", "feature", false)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(pull_request.id, cynic::Id::new("PR_1"));
+        assert!(pull_request.is_draft);
+    }
+
+    #[tokio::test]
+    async fn test_get_pr_for_branch_with_special_characters() {
+        let server = MockServer::start().await;
+        for branch in ["feature/foo#bar", "raine/fix-ä-ö", "feature/100%"] {
+            Mock::given(method("POST"))
+                .and(path("/graphql"))
+                .and(body_partial_json(serde_json::json!({
+                    "variables": { "headRefName": branch }
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "data": {
+                        "repository": {
+                            "pullRequests": {
+                                "nodes": [{
+                                    "number": 1,
+                                    "headRefName": branch,
+                                    "id": "PR_1",
+                                    "state": "OPEN",
+                                    "baseRefName": "main",
+                                    "isCrossRepository": false,
+                                    "isDraft": false,
+                                    "headRepositoryOwner": null
+                                }]
+                            },
+                            "defaultBranchRef": { "name": "main" }
+                        }
+                    }
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        for branch in ["feature/foo#bar", "raine/fix-ä-ö", "feature/100%"] {
+            let pull_request = client
+                .get_pr_for_branch("org", "repo", branch, false)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(pull_request.id, cynic::Id::new("PR_1"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_workflow_run_checks() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/runs/42/jobs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 2,
+                "jobs": [
+                    {
+                        "id": 1,
+                        "name": "Test",
+                        "head_sha": "abc123",
+                        "status": "completed",
+                        "conclusion": "failure",
+                        "html_url": "https://github.com/org/repo/actions/runs/42/job/1",
+                        "started_at": "2024-03-01T10:00:00Z",
+                        "completed_at": "2024-03-01T10:05:00Z"
+                    },
+                    {
+                        "id": 2,
+                        "name": "Lint",
+                        "head_sha": "abc123",
+                        "status": "in_progress",
+                        "conclusion": null,
+                        "html_url": "https://github.com/org/repo/actions/runs/42/job/2",
+                        "started_at": "2024-03-01T10:00:00Z",
+                        "completed_at": null
+                    }
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let pr_checks = client
+            .get_workflow_run_checks("org", "repo", 42)
+            .await
+            .unwrap();
+
+        assert_eq!(pr_checks.head_sha, "abc123");
+        let test = &pr_checks.check_runs[0];
+        assert_eq!(test.name, "Test");
+        assert!(matches!(
+            test.conclusion,
+            Some(CheckConclusionState::Failure)
+        ));
+        assert_eq!(test.workflow_run_id(), Some(42));
+        assert_eq!(test.app.as_deref(), Some("github-actions"));
+        let lint = &pr_checks.check_runs[1];
+        assert!(matches!(lint.status, CheckStatusState::InProgress));
+        assert!(lint.conclusion.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_workflow_run_checks_paginates() {
+        let job = |id: u64| {
+            serde_json::json!({
+                "id": id,
+                "name": format!("Test ({id})"),
+                "head_sha": "abc123",
+                "status": "completed",
+                "conclusion": "failure",
+                "html_url": format!("https://github.com/org/repo/actions/runs/42/job/{id}"),
+                "started_at": "2024-03-01T10:00:00Z",
+                "completed_at": "2024-03-01T10:05:00Z"
+            })
+        };
+        let server = MockServer::start().await;
+        let next_page = format!(
+            "{}/repos/org/repo/actions/runs/42/jobs?per_page=100&page=2",
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/runs/42/jobs"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 2,
+                "jobs": [job(2)]
+            })))
+            .with_priority(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/runs/42/jobs"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "link",
+                        format!(r#"<{next_page}>; rel="next", <{next_page}>; rel="last""#),
+                    )
+                    .set_body_json(serde_json::json!({
+                        "total_count": 2,
+                        "jobs": [job(1)]
+                    })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let pr_checks = client
+            .get_workflow_run_checks("org", "repo", 42)
+            .await
+            .unwrap();
+
+        let ids: Vec<_> = pr_checks.check_runs.iter().map(|run| run.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_get_workflow_run_checks_without_jobs() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/runs/42/jobs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 0,
+                "jobs": []
+            })))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let err = client
+            .get_workflow_run_checks("org", "repo", 42)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<GhtoolError>(),
+            Some(&GhtoolError::NoWorkflowRunJobs { run_id: 42 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_pr_status_checks_node_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "node": null },
+                "errors": [{ "message": "Could not resolve to a node with the global id of 'PR_1'" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let err = client
+            .get_pr_status_checks(&cynic::Id::new("PR_1"), false)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<GithubApiError>(),
+            Some(GithubApiError::NodeNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_download_artifacts() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/runs/123/artifacts"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_count": 2,
+                "artifacts": [
+                    { "id": 1, "name": "coverage", "size_in_bytes": 10 },
+                    { "id": 2, "name": "junit", "size_in_bytes": 10 }
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/artifacts/2/zip"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"PK".to_vec()))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let artifacts = client.list_artifacts("org", "repo", 123).await.unwrap();
+        let names: Vec<_> = artifacts.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["coverage", "junit"]);
+
+        let bytes = client.download_artifact("org", "repo", 2).await.unwrap();
+        assert_eq!(bytes.as_ref(), b"PK");
+    }
+
+    #[tokio::test]
+    async fn test_sso_required() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/runs/123/artifacts"))
+            .respond_with(ResponseTemplate::new(403).insert_header(
+                "X-GitHub-SSO",
+                "required; url=https://github.com/orgs/org/sso?authorization_request=abc",
+            ))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let err = client.list_artifacts("org", "repo", 123).await.unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Your token needs SSO authorization for org: https://github.com/orgs/org/sso?authorization_request=abc"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_job_logs() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/jobs/1/logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("log line 1\nlog line 2\n"))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let logs = client
+            .get_job_logs("org", "repo", 1, u64::MAX, &ProgressBar::hidden())
+            .await
+            .unwrap();
+
+        assert_eq!(logs.as_ref(), b"log line 1\nlog line 2\n");
+    }
+
+    #[tokio::test]
+    async fn test_get_job_logs_downloads_once() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/jobs/1/logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("log line 1\n"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        for _ in 0..2 {
+            let logs = client
+                .get_job_logs("org", "repo", 1, u64::MAX, &ProgressBar::hidden())
+                .await
+                .unwrap();
+            assert_eq!(logs.as_ref(), b"log line 1\n");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_job_logs_stops_at_max_bytes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/jobs/1/logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("log line 1\nlog line 2\n"))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let logs = client
+            .get_job_logs("org", "repo", 1, 15, &ProgressBar::hidden())
+            .await
+            .unwrap();
+
+        assert_eq!(logs.as_ref(), b"log line 1\n");
+        assert!(client.is_job_log_truncated(1));
+    }
+
+    #[tokio::test]
+    async fn test_get_job_logs_retries_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/jobs/1/logs"))
+            .respond_with(ResponseTemplate::new(404))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/jobs/1/logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("log line 1\n"))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let logs = client
+            .get_job_logs("org", "repo", 1, u64::MAX, &ProgressBar::hidden())
+            .await
+            .unwrap();
+
+        assert_eq!(logs.as_ref(), b"log line 1\n");
+    }
+
+    #[tokio::test]
+    async fn test_get_job_logs_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/actions/jobs/1/logs"))
+            .respond_with(ResponseTemplate::new(410))
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let result = client
+            .get_job_logs("org", "repo", 1, u64::MAX, &ProgressBar::hidden())
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_check_run_annotations_paginates() {
+        let annotation = serde_json::json!({
+            "path": "src/a.ts",
+            "start_line": 1,
+            "start_column": null,
+            "annotation_level": "failure",
+            "title": null,
+            "message": "error"
+        });
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/check-runs/1/annotations"))
+            .and(query_param("page", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(vec![annotation.clone(); ANNOTATIONS_PER_PAGE]),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/repo/check-runs/1/annotations"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![annotation]))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GithubClient::with_base_uri("secret", &server.uri()).unwrap();
+        let annotations = client
+            .get_check_run_annotations("org", "repo", 1)
+            .await
+            .unwrap();
+
+        assert_eq!(annotations.len(), ANNOTATIONS_PER_PAGE + 1);
+    }
+}
@@ -0,0 +1,180 @@
+use eyre::Result;
+use tracing::warn;
+
+use super::{PullRequestChecks, SimpleCheckRun};
+
+pub fn extract_check_runs(default_branch_checks: DefaultBranchChecks) -> Result<PullRequestChecks> {
+    let default_branch_ref = default_branch_checks
+        .repository
+        .ok_or_else(|| eyre::eyre!("Repository not found"))?
+        .default_branch_ref
+        .ok_or_else(|| eyre::eyre!("Repository has no default branch"))?;
+    extract_ref_check_runs(default_branch_ref)
+}
+
+/// Check runs of the commit a branch points to
+pub(super) fn extract_ref_check_runs(branch_ref: Ref) -> Result<PullRequestChecks> {
+    let GitObject::Commit(commit) = branch_ref
+        .target
+        .ok_or_else(|| eyre::eyre!("Branch {} has no commit", branch_ref.name))?
+    else {
+        eyre::bail!("Branch {} doesn't point to a commit", branch_ref.name);
+    };
+
+    // A commit without checks has no rollup
+    let check_runs = commit
+        .status_check_rollup
+        .and_then(|status_check_rollup| status_check_rollup.contexts.nodes)
+        .unwrap_or_default()
+        .into_iter()
+        .flatten()
+        .filter_map(|x| match x {
+            StatusCheckRollupContext::CheckRun(check_run) => Some(check_run),
+            StatusCheckRollupContext::Unknown => None,
+        })
+        .filter(|check_run| {
+            if check_run.database_id.is_none() {
+                warn!(name = ?check_run.name, "skipping check run without database id");
+            }
+            check_run.database_id.is_some()
+        })
+        .map(SimpleCheckRun::from)
+        .collect();
+
+    Ok(PullRequestChecks {
+        head_sha: commit.oid.0,
+        check_runs,
+    })
+}
+
+use cynic_github_schema as schema;
+
+use super::pull_request_status_checks::{
+    BigInt, CheckConclusionState, CheckStatusState, CheckSuite, DateTime, GitObjectId, Uri,
+};
+
+// Below is generated with https://generator.cynic-rs.dev using ./default_branch_checks.graphql,
+// except database_id is changed from Option<i32> to Option<BigInt> manually.
+#[derive(cynic::QueryVariables, Debug)]
+pub struct DefaultBranchChecksVariables<'a> {
+    pub owner: &'a str,
+    pub repo: &'a str,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+#[cynic(graphql_type = "Query", variables = "DefaultBranchChecksVariables")]
+pub struct DefaultBranchChecks {
+    #[arguments(owner: $owner, name: $repo)]
+    pub repository: Option<Repository>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Repository {
+    pub default_branch_ref: Option<Ref>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Ref {
+    pub name: String,
+    pub target: Option<GitObject>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct Commit {
+    pub oid: GitObjectId,
+    pub status_check_rollup: Option<StatusCheckRollup>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct StatusCheckRollup {
+    #[arguments(first: 100)]
+    pub contexts: StatusCheckRollupContextConnection,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct StatusCheckRollupContextConnection {
+    pub nodes: Option<Vec<Option<StatusCheckRollupContext>>>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct CheckRun {
+    pub id: cynic::Id,
+    pub url: Uri,
+    pub external_id: Option<String>,
+    pub name: String,
+    pub status: CheckStatusState,
+    pub conclusion: Option<CheckConclusionState>,
+    pub started_at: Option<DateTime>,
+    pub completed_at: Option<DateTime>,
+    pub details_url: Option<Uri>,
+    pub database_id: Option<BigInt>,
+    pub check_suite: Option<CheckSuite>,
+    pub __typename: String,
+}
+
+#[derive(cynic::InlineFragments, Debug)]
+pub enum GitObject {
+    Commit(Commit),
+    #[cynic(fallback)]
+    Unknown,
+}
+
+#[derive(cynic::InlineFragments, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum StatusCheckRollupContext {
+    CheckRun(CheckRun),
+    #[cynic(fallback)]
+    Unknown,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_extract_check_runs() {
+        let json = serde_json::json!({
+            "repository": {
+                "defaultBranchRef": {
+                    "name": "main",
+                    "target": {
+                        "__typename": "Commit",
+                        "oid": "0123456789abcdef0123456789abcdef01234567",
+                        "statusCheckRollup": {
+                            "contexts": {
+                                "nodes": [{
+                                    "__typename": "CheckRun",
+                                    "id": "CR_1",
+                                    "url": "https://github.com/org/repo/runs/1",
+                                    "externalId": null,
+                                    "name": "Unit tests",
+                                    "status": "COMPLETED",
+                                    "conclusion": "FAILURE",
+                                    "startedAt": "2023-09-01T10:00:00Z",
+                                    "completedAt": "2023-09-01T10:05:00Z",
+                                    "detailsUrl": "https://github.com/org/repo/actions/runs/2/job/1",
+                                    "databaseId": 1,
+                                    "checkSuite": { "app": { "slug": "github-actions" } },
+                                }, {
+                                    "__typename": "StatusContext",
+                                }]
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let default_branch_checks: DefaultBranchChecks = serde_json::from_value(json).unwrap();
+        let checks = extract_check_runs(default_branch_checks).unwrap();
+        assert_eq!(checks.head_sha, "0123456789abcdef0123456789abcdef01234567");
+        let names: Vec<_> = checks
+            .check_runs
+            .iter()
+            .map(|cr| cr.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Unit tests"]);
+        assert_eq!(checks.check_runs[0].workflow_run_id(), Some(2));
+    }
+}
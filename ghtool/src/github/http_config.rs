@@ -0,0 +1,71 @@
+use std::{fs, path::PathBuf, sync::OnceLock};
+
+use eyre::{Context, Result};
+
+/// Proxy and TLS settings shared by all HTTP clients. Proxies from the HTTPS_PROXY, HTTP_PROXY
+/// and NO_PROXY env variables are used by reqwest when no proxy is given here.
+#[derive(Debug, Default, Clone)]
+pub struct HttpConfig {
+    pub proxy: Option<String>,
+    /// PEM file with certificates to trust in addition to system ones, e.g. of a proxy
+    pub ca_file: Option<PathBuf>,
+}
+
+static HTTP_CONFIG: OnceLock<HttpConfig> = OnceLock::new();
+
+pub fn set_http_config(config: HttpConfig) {
+    let _ = HTTP_CONFIG.set(config);
+}
+
+pub fn client_builder() -> Result<reqwest::ClientBuilder> {
+    let config = HTTP_CONFIG.get().cloned().unwrap_or_default();
+    apply_http_config(reqwest::Client::builder(), &config)
+}
+
+fn apply_http_config(
+    mut builder: reqwest::ClientBuilder,
+    config: &HttpConfig,
+) -> Result<reqwest::ClientBuilder> {
+    if let Some(proxy) = &config.proxy {
+        let proxy = reqwest::Proxy::all(proxy)
+            .wrap_err_with(|| format!("Invalid proxy {}", proxy))?
+            .no_proxy(reqwest::NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(ca_file) = &config.ca_file {
+        let pem = fs::read(ca_file)
+            .wrap_err_with(|| format!("Error reading CA file {}", ca_file.display()))?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .wrap_err_with(|| format!("Invalid certificates in {}", ca_file.display()))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_http_config() {
+        let config = HttpConfig {
+            proxy: Some("http://proxy.example.com:8080".to_string()),
+            ca_file: None,
+        };
+        assert!(apply_http_config(reqwest::Client::builder(), &config)
+            .unwrap()
+            .build()
+            .is_ok());
+
+        let config = HttpConfig {
+            proxy: None,
+            ca_file: Some(PathBuf::from("/nonexistent/ca.pem")),
+        };
+        let err = apply_http_config(reqwest::Client::builder(), &config).unwrap_err();
+        assert!(err.to_string().contains("Error reading CA file"));
+    }
+}
@@ -1,22 +1,31 @@
 use bytes::Bytes;
 use eyre::Result;
 use futures::future::try_join_all;
-use indicatif::{MultiProgress, ProgressBar};
-use std::collections::HashMap;
+use indicatif::ProgressBar;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
+pub use self::app_auth::{get_installation_token, AppCredentials};
 pub use self::auth_client::{AccessToken, AccessTokenResponse, CodeResponse, GithubAuthClient};
 pub use self::client::{GithubApiError, GithubClient};
-use crate::{git::Repository, spinner::make_spinner_style};
+use crate::{
+    git::Repository,
+    spinner::{make_spinner_style, new_multi_progress, spinners_enabled},
+};
 
 pub use current_user::CurrentUser;
-pub use pull_request_status_checks::CheckConclusionState;
+pub use http_config::{set_http_config, HttpConfig};
+pub use pull_request_status_checks::{CheckConclusionState, CheckStatusState};
 pub use types::*;
 pub use wait_for_pr_checks::*;
 
+mod app_auth;
 mod auth_client;
+mod branch_checks;
 mod client;
 mod current_user;
+mod default_branch_checks;
+mod http_config;
 mod pull_request_for_branch;
 mod pull_request_status_checks;
 mod types;
@@ -26,8 +35,18 @@ pub async fn fetch_check_run_logs(
     client: &GithubClient,
     repo: &Repository,
     check_runs: &[SimpleCheckRun],
+    max_bytes: u64,
 ) -> Result<HashMap<u64, Bytes>> {
-    let m = MultiProgress::new();
+    // A check run can be failed for several commands of ght all
+    let mut check_runs: Vec<_> = check_runs.iter().collect();
+    let mut seen = HashSet::new();
+    check_runs.retain(|check_run| seen.insert(check_run.id));
+
+    let m = new_multi_progress();
+    if !spinners_enabled() {
+        eprintln!("Fetching logs for {} checks...", check_runs.len());
+    }
+
     let log_futures: Vec<_> = check_runs
         .iter()
         .map(|cr| {
@@ -39,7 +58,7 @@ pub async fn fetch_check_run_logs(
             let check_run_id = cr.id;
             async move {
                 let result = client
-                    .get_job_logs(&repo.owner, &repo.name, check_run_id, &pb)
+                    .get_job_logs(&repo.owner, &repo.name, check_run_id, max_bytes, &pb)
                     .await;
                 pb.finish_and_clear();
                 result.map(|bytes| (check_run_id, bytes))
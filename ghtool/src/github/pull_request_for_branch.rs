@@ -44,10 +44,12 @@ pub struct PullRequestForBranch {
 pub struct Repository {
     #[arguments(headRefName: $head_ref_name, states: $states, first: 30, orderBy: { direction: "DESC", field: "CREATED_AT" })]
     pub pull_requests: PullRequestConnection,
+    #[allow(dead_code)]
     pub default_branch_ref: Option<Ref>,
 }
 
 #[derive(cynic::QueryFragment, Debug)]
+#[allow(dead_code)]
 pub struct Ref {
     pub name: String,
 }
@@ -65,6 +67,7 @@ pub struct PullRequest {
     pub state: PullRequestState,
     pub base_ref_name: String,
     pub is_cross_repository: bool,
+    pub is_draft: bool,
     pub head_repository_owner: Option<RepositoryOwner>,
 }
 
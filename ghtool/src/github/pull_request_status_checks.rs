@@ -1,14 +1,75 @@
+use std::collections::HashSet;
+
 use eyre::Result;
+use tracing::warn;
+
+use super::{PullRequestChecks, SimpleCheckRun};
 
-pub fn extract_check_runs(pull_request: PullRequest) -> Result<Vec<CheckRun>> {
+pub fn extract_check_runs(pull_request: PullRequest) -> Result<PullRequestChecks> {
     let mut nodes = pull_request.status_check_rollup.nodes.unwrap();
-    let pull_request_commit = nodes.remove(0);
+    let commit = nodes.pop().unwrap().unwrap().commit;
+    let head_sha = commit.oid.0.clone();
 
-    Ok(pull_request_commit
-        .unwrap()
-        .commit
-        .status_check_rollup
-        .ok_or_else(|| eyre::eyre!("No status check rollup found for pull request"))?
+    // A commit without checks, e.g. in a repository without workflows, has no rollup
+    Ok(PullRequestChecks {
+        head_sha,
+        check_runs: commit_check_runs(commit),
+    })
+}
+
+/// Pick the check runs of each matching job from the most recent commit where the job has
+/// completed, so that jobs still running on newer commits are skipped. The sha is that of the most
+/// recent commit any check run was picked from.
+pub fn extract_last_completed_check_runs(
+    pull_request: PullRequest,
+    match_check_run: Option<&dyn Fn(&SimpleCheckRun) -> bool>,
+) -> Result<PullRequestChecks> {
+    let commits = pull_request.status_check_rollup.nodes.unwrap();
+    let mut check_runs = Vec::new();
+    let mut shas = Vec::new();
+    let mut seen_names = HashSet::new();
+
+    // Commits are in order from oldest to newest
+    for commit in commits.into_iter().rev().flatten().map(|node| node.commit) {
+        let sha = commit.oid.0.clone();
+        let completed: Vec<_> = commit_check_runs(commit)
+            .into_iter()
+            .filter(|check_run| check_run.completed_at.is_some())
+            .filter(|check_run| match_check_run.is_none_or(|matches| matches(check_run)))
+            .filter(|check_run| !seen_names.contains(&check_run.name))
+            .collect();
+
+        if !completed.is_empty() {
+            shas.push(sha);
+        }
+        seen_names.extend(completed.iter().map(|check_run| check_run.name.clone()));
+        check_runs.extend(completed);
+    }
+
+    let head_sha = shas
+        .first()
+        .cloned()
+        .ok_or_else(|| eyre::eyre!("No completed check runs found for pull request"))?;
+    if shas.len() > 1 {
+        eprintln!(
+            "Note: check runs are from {} commits, the most recent being {}",
+            shas.len(),
+            head_sha
+        );
+    }
+
+    Ok(PullRequestChecks {
+        head_sha,
+        check_runs,
+    })
+}
+
+fn commit_check_runs(commit: Commit) -> Vec<SimpleCheckRun> {
+    let Some(status_check_rollup) = commit.status_check_rollup else {
+        return Vec::new();
+    };
+
+    status_check_rollup
         .contexts
         .nodes
         .unwrap()
@@ -20,11 +81,24 @@ pub fn extract_check_runs(pull_request: PullRequest) -> Result<Vec<CheckRun>> {
             StatusCheckRollupContext::CheckRun(check_run) => Some(check_run),
             StatusCheckRollupContext::Unknown => None,
         })
-        .collect::<Vec<_>>())
+        // Logs can't be fetched without database id, and some app-provided check runs don't
+        // have one
+        .filter(|check_run| {
+            if check_run.database_id.is_none() {
+                warn!(name = ?check_run.name, "skipping check run without database id");
+            }
+            check_run.database_id.is_some()
+        })
+        .map(SimpleCheckRun::from)
+        .collect::<Vec<_>>()
 }
 
 use cynic_github_schema as schema;
 
+/// Feature of the query selecting CheckRun.isRequired(pullRequestId:), which has changed in
+/// GitHub's schema before
+pub const IS_REQUIRED_FEATURE: &str = "is_required";
+
 // https://github.com/obmarg/cynic/issues/713
 #[derive(cynic::Scalar, Debug)]
 #[cynic(graphql_type = "Int")]
@@ -36,6 +110,7 @@ pub struct BigInt(pub u64);
 #[derive(cynic::QueryVariables, Debug)]
 pub struct PullRequestStatusChecksVariables<'a> {
     pub id: &'a cynic::Id,
+    pub commits: i32,
 }
 
 #[derive(cynic::QueryFragment, Debug)]
@@ -48,7 +123,7 @@ pub struct PullRequestStatusChecks {
 #[derive(cynic::QueryFragment, Debug)]
 #[cynic(variables = "PullRequestStatusChecksVariables")]
 pub struct PullRequest {
-    #[arguments(last: 1)]
+    #[arguments(last: $commits)]
     #[cynic(rename = "commits")]
     pub status_check_rollup: PullRequestCommitConnection,
 }
@@ -68,6 +143,7 @@ pub struct PullRequestCommit {
 #[derive(cynic::QueryFragment, Debug)]
 #[cynic(variables = "PullRequestStatusChecksVariables")]
 pub struct Commit {
+    pub oid: GitObjectId,
     pub status_check_rollup: Option<StatusCheckRollup>,
 }
 
@@ -76,6 +152,7 @@ pub struct Commit {
 pub struct StatusCheckRollup {
     #[arguments(first: 100)]
     pub contexts: StatusCheckRollupContextConnection,
+    #[allow(dead_code)]
     pub id: cynic::Id,
 }
 
@@ -83,10 +160,12 @@ pub struct StatusCheckRollup {
 #[cynic(variables = "PullRequestStatusChecksVariables")]
 pub struct StatusCheckRollupContextConnection {
     pub nodes: Option<Vec<Option<StatusCheckRollupContext>>>,
+    #[allow(dead_code)]
     pub page_info: PageInfo,
 }
 
 #[derive(cynic::QueryFragment, Debug)]
+#[allow(dead_code)]
 pub struct PageInfo {
     pub has_next_page: bool,
     pub end_cursor: Option<String>,
@@ -104,12 +183,26 @@ pub struct CheckRun {
     pub started_at: Option<DateTime>,
     pub completed_at: Option<DateTime>,
     pub details_url: Option<Uri>,
+    /// Queried only with the IS_REQUIRED_FEATURE enabled, so that the query can be retried without
+    /// it when GitHub changes the field; false when not queried
     #[arguments(pullRequestId: $id)]
+    #[cynic(feature = "is_required")]
     pub is_required: bool,
     pub database_id: Option<BigInt>,
+    pub check_suite: Option<CheckSuite>,
     pub __typename: String,
 }
 
+#[derive(cynic::QueryFragment, Debug)]
+pub struct CheckSuite {
+    pub app: Option<App>,
+}
+
+#[derive(cynic::QueryFragment, Debug)]
+pub struct App {
+    pub slug: String,
+}
+
 #[derive(cynic::InlineFragments, Debug)]
 #[cynic(variables = "PullRequestStatusChecksVariables")]
 pub enum Node {
@@ -120,6 +213,7 @@ pub enum Node {
 
 #[derive(cynic::InlineFragments, Debug)]
 #[cynic(variables = "PullRequestStatusChecksVariables")]
+#[allow(clippy::large_enum_variant)]
 pub enum StatusCheckRollupContext {
     CheckRun(CheckRun),
     #[cynic(fallback)]
@@ -152,6 +246,151 @@ pub enum CheckStatusState {
 #[derive(cynic::Scalar, Debug, Clone)]
 pub struct DateTime(pub String);
 
+#[derive(cynic::Scalar, Debug, Clone)]
+#[cynic(graphql_type = "GitObjectID")]
+pub struct GitObjectId(pub String);
+
 #[derive(cynic::Scalar, Debug, Clone)]
 #[cynic(graphql_type = "URI")]
 pub struct Uri(pub String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn check_run_json(name: &str, database_id: Option<u64>) -> serde_json::Value {
+        check_run_json_with_completion(name, database_id, true)
+    }
+
+    fn check_run_json_with_completion(
+        name: &str,
+        database_id: Option<u64>,
+        completed: bool,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "__typename": "CheckRun",
+            "id": format!("CR_{name}"),
+            "url": format!("https://github.com/org/repo/runs/{name}"),
+            "externalId": null,
+            "name": name,
+            "status": if completed { "COMPLETED" } else { "IN_PROGRESS" },
+            "conclusion": if completed { Some("FAILURE") } else { None },
+            "startedAt": "2023-09-01T10:00:00Z",
+            "completedAt": if completed { Some("2023-09-01T10:05:00Z") } else { None },
+            "detailsUrl": null,
+            "isRequired": false,
+            "databaseId": database_id,
+            "checkSuite": { "app": { "slug": "github-actions" } },
+        })
+    }
+
+    #[test]
+    fn test_skip_check_run_without_database_id() {
+        let json = serde_json::json!({
+            "commits": {
+                "nodes": [{
+                    "commit": {
+                        "oid": "0123456789abcdef0123456789abcdef01234567",
+                        "statusCheckRollup": {
+                            "id": "SCR_1",
+                            "contexts": {
+                                "nodes": [
+                                    check_run_json("Unit tests", Some(123)),
+                                    check_run_json("Third party app", None),
+                                ],
+                                "pageInfo": { "hasNextPage": false, "endCursor": null }
+                            }
+                        }
+                    }
+                }]
+            }
+        });
+
+        let pull_request: PullRequest = serde_json::from_value(json).unwrap();
+        let pr_checks = extract_check_runs(pull_request).unwrap();
+        assert_eq!(
+            pr_checks.head_sha,
+            "0123456789abcdef0123456789abcdef01234567"
+        );
+        let names: Vec<_> = pr_checks
+            .check_runs
+            .iter()
+            .map(|cr| cr.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Unit tests"]);
+    }
+
+    #[test]
+    fn test_extract_check_runs_without_rollup() {
+        let json = serde_json::json!({
+            "commits": {
+                "nodes": [{
+                    "commit": {
+                        "oid": "0123456789abcdef0123456789abcdef01234567",
+                        "statusCheckRollup": null
+                    }
+                }]
+            }
+        });
+
+        let pull_request: PullRequest = serde_json::from_value(json).unwrap();
+        let pr_checks = extract_check_runs(pull_request).unwrap();
+        assert_eq!(
+            pr_checks.head_sha,
+            "0123456789abcdef0123456789abcdef01234567"
+        );
+        assert!(pr_checks.check_runs.is_empty());
+    }
+
+    fn commit_json(oid: &str, check_runs: Vec<serde_json::Value>) -> serde_json::Value {
+        serde_json::json!({
+            "commit": {
+                "oid": oid,
+                "statusCheckRollup": {
+                    "id": format!("SCR_{oid}"),
+                    "contexts": {
+                        "nodes": check_runs,
+                        "pageInfo": { "hasNextPage": false, "endCursor": null }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_extract_last_completed_check_runs() {
+        let json = serde_json::json!({
+            "commits": {
+                "nodes": [
+                    commit_json("aaa", vec![
+                        check_run_json_with_completion("Unit tests", Some(1), true),
+                        check_run_json_with_completion("Lint", Some(2), true),
+                        check_run_json_with_completion("Deploy", Some(3), true),
+                    ]),
+                    commit_json("bbb", vec![
+                        check_run_json_with_completion("Unit tests", Some(4), true),
+                        check_run_json_with_completion("Lint", Some(5), false),
+                    ]),
+                    commit_json("ccc", vec![
+                        check_run_json_with_completion("Unit tests", Some(6), false),
+                        check_run_json_with_completion("Lint", Some(7), false),
+                    ]),
+                ]
+            }
+        });
+
+        let pull_request: PullRequest = serde_json::from_value(json.clone()).unwrap();
+        let pr_checks = extract_last_completed_check_runs(pull_request, None).unwrap();
+        assert_eq!(pr_checks.head_sha, "bbb");
+        let ids: Vec<_> = pr_checks.check_runs.iter().map(|cr| cr.id).collect();
+        assert_eq!(ids, vec![4, 2, 3]);
+
+        let pull_request: PullRequest = serde_json::from_value(json).unwrap();
+        let matcher = |check_run: &SimpleCheckRun| check_run.name == "Lint";
+        let pr_checks = extract_last_completed_check_runs(pull_request, Some(&matcher)).unwrap();
+        assert_eq!(pr_checks.head_sha, "aaa");
+        let ids: Vec<_> = pr_checks.check_runs.iter().map(|cr| cr.id).collect();
+        assert_eq!(ids, vec![2]);
+    }
+}
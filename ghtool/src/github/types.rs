@@ -2,27 +2,44 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::{
+    default_branch_checks,
     pull_request_for_branch::PullRequest,
-    pull_request_status_checks::{CheckConclusionState, CheckRun},
+    pull_request_status_checks::{self, CheckConclusionState, CheckRun, CheckStatusState},
 };
 
 #[derive(Debug, Clone)]
 pub struct SimpleCheckRun {
     pub id: u64,
     pub name: String,
+    pub status: CheckStatusState,
     pub conclusion: Option<CheckConclusionState>,
     pub url: Option<String>,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// Slug of the GitHub app that created the check run, e.g. github-actions
+    pub app: Option<String>,
+    /// Whether branch protection requires the check to pass to merge the pull request. False for
+    /// checks not of a pull request.
+    pub is_required: bool,
 }
 
 impl SimpleCheckRun {
+    /// Id of the workflow run the check run is a job of, from its url, e.g.
+    /// https://github.com/owner/repo/actions/runs/<run id>/job/<job id>
+    pub fn workflow_run_id(&self) -> Option<u64> {
+        let url = self.url.as_ref()?;
+        let (_, after_runs) = url.split_once("/actions/runs/")?;
+        after_runs.split('/').next()?.parse().ok()
+    }
+
+    /// Time since the check run started. A start in the future, e.g. because of clock skew, counts
+    /// as just started.
     pub fn elapsed(&self) -> Option<std::time::Duration> {
         self.started_at.map(|started_at| {
             Utc::now()
                 .signed_duration_since(started_at)
                 .to_std()
-                .unwrap()
+                .unwrap_or_default()
         })
     }
 }
@@ -31,32 +48,210 @@ impl From<CheckRun> for SimpleCheckRun {
     fn from(check_run: CheckRun) -> Self {
         SimpleCheckRun {
             name: check_run.name,
-            id: check_run.database_id.unwrap().0,
+            id: check_run
+                .database_id
+                .expect("check runs without database id are filtered out")
+                .0,
+            status: check_run.status,
+            conclusion: check_run.conclusion,
+            url: check_run.details_url.map(|e| e.0),
+            started_at: check_run.started_at.map(parse_date_time),
+            completed_at: check_run.completed_at.map(parse_date_time),
+            app: app_slug(check_run.check_suite),
+            is_required: check_run.is_required,
+        }
+    }
+}
+
+impl From<default_branch_checks::CheckRun> for SimpleCheckRun {
+    fn from(check_run: default_branch_checks::CheckRun) -> Self {
+        SimpleCheckRun {
+            name: check_run.name,
+            id: check_run
+                .database_id
+                .expect("check runs without database id are filtered out")
+                .0,
+            status: check_run.status,
             conclusion: check_run.conclusion,
             url: check_run.details_url.map(|e| e.0),
-            started_at: check_run.started_at.map(|e| {
-                DateTime::parse_from_rfc3339(&e.0)
-                    .expect("Failed to parse date")
-                    .with_timezone(&chrono::Utc)
-            }),
-            completed_at: check_run.completed_at.map(|e| {
-                DateTime::parse_from_rfc3339(&e.0)
-                    .expect("Failed to parse date")
-                    .with_timezone(&chrono::Utc)
-            }),
+            started_at: check_run.started_at.map(parse_date_time),
+            completed_at: check_run.completed_at.map(parse_date_time),
+            app: app_slug(check_run.check_suite),
+            is_required: false,
+        }
+    }
+}
+
+fn app_slug(check_suite: Option<pull_request_status_checks::CheckSuite>) -> Option<String> {
+    check_suite
+        .and_then(|check_suite| check_suite.app)
+        .map(|app| app.slug)
+}
+
+fn parse_date_time(date_time: pull_request_status_checks::DateTime) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&date_time.0)
+        .expect("Failed to parse date")
+        .with_timezone(&chrono::Utc)
+}
+
+/// Job of a workflow run as returned by the REST API
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkflowJob {
+    pub id: u64,
+    pub name: String,
+    pub head_sha: String,
+    /// queued, in_progress, completed, waiting, requested or pending
+    pub status: String,
+    /// success, failure, neutral, cancelled, skipped, timed_out or action_required
+    pub conclusion: Option<String>,
+    pub html_url: Option<String>,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkflowJobs {
+    pub jobs: Vec<WorkflowJob>,
+}
+
+impl From<WorkflowJob> for SimpleCheckRun {
+    fn from(job: WorkflowJob) -> Self {
+        SimpleCheckRun {
+            id: job.id,
+            name: job.name,
+            status: match job.status.as_str() {
+                "completed" => CheckStatusState::Completed,
+                "in_progress" => CheckStatusState::InProgress,
+                "queued" => CheckStatusState::Queued,
+                "requested" => CheckStatusState::Requested,
+                "waiting" => CheckStatusState::Waiting,
+                _ => CheckStatusState::Pending,
+            },
+            conclusion: job
+                .conclusion
+                .and_then(|conclusion| match conclusion.as_str() {
+                    "action_required" => Some(CheckConclusionState::ActionRequired),
+                    "cancelled" => Some(CheckConclusionState::Cancelled),
+                    "failure" => Some(CheckConclusionState::Failure),
+                    "neutral" => Some(CheckConclusionState::Neutral),
+                    "skipped" => Some(CheckConclusionState::Skipped),
+                    "stale" => Some(CheckConclusionState::Stale),
+                    "startup_failure" => Some(CheckConclusionState::StartupFailure),
+                    "success" => Some(CheckConclusionState::Success),
+                    "timed_out" => Some(CheckConclusionState::TimedOut),
+                    _ => None,
+                }),
+            url: job.html_url,
+            started_at: job
+                .started_at
+                .map(|date_time| parse_date_time(pull_request_status_checks::DateTime(date_time))),
+            completed_at: job
+                .completed_at
+                .map(|date_time| parse_date_time(pull_request_status_checks::DateTime(date_time))),
+            // Jobs of workflow runs are always check runs of GitHub Actions
+            app: Some("github-actions".to_string()),
+            is_required: false,
         }
     }
 }
 
+/// Check runs of a pull request's head commit, or of the default branch's latest commit
+#[derive(Debug, Clone)]
+pub struct PullRequestChecks {
+    pub head_sha: String,
+    pub check_runs: Vec<SimpleCheckRun>,
+}
+
+/// Annotation of a check run, e.g. an error a problem matcher found in the logs
+#[derive(Debug, Clone, Deserialize)]
+pub struct Annotation {
+    pub path: String,
+    pub start_line: u32,
+    pub start_column: Option<u32>,
+    /// notice, warning or failure
+    pub annotation_level: String,
+    pub title: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Artifact {
+    pub id: u64,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArtifactList {
+    pub artifacts: Vec<Artifact>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimplePullRequest {
     pub id: cynic::Id,
+    /// Pull requests cached before this was added are taken to not be drafts
+    #[serde(default)]
+    pub is_draft: bool,
+    /// Branch the pull request is to be merged into. Unknown for pull requests cached before this
+    /// was added.
+    #[serde(default)]
+    pub base_ref_name: Option<String>,
 }
 
 impl From<PullRequest> for SimplePullRequest {
     fn from(pull_request: PullRequest) -> Self {
         SimplePullRequest {
             id: pull_request.id,
+            is_draft: pull_request.is_draft,
+            base_ref_name: Some(pull_request.base_ref_name),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_workflow_run_id() {
+        let mut check_run = SimpleCheckRun {
+            id: 456,
+            name: "Unit tests".to_string(),
+            status: super::super::CheckStatusState::Completed,
+            conclusion: None,
+            url: Some("https://github.com/org/repo/actions/runs/123/job/456".to_string()),
+            started_at: None,
+            completed_at: None,
+            app: None,
+            is_required: false,
+        };
+        assert_eq!(check_run.workflow_run_id(), Some(123));
+
+        check_run.url = Some("https://ci.example.com/builds/1".to_string());
+        assert_eq!(check_run.workflow_run_id(), None);
+
+        check_run.url = None;
+        assert_eq!(check_run.workflow_run_id(), None);
+    }
+
+    #[test]
+    fn test_elapsed_with_future_started_at() {
+        let check_run = SimpleCheckRun {
+            id: 456,
+            name: "Unit tests".to_string(),
+            status: super::super::CheckStatusState::InProgress,
+            conclusion: None,
+            url: None,
+            started_at: Some(Utc::now() + chrono::Duration::seconds(30)),
+            completed_at: None,
+            app: None,
+            is_required: false,
+        };
+        assert_eq!(check_run.elapsed(), Some(std::time::Duration::ZERO));
+    }
+}
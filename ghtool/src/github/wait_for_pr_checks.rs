@@ -1,44 +1,127 @@
 use cynic::Id;
 use eyre::Result;
-use indicatif::{MultiProgress, ProgressBar};
+use indicatif::{HumanDuration, MultiProgress, ProgressBar};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::spinner::{make_job_completed_spinner, make_job_failed_spinner, make_job_spinner};
+use crate::error::GhtoolError;
+use crate::spinner::{
+    make_job_completed_spinner, make_job_failed_spinner, make_job_spinner, new_multi_progress,
+    spinners_enabled,
+};
 use crate::term::{bold, exit_with_error};
 
-use super::{CheckConclusionState, GithubClient, SimpleCheckRun};
+use super::{CheckConclusionState, GithubClient, PullRequestChecks, SimpleCheckRun};
 
 const POLL_INTERVAL: Duration = Duration::from_secs(10);
 
-type CheckRunMatcher = dyn Fn(&str) -> bool;
+/// How long to wait for checks to be queued for a commit without any, before taking it to have none
+/// configured
+const NO_CHECKS_TIMEOUT: Duration = Duration::from_secs(120);
 
+/// A pattern matching every check run on a pull request with at least this many check runs is
+/// likely a mistake in config
+const MATCHES_ALL_WARNING_THRESHOLD: usize = 5;
+
+type CheckRunMatcher = dyn Fn(&SimpleCheckRun) -> bool;
+
+/// Whether the check runs are in a state that ends waiting
+type DoneWaiting = fn(&[SimpleCheckRun]) -> bool;
+
+/// Wait until a check run has failed or all have completed
 pub async fn wait_for_pr_checks(
     client: &GithubClient,
     pull_request_id: Id,
-    match_checkrun_name: Option<&CheckRunMatcher>,
-) -> Result<Vec<SimpleCheckRun>> {
-    let m = MultiProgress::new();
+    match_check_run: Option<&CheckRunMatcher>,
+    expected_head_sha: Option<&str>,
+) -> Result<PullRequestChecks> {
+    wait_until(
+        client,
+        pull_request_id,
+        match_check_run,
+        expected_head_sha,
+        is_done_waiting,
+    )
+    .await
+}
+
+/// Wait until all check runs have completed, also after some have failed
+pub async fn wait_for_all_pr_checks(
+    client: &GithubClient,
+    pull_request_id: Id,
+    match_check_run: Option<&CheckRunMatcher>,
+    expected_head_sha: Option<&str>,
+) -> Result<PullRequestChecks> {
+    wait_until(
+        client,
+        pull_request_id,
+        match_check_run,
+        expected_head_sha,
+        all_completed,
+    )
+    .await
+}
+
+async fn wait_until(
+    client: &GithubClient,
+    pull_request_id: Id,
+    match_check_run: Option<&CheckRunMatcher>,
+    expected_head_sha: Option<&str>,
+    done_waiting: DoneWaiting,
+) -> Result<PullRequestChecks> {
+    let m = new_multi_progress();
     let spinners = Arc::new(Mutex::new(HashMap::new()));
 
-    let mut initial_check_runs = client.get_pr_status_checks(&pull_request_id, true).await?;
-    if let Some(match_checkrun_name) = match_checkrun_name {
-        initial_check_runs.retain(|check_run| match_checkrun_name(&check_run.name));
+    let PullRequestChecks {
+        head_sha,
+        check_runs: mut initial_check_runs,
+    } = client.get_pr_status_checks(&pull_request_id, true).await?;
+
+    // Checked before waiting, so that right after pushing we don't wait for checks of the
+    // previous commit
+    if let Some(expected_head_sha) = expected_head_sha {
+        if !head_sha_matches(&head_sha, expected_head_sha) {
+            eyre::bail!(
+                "Pull request head is {}, expected {}",
+                bold(&head_sha),
+                bold(expected_head_sha)
+            );
+        }
     }
 
-    let any_failed = initial_check_runs.iter().any(|check_run| {
-        check_run.conclusion.map_or(false, |conclusion| {
-            conclusion == CheckConclusionState::Failure
-        })
-    });
+    // Right after pushing, the commit has no checks until they're queued
+    let waiting_since = Instant::now();
+    if initial_check_runs.is_empty() {
+        eprintln!("Waiting for checks of {} to be queued...", bold(&head_sha));
+    }
+    while initial_check_runs.is_empty() {
+        if waiting_since.elapsed() >= NO_CHECKS_TIMEOUT {
+            return Err(GhtoolError::NoChecks.into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+        initial_check_runs = client
+            .get_pr_status_checks(&pull_request_id, false)
+            .await?
+            .check_runs;
+    }
 
-    let all_completed = initial_check_runs
-        .iter()
-        .all(|check_run| check_run.completed_at.map_or(false, |_| true));
+    let check_run_count = initial_check_runs.len();
+    retain_matching(&mut initial_check_runs, match_check_run);
+    if match_check_run.is_some()
+        && matches_implausibly_many(check_run_count, initial_check_runs.len())
+    {
+        eprintln!(
+            "Warning: job pattern matches all {} check runs of the pull request; check job_pattern in .ghtool.toml",
+            check_run_count
+        );
+    }
 
-    if any_failed || all_completed {
-        return Ok(initial_check_runs);
+    if done_waiting(&initial_check_runs) {
+        return Ok(PullRequestChecks {
+            head_sha,
+            check_runs: initial_check_runs,
+        });
     }
 
     let max_check_name_length = initial_check_runs
@@ -47,21 +130,23 @@ pub async fn wait_for_pr_checks(
         .max()
         .unwrap_or(0);
 
+    if !spinners_enabled() {
+        eprintln!("Waiting for {} checks...", initial_check_runs.len());
+    }
+
     for check_run in initial_check_runs.iter() {
         get_or_insert_spinner(&spinners, check_run, &m, max_check_name_length).await;
     }
 
     tokio::time::sleep(POLL_INTERVAL).await;
 
-    let check_runs = loop {
+    let pr_checks = loop {
         match client.get_pr_status_checks(&pull_request_id, false).await {
-            Ok(mut check_runs) => {
-                if let Some(match_checkrun_name) = match_checkrun_name {
-                    check_runs.retain(|check_run| match_checkrun_name(&check_run.name));
-                }
+            Ok(mut pr_checks) => {
+                retain_matching(&mut pr_checks.check_runs, match_check_run);
 
-                if process_check_runs(&m, &check_runs, &spinners).await {
-                    break check_runs;
+                if process_check_runs(&m, &pr_checks.check_runs, &spinners, done_waiting).await {
+                    break pr_checks;
                 }
             }
             Err(e) => exit_with_error(e),
@@ -69,16 +154,53 @@ pub async fn wait_for_pr_checks(
         tokio::time::sleep(POLL_INTERVAL).await;
     };
 
-    Ok(check_runs)
+    Ok(pr_checks)
+}
+
+/// Expected sha may be abbreviated
+fn head_sha_matches(head_sha: &str, expected_head_sha: &str) -> bool {
+    !expected_head_sha.is_empty()
+        && head_sha
+            .to_lowercase()
+            .starts_with(&expected_head_sha.to_lowercase())
+}
+
+/// Only the check runs the caller is interested in are waited for, so that e.g. a slow deploy
+/// check doesn't block getting test results
+fn retain_matching(
+    check_runs: &mut Vec<SimpleCheckRun>,
+    match_check_run: Option<&CheckRunMatcher>,
+) {
+    if let Some(match_check_run) = match_check_run {
+        check_runs.retain(|check_run| match_check_run(check_run));
+    }
+}
+
+fn matches_implausibly_many(check_run_count: usize, matching_count: usize) -> bool {
+    check_run_count >= MATCHES_ALL_WARNING_THRESHOLD && matching_count == check_run_count
+}
+
+/// Waiting is done when any check run has failed or all have completed
+fn is_done_waiting(check_runs: &[SimpleCheckRun]) -> bool {
+    let any_failed = check_runs
+        .iter()
+        .any(|check_run| check_run.conclusion == Some(CheckConclusionState::Failure));
+
+    any_failed || all_completed(check_runs)
+}
+
+fn all_completed(check_runs: &[SimpleCheckRun]) -> bool {
+    check_runs
+        .iter()
+        .all(|check_run| check_run.completed_at.is_some())
 }
 
 async fn process_check_runs(
     m: &MultiProgress,
     check_runs: &[SimpleCheckRun],
     spinners: &Arc<Mutex<HashMap<u64, ProgressBar>>>,
+    done_waiting: DoneWaiting,
 ) -> bool {
-    let mut any_failed = false;
-    let mut all_completed = true;
     let max_check_name_length = check_runs
         .iter()
         .map(|check_run| check_run.name.len())
@@ -89,14 +211,10 @@ async fn process_check_runs(
         let pb = get_or_insert_spinner(spinners, check_run, m, max_check_name_length).await;
         if check_run.completed_at.is_some() {
             update_spinner_on_completion(&pb, check_run);
-        } else {
-            all_completed = false;
         }
-
-        any_failed = check_run.conclusion == Some(CheckConclusionState::Failure);
     }
 
-    any_failed || all_completed
+    done_waiting(check_runs)
 }
 
 async fn get_or_insert_spinner(
@@ -137,6 +255,11 @@ fn add_spinner(
 }
 
 fn update_spinner_on_completion(pb: &ProgressBar, check_run: &SimpleCheckRun) {
+    // Completed check runs are seen again on every poll
+    if pb.is_finished() {
+        return;
+    }
+
     let (style, prefix, message) = match check_run.conclusion {
         Some(CheckConclusionState::Success) => (
             make_job_completed_spinner(),
@@ -157,5 +280,104 @@ fn update_spinner_on_completion(pb: &ProgressBar, check_run: &SimpleCheckRun) {
 
     pb.set_style(style);
     pb.set_prefix(prefix);
+    if !spinners_enabled() {
+        eprintln!("{} {} {}", prefix, message, HumanDuration(pb.elapsed()));
+    }
     pb.finish_with_message(message);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::CheckStatusState;
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+
+    fn check_run(
+        id: u64,
+        name: &str,
+        conclusion: Option<CheckConclusionState>,
+        completed: bool,
+    ) -> SimpleCheckRun {
+        SimpleCheckRun {
+            id,
+            name: name.to_string(),
+            status: if completed {
+                CheckStatusState::Completed
+            } else {
+                CheckStatusState::InProgress
+            },
+            conclusion,
+            url: None,
+            started_at: None,
+            completed_at: completed.then(Utc::now),
+            app: Some("github-actions".to_string()),
+            is_required: false,
+        }
+    }
+
+    #[test]
+    fn test_wait_only_for_matching_check_runs() {
+        let mut check_runs = vec![
+            check_run(
+                1,
+                "Unit tests (1)",
+                Some(CheckConclusionState::Success),
+                true,
+            ),
+            check_run(2, "Deploy preview", None, false),
+            check_run(
+                3,
+                "Unit tests (2)",
+                Some(CheckConclusionState::Success),
+                true,
+            ),
+        ];
+        assert!(!is_done_waiting(&check_runs));
+
+        let matcher = |check_run: &SimpleCheckRun| check_run.name.starts_with("Unit tests");
+        retain_matching(&mut check_runs, Some(&matcher));
+        let ids: Vec<_> = check_runs.iter().map(|cr| cr.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert!(is_done_waiting(&check_runs));
+    }
+
+    #[test]
+    fn test_head_sha_matches() {
+        let head_sha = "0123456789abcdef0123456789abcdef01234567";
+        assert!(head_sha_matches(head_sha, head_sha));
+        assert!(head_sha_matches(head_sha, "0123456"));
+        assert!(head_sha_matches(head_sha, "0123456789ABCDEF"));
+        assert!(!head_sha_matches(head_sha, "1234567"));
+        assert!(!head_sha_matches(head_sha, ""));
+    }
+
+    #[test]
+    fn test_matches_implausibly_many() {
+        assert!(matches_implausibly_many(5, 5));
+        assert!(!matches_implausibly_many(5, 4));
+        assert!(!matches_implausibly_many(2, 2));
+    }
+
+    #[test]
+    fn test_done_waiting_when_any_matching_check_run_failed() {
+        let check_runs = vec![
+            check_run(
+                1,
+                "Unit tests (1)",
+                Some(CheckConclusionState::Failure),
+                true,
+            ),
+            check_run(2, "Unit tests (2)", None, false),
+            check_run(
+                3,
+                "Unit tests (3)",
+                Some(CheckConclusionState::Success),
+                true,
+            ),
+        ];
+        assert!(is_done_waiting(&check_runs));
+        // Until green waits for the rest after a failure
+        assert!(!all_completed(&check_runs));
+    }
+}
@@ -1,10 +1,15 @@
 pub mod cache;
 pub mod cli;
 pub mod commands;
+pub mod error;
+pub mod gh_config;
 pub mod git;
 pub mod github;
+pub mod log_cache;
 pub mod repo_config;
 pub mod setup;
 pub mod spinner;
 pub mod term;
 pub mod token_store;
+pub mod trace;
+pub mod update_check;
@@ -0,0 +1,112 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Once,
+    time::Duration,
+};
+
+use bytes::Bytes;
+use eyre::{Result, WrapErr};
+use lazy_static::lazy_static;
+use tracing::debug;
+
+lazy_static! {
+    /// Logs are kept as files next to the sled cache rather than in it, as they can be hundreds
+    /// of megabytes
+    static ref LOG_CACHE_DIR: PathBuf = {
+        let mut path = dirs::cache_dir().expect("failed to get cache dir");
+        path.push("ghtool-logs");
+        path
+    };
+}
+
+/// Logs cached longer ago than this are removed, so that the cache doesn't grow without bound.
+/// Logs are mostly read again soon after they're cached, e.g. with --reparse.
+const MAX_LOG_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+static REMOVE_OLD_LOGS: Once = Once::new();
+
+/// Ids of check runs are unique on a host, so logs are kept in a directory of each host
+fn log_path(hostname: &str, check_run_id: u64) -> PathBuf {
+    LOG_CACHE_DIR
+        .join(hostname)
+        .join(format!("{check_run_id}.log"))
+}
+
+/// Logs of a check run downloaded earlier. Logs of completed check runs don't change, so they are
+/// never stale.
+pub fn get(hostname: &str, check_run_id: u64) -> Result<Option<Bytes>> {
+    match fs::read(log_path(hostname, check_run_id)) {
+        Ok(log) => {
+            debug!(check_run_id, "found cached log");
+            Ok(Some(Bytes::from(log)))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).wrap_err_with(|| format!("Failed to read cached log of {check_run_id}")),
+    }
+}
+
+pub fn put(hostname: &str, check_run_id: u64, log: &[u8]) -> Result<()> {
+    REMOVE_OLD_LOGS.call_once(|| remove_old_logs(&LOG_CACHE_DIR, MAX_LOG_AGE));
+    let path = log_path(hostname, check_run_id);
+    let dir = path.parent().unwrap();
+    fs::create_dir_all(dir).wrap_err_with(|| format!("Failed to create {}", dir.display()))?;
+    fs::write(path, log).wrap_err_with(|| format!("Failed to cache log of {check_run_id}"))?;
+    debug!(check_run_id, "cached log");
+    Ok(())
+}
+
+/// Remove the logs of all hosts in the directory that were written longer ago than max_age.
+/// Failing to remove them only leaves them behind, so it doesn't fail caching a log.
+fn remove_old_logs(dir: &Path, max_age: Duration) {
+    let Ok(host_dirs) = fs::read_dir(dir) else {
+        return;
+    };
+    for log in host_dirs
+        .flatten()
+        .filter_map(|host_dir| fs::read_dir(host_dir.path()).ok())
+        .flatten()
+        .flatten()
+    {
+        let is_old = log
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if is_old {
+            match fs::remove_file(log.path()) {
+                Ok(()) => debug!(path = ?log.path(), "removed old cached log"),
+                Err(e) => debug!(path = ?log.path(), ?e, "failed to remove old cached log"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_remove_old_logs() {
+        let dir = std::env::temp_dir().join(format!("ghtool-log-cache-{}", std::process::id()));
+        let host_dir = dir.join("github.com");
+        fs::create_dir_all(&host_dir).unwrap();
+        let (old_log, new_log) = (host_dir.join("1.log"), host_dir.join("2.log"));
+        fs::write(&old_log, "old").unwrap();
+        fs::write(&new_log, "new").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&old_log)
+            .unwrap()
+            .set_modified(SystemTime::now() - MAX_LOG_AGE * 2)
+            .unwrap();
+
+        remove_old_logs(&dir, MAX_LOG_AGE);
+
+        assert!(!old_log.exists());
+        assert!(new_log.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,68 +1,272 @@
 use eyre::{Result, WrapErr};
-use serde::{Deserialize, Deserializer};
-use std::{fs, path::Path};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{Schema, SchemaObject, SubschemaValidation},
+    JsonSchema,
+};
+use serde::{
+    de::{self, IntoDeserializer, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{fmt, fs, marker::PhantomData, path::Path};
 
-#[derive(Debug, Deserialize)]
+use crate::error::GhtoolError;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[schemars(title = ".ghtool.toml", deny_unknown_fields)]
 pub struct RepoConfig {
     pub test: Option<TestConfig>,
     pub lint: Option<LintConfig>,
     pub build: Option<BuildConfig>,
+    /// Check at most once a day whether a newer version of ghtool has been released, and tell
+    /// about it
+    #[serde(default)]
+    pub check_for_updates: bool,
+    /// Message printed when all checks are green, instead of "✓ All checks are green". An empty
+    /// message prints nothing.
+    #[serde(default)]
+    pub success_message: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct TestConfig {
+/// Fields that the test, lint and build sections all have
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
+pub struct SectionConfig {
+    /// Regular expression to match the names of the section's jobs
     #[serde(deserialize_with = "deserialize_regex")]
+    #[schemars(with = "String")]
     pub job_pattern: regex::Regex,
+    /// Directory the tool is run in relative to the repository root, e.g. "packages/web", when a
+    /// job changes to it first. It's prepended to the relative paths the tool reports.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// Shell command that the results are piped through as JSON before printing, e.g. to map
+    /// paths of transpiled files back to their sources. Its output must be JSON of the same shape.
+    #[serde(default)]
+    pub post_parse_command: Option<String>,
+    /// Slug of the GitHub app whose check runs are considered, "github-actions" by default. "*"
+    /// considers check runs of any app.
+    #[serde(default)]
+    pub app: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
+pub struct TestConfig {
+    #[serde(flatten)]
+    pub section: SectionConfig,
     pub tool: TestRunner,
+    /// Regular expression to match the shard part of sharded test job names, e.g. " \\(\\d+/\\d+\\)".
+    /// Results of jobs with the same name apart from the shard are shown together.
+    #[serde(default, deserialize_with = "deserialize_optional_regex")]
+    #[schemars(with = "Option<String>")]
+    pub shard_pattern: Option<regex::Regex>,
+    /// Name of a workflow run artifact with JUnit XML reports, e.g. uploaded from jest-junit's
+    /// output. When found, failures are read from it instead of logs.
+    #[serde(default)]
+    pub junit_artifact: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
 pub struct LintConfig {
-    #[serde(deserialize_with = "deserialize_regex")]
-    pub job_pattern: regex::Regex,
-    pub tool: LintTool,
+    #[serde(flatten)]
+    pub section: SectionConfig,
+    /// Lint tool, or a list of them when jobs may run any of them, e.g. ["eslint", "biome"]. Logs
+    /// are parsed with the one that finds the most issues.
+    pub tool: Tools<LintTool>,
+    /// Directory of workspace packages relative to the repository root, e.g. "packages/{package}".
+    /// Paths in output of eslint run per package are prefixed with it, {package} being the package
+    /// name from turbo's task prefix without npm scope.
+    #[serde(default)]
+    pub package_dir: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
 pub struct BuildConfig {
-    #[serde(deserialize_with = "deserialize_regex")]
-    pub job_pattern: regex::Regex,
-    pub tool: BuildTool,
+    #[serde(flatten)]
+    pub section: SectionConfig,
+    /// Build tool, or a list of them when jobs may run any of them, e.g. ["tsc", "cargo"]. Logs
+    /// are parsed with the one that finds the most errors.
+    pub tool: Tools<BuildTool>,
+}
+
+impl RepoConfig {
+    /// Consider check runs of the given app in every section, e.g. when given on the command line
+    pub fn set_app(&mut self, app: &str) {
+        let app = Some(app.to_string());
+        if let Some(test) = &mut self.test {
+            test.section.app.clone_from(&app);
+        }
+        if let Some(lint) = &mut self.lint {
+            lint.section.app.clone_from(&app);
+        }
+        if let Some(build) = &mut self.build {
+            build.section.app = app;
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Test runner used in tests; determines how logs are parsed
+#[derive(Debug, Clone, JsonSchema)]
+#[schemars(rename_all = "lowercase")]
 pub enum TestRunner {
     Jest,
+    Gradle,
+    #[schemars(rename = "deno")]
+    DenoTest,
 }
 
-#[derive(Debug, Clone)]
+/// Lint tool used in the checks; determines how logs are parsed
+#[derive(Debug, Clone, PartialEq, JsonSchema)]
+#[schemars(rename_all = "lowercase")]
 pub enum LintTool {
     Eslint,
+    Biome,
 }
 
-#[derive(Debug, Clone)]
+/// Build tool used in matching jobs; determines how logs are parsed
+#[derive(Debug, Clone, PartialEq, JsonSchema)]
+#[schemars(rename_all = "lowercase")]
 pub enum BuildTool {
     Tsc,
+    Cargo,
+    Phpstan,
+    Psalm,
+}
+
+/// Tools configured for a section, given as one name or a list of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tools<T>(Vec<T>);
+
+impl<T> Tools<T> {
+    /// In the order they're configured in; never empty
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
 }
 
-fn deserialize_tool<'de, D, T>(
-    deserializer: D,
-    valid_tool: &'static str,
-    tool: T,
-    tool_name: &str,
-) -> Result<T, D::Error>
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Tools<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ToolsVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for ToolsVisitor<T> {
+            type Value = Tools<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a tool or a list of tools")
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                T::deserialize(s.into_deserializer()).map(|tool| Tools(vec![tool]))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                let tools = Vec::<T>::deserialize(de::value::SeqAccessDeserializer::new(seq))?;
+                if tools.is_empty() {
+                    return Err(de::Error::custom("list of tools must not be empty"));
+                }
+                Ok(Tools(tools))
+            }
+        }
+
+        deserializer.deserialize_any(ToolsVisitor(PhantomData))
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Tools<T> {
+    fn schema_name() -> String {
+        format!("Tools_of_{}", T::schema_name())
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![
+                    gen.subschema_for::<T>(),
+                    gen.subschema_for::<Vec<T>>(),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Value of a section's `tool`, listed by `ght tools`
+pub trait Tool: Clone + 'static {
+    /// Every tool, by the name it's given as in .ghtool.toml
+    const NAMES: &'static [(&'static str, Self)];
+
+    /// What the tool is in error messages, e.g. "test runner"
+    const KIND: &'static str;
+
+    /// One line telling which output of the tool is parsed
+    fn description(&self) -> &'static str;
+}
+
+impl Tool for TestRunner {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("jest", TestRunner::Jest),
+        ("gradle", TestRunner::Gradle),
+        ("deno", TestRunner::DenoTest),
+    ];
+    const KIND: &'static str = "test runner";
+
+    fn description(&self) -> &'static str {
+        match self {
+            TestRunner::Jest => "Jest, also run through turbo or nx",
+            TestRunner::Gradle => "Gradle test tasks, e.g. JUnit tests; failures are by class",
+            TestRunner::DenoTest => "deno test, including failed test steps",
+        }
+    }
+}
+
+impl Tool for LintTool {
+    const NAMES: &'static [(&'static str, Self)] =
+        &[("eslint", LintTool::Eslint), ("biome", LintTool::Biome)];
+    const KIND: &'static str = "lint tool";
+
+    fn description(&self) -> &'static str {
+        match self {
+            LintTool::Eslint => "ESLint with the default stylish formatter",
+            LintTool::Biome => "biome lint, check and ci",
+        }
+    }
+}
+
+impl Tool for BuildTool {
+    const NAMES: &'static [(&'static str, Self)] = &[
+        ("tsc", BuildTool::Tsc),
+        ("cargo", BuildTool::Cargo),
+        ("phpstan", BuildTool::Phpstan),
+        ("psalm", BuildTool::Psalm),
+    ];
+    const KIND: &'static str = "build tool";
+
+    fn description(&self) -> &'static str {
+        match self {
+            BuildTool::Tsc => "TypeScript compiler",
+            BuildTool::Cargo => "rustc diagnostics of cargo build, check, test and clippy",
+            BuildTool::Phpstan => "PHPStan with the default table format",
+            BuildTool::Psalm => "Psalm with the default console format, ERROR issues only",
+        }
+    }
+}
+
+fn deserialize_tool<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
     D: Deserializer<'de>,
+    T: Tool,
 {
     let s = String::deserialize(deserializer)?;
-    if s.eq_ignore_ascii_case(valid_tool) {
-        Ok(tool)
-    } else {
-        Err(serde::de::Error::custom(format!(
-            "invalid {}: {}",
-            tool_name, s
-        )))
-    }
+    T::NAMES
+        .iter()
+        .find(|(name, _)| s.eq_ignore_ascii_case(name))
+        .map(|(_, tool)| tool.clone())
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid {}: {}", T::KIND, s)))
 }
 
 impl<'de> Deserialize<'de> for TestRunner {
@@ -70,7 +274,7 @@ impl<'de> Deserialize<'de> for TestRunner {
     where
         D: Deserializer<'de>,
     {
-        deserialize_tool(deserializer, "jest", TestRunner::Jest, "test runner")
+        deserialize_tool(deserializer)
     }
 }
 
@@ -79,7 +283,7 @@ impl<'de> Deserialize<'de> for LintTool {
     where
         D: Deserializer<'de>,
     {
-        deserialize_tool(deserializer, "eslint", LintTool::Eslint, "lint tool")
+        deserialize_tool(deserializer)
     }
 }
 
@@ -88,7 +292,7 @@ impl<'de> Deserialize<'de> for BuildTool {
     where
         D: Deserializer<'de>,
     {
-        deserialize_tool(deserializer, "tsc", BuildTool::Tsc, "build tool")
+        deserialize_tool(deserializer)
     }
 }
 
@@ -97,16 +301,25 @@ where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
+    // An empty pattern would match every check run and cause logs of all jobs to be downloaded
+    if s.trim().is_empty() {
+        return Err(serde::de::Error::custom("pattern must not be empty"));
+    }
     regex::Regex::new(&s).map_err(serde::de::Error::custom)
 }
 
+fn deserialize_optional_regex<'de, D>(deserializer: D) -> Result<Option<regex::Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_regex(deserializer).map(Some)
+}
+
 pub fn read_repo_config_from_path(config_path: &Path) -> Result<RepoConfig> {
-    let config_str = fs::read_to_string(config_path).wrap_err_with(|| {
-        format!(
-            "Error reading config from path {}",
-            config_path.to_string_lossy()
-        )
-    })?;
+    let config_str =
+        fs::read_to_string(config_path).wrap_err_with(|| GhtoolError::ConfigNotReadable {
+            path: config_path.to_string_lossy().to_string(),
+        })?;
     let config: RepoConfig = toml::from_str(&config_str)?;
     Ok(config)
 }
@@ -115,3 +328,114 @@ pub fn read_repo_config(repo_path: &Path) -> Result<RepoConfig> {
     let config_path = repo_path.join(".ghtool.toml");
     read_repo_config_from_path(&config_path)
 }
+
+pub fn repo_config_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(RepoConfig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_empty_job_pattern() {
+        for job_pattern in ["", "  "] {
+            let config = format!("[test]\njob_pattern = \"{job_pattern}\"\ntool = \"jest\"\n");
+            let err = toml::from_str::<RepoConfig>(&config).unwrap_err();
+            assert!(err.to_string().contains("pattern must not be empty"));
+        }
+
+        let config = "[test]\njob_pattern = \"Unit tests\"\ntool = \"jest\"\n";
+        assert!(toml::from_str::<RepoConfig>(config).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_section() {
+        let config = "[tests]\njob_pattern = \"Unit tests\"\ntool = \"jest\"\n";
+        let err = toml::from_str::<RepoConfig>(config).unwrap_err();
+        assert!(err.to_string().contains("unknown field `tests`"));
+    }
+
+    #[test]
+    fn test_shard_pattern() {
+        let config = "[test]\njob_pattern = \"Unit tests\"\ntool = \"jest\"\n";
+        let repo_config = toml::from_str::<RepoConfig>(config).unwrap();
+        assert!(repo_config.test.unwrap().shard_pattern.is_none());
+
+        let config = format!("{config}shard_pattern = ' \\(\\d+/\\d+\\)'\n");
+        let repo_config = toml::from_str::<RepoConfig>(&config).unwrap();
+        let shard_pattern = repo_config.test.unwrap().shard_pattern.unwrap();
+        assert!(shard_pattern.is_match("Unit tests (1/4)"));
+    }
+
+    #[test]
+    fn test_tools() {
+        let lint_tools = |tool: &str| {
+            let config = format!("[lint]\njob_pattern = \"Lint\"\ntool = {tool}\n");
+            toml::from_str::<RepoConfig>(&config).map(|config| config.lint.unwrap().tool)
+        };
+
+        assert_eq!(
+            lint_tools("\"eslint\"").unwrap(),
+            Tools(vec![LintTool::Eslint])
+        );
+        assert_eq!(
+            lint_tools("[\"eslint\", \"Biome\"]").unwrap(),
+            Tools(vec![LintTool::Eslint, LintTool::Biome])
+        );
+        assert!(lint_tools("[]")
+            .unwrap_err()
+            .to_string()
+            .contains("list of tools must not be empty"));
+        assert!(lint_tools("[\"eslint\", \"jslint\"]")
+            .unwrap_err()
+            .to_string()
+            .contains("invalid lint tool: jslint"));
+    }
+
+    fn schema_names<T: Tool>() -> serde_json::Value {
+        T::NAMES.iter().map(|(name, _)| *name).collect()
+    }
+
+    #[test]
+    fn test_tool_names_match_schema() {
+        let schema = serde_json::to_value(repo_config_schema()).unwrap();
+        let definitions = &schema["definitions"];
+        assert_eq!(
+            definitions["TestRunner"]["enum"],
+            schema_names::<TestRunner>()
+        );
+        assert_eq!(definitions["LintTool"]["enum"], schema_names::<LintTool>());
+        assert_eq!(
+            definitions["BuildTool"]["enum"],
+            schema_names::<BuildTool>()
+        );
+    }
+
+    #[test]
+    fn test_repo_config_schema() {
+        let schema = serde_json::to_value(repo_config_schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        let mut sections: Vec<_> = properties.keys().collect();
+        sections.sort();
+        assert_eq!(
+            sections,
+            vec![
+                "build",
+                "check_for_updates",
+                "lint",
+                "success_message",
+                "test"
+            ]
+        );
+        assert_eq!(
+            schema["definitions"]["TestRunner"]["enum"],
+            serde_json::json!(["jest", "gradle", "deno"])
+        );
+        assert_eq!(
+            schema["definitions"]["TestConfig"]["properties"]["job_pattern"]["type"],
+            "string"
+        );
+    }
+}
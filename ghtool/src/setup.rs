@@ -12,17 +12,43 @@ use tracing_subscriber::EnvFilter;
 
 use crate::{
     cli::Cli,
+    error::GhtoolError,
     git::{parse_repository_from_github, Git, Repository},
+    github::{set_http_config, HttpConfig},
     repo_config::{read_repo_config, read_repo_config_from_path, RepoConfig},
+    spinner::set_spinners_enabled,
+    term::{colors_enabled, set_color_mode, set_compact_headers, set_strip_ansi},
+    trace::set_trace_enabled,
 };
 
 pub fn setup() -> Result<Cli> {
     let cli = Cli::parse();
 
-    if cli.verbose {
-        std::env::set_var("RUST_LOG", "info");
+    // Variables already set take precedence over the file
+    if let Some(env_file) = &cli.env_file {
+        dotenvy::from_path(env_file)
+            .wrap_err_with(|| format!("Failed to load {}", env_file.display()))?;
     }
 
+    match cli.verbose {
+        0 => {}
+        1 => std::env::set_var("RUST_LOG", "info"),
+        _ => std::env::set_var("RUST_LOG", "debug"),
+    }
+
+    set_color_mode(cli.color);
+    set_strip_ansi(cli.strip_ansi || (!cli.keep_ansi && !colors_enabled()));
+    set_compact_headers(cli.compact);
+    set_spinners_enabled(!cli.no_spinner);
+    set_trace_enabled(cli.trace_requests);
+    set_http_config(HttpConfig {
+        proxy: cli.proxy.clone(),
+        ca_file: cli
+            .cainfo
+            .clone()
+            .or_else(|| env::var_os("SSL_CERT_FILE").map(PathBuf::from)),
+    });
+
     setup_env()?;
     Ok(cli)
 }
@@ -47,16 +73,31 @@ pub fn get_repo_config(cli: &Cli) -> Result<(RepoConfig, Repository, String)> {
         .map(|p| Path::new(&p).to_path_buf())
         .map_err(|e| eyre::eyre!("Error getting repo config path: {}", e))
         .and_then(|p| read_repo_config_from_path(&p));
-    let repo_from_env = env::var("REPO").map(|s| parse_repository_from_github(&s).unwrap());
+    let repo_from_env = env::var("REPO").map(|s| parse_repository_from_github(&s, &cli.host));
 
     // The env variables are meant to help with development. I opted to not put them as cli
     // arguments as they would make --help more noisy.
-    let (repo_config, repo, branch) = match (env_repo_config, repo_from_env) {
+    let (mut repo_config, repo, branch) = match (env_repo_config, repo_from_env) {
+        (env_repo_config, _) if cli.repo.is_some() => {
+            let repo = parse_repository_from_github(cli.repo.as_ref().unwrap(), &cli.host)?;
+            let branch = cli
+                .branch
+                .clone()
+                .ok_or_else(|| eyre::eyre!("Error: --branch must be given when using --repo"))?;
+            // Outside of a git repository, the config can still be given with --config or
+            // REPO_CONFIG
+            let repo_config = match (&cli.config, env_repo_config) {
+                (Some(config_path), _) => read_repo_config_from_path(config_path)?,
+                (None, Ok(repo_config)) => repo_config,
+                (None, Err(_)) => read_repo_config(&get_repo_path()?)?,
+            };
+            (repo_config, repo, branch)
+        }
         (Ok(repo_config), Ok(repo)) => {
             let branch = cli.branch.clone().ok_or_else(|| {
                 eyre::eyre!("Error: --branch must be given when using REPO env variable")
             })?;
-            (repo_config, repo, branch)
+            (repo_config, repo?, branch)
         }
         (Ok(_), Err(_)) | (Err(_), Ok(_)) => {
             eyre::bail!("Error: both env variables REPO and REPO_CONFIG should be given at the same time or not at all")
@@ -64,11 +105,18 @@ pub fn get_repo_config(cli: &Cli) -> Result<(RepoConfig, Repository, String)> {
         (Err(_), Err(_)) => {
             let repo_path = get_repo_path()?;
             let (repo, current_branch) = get_git_info(&repo_path, cli)?;
-            let repo_config = read_repo_config(&repo_path)?;
+            let repo_config = match &cli.config {
+                Some(config_path) => read_repo_config_from_path(config_path)?,
+                None => read_repo_config(&repo_path)?,
+            };
             (repo_config, repo, current_branch)
         }
     };
 
+    if let Some(app) = &cli.app {
+        repo_config.set_app(app);
+    }
+
     info!(?repo_config, ?repo, "config");
     Ok((repo_config, repo, branch))
 }
@@ -86,12 +134,33 @@ fn find_git_ancestor(mut dir: PathBuf) -> Option<PathBuf> {
     }
 }
 
-fn get_repo_path() -> Result<PathBuf> {
-    env::var("REPO_PATH")
-        .map(|p| Path::new(&p).to_path_buf())
-        .or_else(|_| env::current_dir().wrap_err("Failed to get current directory"))
-        .and_then(|path| find_git_ancestor(path).ok_or(eyre::eyre!("Not in git repository")))
-        .map_err(|e| eyre::eyre!("Error getting repo path: {}", e))
+/// The repository of --repo, or else of the origin remote of the repository in the current
+/// directory
+pub fn get_repository(cli: &Cli) -> Result<Repository> {
+    match &cli.repo {
+        Some(repo) => parse_repository_from_github(repo, &cli.host),
+        None => Git::new(get_repo_path()?).get_remote(),
+    }
+}
+
+/// Read the repo config without looking up the repository, for commands that work offline
+pub fn get_local_repo_config(cli: &Cli) -> Result<RepoConfig> {
+    if let Some(config_path) = &cli.config {
+        return read_repo_config_from_path(config_path);
+    }
+    if let Ok(config_path) = env::var("REPO_CONFIG") {
+        return read_repo_config_from_path(Path::new(&config_path));
+    }
+    read_repo_config(&get_repo_path()?)
+}
+
+pub fn get_repo_path() -> Result<PathBuf> {
+    let path = match env::var("REPO_PATH") {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => env::current_dir()
+            .wrap_err("Error getting repo path: Failed to get current directory")?,
+    };
+    find_git_ancestor(path).ok_or_else(|| GhtoolError::NotInGitRepository.into())
 }
 
 fn get_git_info(repo_path: &Path, cli: &Cli) -> Result<(Repository, String)> {
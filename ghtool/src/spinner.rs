@@ -1,7 +1,48 @@
-use indicatif::ProgressStyle;
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 
 const TICK_CHARS: &str = "⠁⠂⠄⡀⢀⠠⠐⠈ ";
 
+static SPINNERS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub fn set_spinners_enabled(enabled: bool) {
+    SPINNERS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn spinners_enabled() -> bool {
+    SPINNERS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Create a ticking spinner with a message. When spinners are disabled, the message is printed
+/// once to stderr instead and the returned progress bar is hidden.
+pub fn new_spinner(message: impl Into<Cow<'static, str>>) -> ProgressBar {
+    let message = message.into();
+    if !spinners_enabled() {
+        eprintln!("{}", message);
+        return ProgressBar::hidden();
+    }
+
+    let pb = ProgressBar::new_spinner();
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb.set_style(make_spinner_style());
+    pb.set_message(message);
+    pb
+}
+
+/// Progress bars added to the returned MultiProgress are hidden when spinners are disabled
+pub fn new_multi_progress() -> MultiProgress {
+    if spinners_enabled() {
+        MultiProgress::new()
+    } else {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    }
+}
+
 pub fn make_spinner_style() -> ProgressStyle {
     ProgressStyle::with_template("{spinner:.yellow.bold} {msg}")
         .unwrap()
@@ -1,58 +1,143 @@
-use std::io::{self, Write};
+use std::{
+    borrow::Cow,
+    io::{self, IsTerminal, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use eyre::Result;
 
-use crate::github;
+use crate::{cli::ColorWhen, error, github};
+
+static COLORS_ENABLED: AtomicBool = AtomicBool::new(true);
+static COMPACT_HEADERS: AtomicBool = AtomicBool::new(false);
+static STRIP_ANSI: AtomicBool = AtomicBool::new(false);
+
+pub fn set_color_mode(color: ColorWhen) {
+    let enabled = match color {
+        ColorWhen::Always => true,
+        ColorWhen::Never => false,
+        ColorWhen::Auto => std::env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal(),
+    };
+    COLORS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn colors_enabled() -> bool {
+    COLORS_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Remove ANSI escapes from lines of CI logs before they're printed
+pub fn set_strip_ansi(strip: bool) {
+    STRIP_ANSI.store(strip, Ordering::Relaxed);
+}
+
+/// A line of a CI log as it should be printed, without its colors if ANSI is stripped
+pub fn log_line(line: &str) -> Cow<'_, str> {
+    if STRIP_ANSI.load(Ordering::Relaxed) {
+        Cow::Owned(strip_ansi_escapes::strip_str(line))
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+/// Print check run headers as a single markdown heading line instead of a box
+pub fn set_compact_headers(compact: bool) {
+    COMPACT_HEADERS.store(compact, Ordering::Relaxed);
+}
+
+fn paint(code: &str, text: &str) -> String {
+    if colors_enabled() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
 
 pub fn bold(text: &str) -> String {
-    format!("\x1b[1m{}\x1b[0m", text)
+    paint("1", text)
 }
 
 pub fn green(text: &str) -> String {
-    format!("\x1b[32m{}\x1b[0m", text)
+    paint("32", text)
 }
 
+pub fn red(text: &str) -> String {
+    paint("31", text)
+}
+
+/// Width of headers when stdout is not a terminal
+const DEFAULT_WIDTH: usize = 80;
+
+/// Narrowest header that fits a truncated line: borders, padding and an ellipsis
+const MIN_WIDTH: usize = 8;
+
 pub fn print_header(header: &str) {
-    if let Some((w, _)) = term_size::dimensions() {
-        let lines = header.split('\n').collect::<Vec<_>>();
-        let horizontal_border = "─".repeat(w - 2);
-        let border = format!("┌{}┐", horizontal_border);
-        let end_border = format!("└{}┘", horizontal_border);
-        println!("{}", border);
-        for line in lines {
-            let stripped_line = strip_ansi_escapes::strip(line);
-            let mut line = String::from_utf8(stripped_line).unwrap();
-            let line_len = line.chars().count();
-            if line_len > w - 4 {
-                let truncated_line_len = w - 7; // For ellipsis and spaces
-                line = line.chars().take(truncated_line_len).collect::<String>();
-                line.push_str("...");
-            }
-            let line_padding = w - line.chars().count() - 4;
-            let header_line = format!("│ {}{} │", line, " ".repeat(line_padding));
-            println!("{}", header_line);
+    let width = term_size::dimensions().map_or(DEFAULT_WIDTH, |(w, _)| w);
+    print!("{}", header_box(header, width));
+}
+
+/// The header in a box as wide as the terminal. Lines too long for it are truncated with an
+/// ellipsis.
+fn header_box(header: &str, width: usize) -> String {
+    let w = width.max(MIN_WIDTH);
+    let horizontal_border = "─".repeat(w - 2);
+    let mut output = format!("┌{}┐\n", horizontal_border);
+    for line in header.split('\n') {
+        let mut line = strip_ansi_escapes::strip_str(line);
+        let line_len = line.chars().count();
+        if line_len > w - 4 {
+            let truncated_line_len = w - 7; // For ellipsis and spaces
+            line = line.chars().take(truncated_line_len).collect::<String>();
+            line.push_str("...");
         }
-        println!("{}", end_border);
+        let line_padding = w - line.chars().count() - 4;
+        output.push_str(&format!("│ {}{} │\n", line, " ".repeat(line_padding)));
     }
+    output.push_str(&format!("└{}┘\n", horizontal_border));
+    output
 }
 
 pub fn exit_with_error<T>(e: eyre::Error) -> T {
-    eprintln!("{}", e);
-    std::process::exit(1);
+    if e.downcast_ref::<error::GhtoolError>().is_some() {
+        eprintln!("{} {}", bold(&red("Error:")), e);
+    } else {
+        eprintln!("{}", e);
+    }
+    std::process::exit(error::exit_code(&e));
 }
 
-pub fn print_check_run_header(check_run: &github::SimpleCheckRun) {
-    print_header(&format!(
-        "{} {}\n{} {}",
-        bold("Job:"),
-        check_run.name,
-        bold("Url:"),
-        check_run.url.as_ref().unwrap()
-    ));
+pub fn print_check_run_header(check_run: &github::SimpleCheckRun, head_sha: &str) {
+    if COMPACT_HEADERS.load(Ordering::Relaxed) {
+        println!("{}", compact_header(check_run));
+        return;
+    }
+
+    print_header(&check_run_header(check_run, head_sha));
 }
 
-pub fn print_all_checks_green() {
-    eprintln!("{} All checks are green", green("✓"));
+/// Check runs of some apps have no details url, in which case the line of the url is left out
+fn check_run_header(check_run: &github::SimpleCheckRun, head_sha: &str) -> String {
+    let mut lines = vec![format!("{} {}", bold("Job:"), check_run.name)];
+    if let Some(url) = &check_run.url {
+        lines.push(format!("{} {}", bold("Url:"), url));
+    }
+    lines.push(format!("{} {}", bold("Commit:"), head_sha));
+    lines.join("\n")
+}
+
+fn compact_header(check_run: &github::SimpleCheckRun) -> String {
+    match &check_run.url {
+        Some(url) => format!("## {} ({})", check_run.name, url),
+        None => format!("## {}", check_run.name),
+    }
+}
+
+/// Print the message instead of the default one if given; an empty message prints nothing
+pub fn print_all_checks_green(message: Option<&str>) {
+    match message {
+        Some("") => {}
+        Some(message) => eprintln!("{}", message),
+        None => eprintln!("{} All checks are green", green("✓")),
+    }
 }
 
 pub fn read_stdin() -> Result<String> {
@@ -61,11 +146,85 @@ pub fn read_stdin() -> Result<String> {
     Ok(input.trim().to_string())
 }
 
-pub fn prompt_for_user_to_continue(prompt_message: &str) -> io::Result<()> {
+/// Print the message and wait for a line, returning it without surrounding whitespace
+pub fn prompt_for_user_to_continue(prompt_message: &str) -> io::Result<String> {
     print!("{}", prompt_message);
     io::stdout().flush()?;
 
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
-    Ok(())
+    Ok(input.trim().to_string())
+}
+
+/// Ask whether to go on with something that changes state, e.g. "Log out of github.com?". Without
+/// a terminal or with --yes, goes on without asking.
+pub fn confirm(assume_yes: bool, prompt_message: &str) -> io::Result<bool> {
+    if assume_yes || !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Ok(true);
+    }
+    let answer = prompt_for_user_to_continue(&format!("{} [y/N] ", prompt_message))?;
+    Ok(is_yes(&answer))
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_is_yes() {
+        assert!(is_yes("y"));
+        assert!(is_yes("Yes"));
+        assert!(!is_yes(""));
+        assert!(!is_yes("n"));
+        assert!(!is_yes("yep"));
+    }
+
+    #[test]
+    fn test_header_box() {
+        assert_eq!(
+            header_box("Build\nhttps://github.com/raine/ghtool", 20),
+            "┌──────────────────┐\n\
+             │ Build            │\n\
+             │ https://githu... │\n\
+             └──────────────────┘\n"
+        );
+    }
+
+    #[test]
+    fn test_check_run_header_without_url() {
+        let check_run = github::SimpleCheckRun {
+            id: 1,
+            name: "Deploy preview".to_string(),
+            status: github::CheckStatusState::Completed,
+            conclusion: Some(github::CheckConclusionState::Failure),
+            url: None,
+            started_at: None,
+            completed_at: None,
+            app: Some("vercel".to_string()),
+            is_required: false,
+        };
+        assert_eq!(
+            check_run_header(&check_run, "abc123"),
+            format!(
+                "{} Deploy preview\n{} abc123",
+                bold("Job:"),
+                bold("Commit:")
+            )
+        );
+    }
+
+    #[test]
+    fn test_header_box_with_width_1() {
+        assert_eq!(
+            header_box("Build", 1),
+            "┌──────┐\n\
+             │ B... │\n\
+             └──────┘\n"
+        );
+    }
 }
@@ -0,0 +1,88 @@
+use std::{
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use reqwest::header::{AUTHORIZATION, COOKIE};
+
+static TRACE_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Print subprocesses and HTTP requests to stderr as they're run
+pub fn set_trace_enabled(enabled: bool) {
+    TRACE_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn trace_enabled() -> bool {
+    TRACE_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Print e.g. "+ git rev-parse --abbrev-ref HEAD" before the command is run
+pub fn trace_command(command: &Command) {
+    if trace_enabled() {
+        eprintln!("{}", command_line(command));
+    }
+}
+
+fn command_line(command: &Command) -> String {
+    let mut line = format!("+ {}", command.get_program().to_string_lossy());
+    for arg in command.get_args() {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    line
+}
+
+/// Send the request, printing its method, url and headers first
+pub async fn send(request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    if trace_enabled() {
+        eprint!("{}", request_lines(&request));
+    }
+    client.execute(request).await
+}
+
+/// Headers with credentials are printed without their values. The client's default headers,
+/// including the token of GithubClient, aren't part of the request and aren't printed.
+fn request_lines(request: &reqwest::Request) -> String {
+    let mut lines = format!("> {} {}\n", request.method(), request.url());
+    for (name, value) in request.headers() {
+        let value = if name == AUTHORIZATION || name == COOKIE {
+            "[redacted]"
+        } else {
+            value.to_str().unwrap_or("[binary]")
+        };
+        lines.push_str(&format!(">   {}: {}\n", name, value));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_request_lines() {
+        let request = reqwest::Client::new()
+            .post("https://api.github.com/app/installations/1/access_tokens")
+            .bearer_auth("secret")
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            request_lines(&request),
+            "> POST https://api.github.com/app/installations/1/access_tokens\n\
+             >   authorization: [redacted]\n\
+             >   accept: application/vnd.github+json\n"
+        );
+    }
+
+    #[test]
+    fn test_command_line() {
+        let mut command = Command::new("git");
+        command.args(["remote", "get-url", "origin"]);
+        assert_eq!(command_line(&command), "+ git remote get-url origin");
+    }
+}
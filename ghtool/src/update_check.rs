@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use eyre::Result;
+use tracing::info;
+
+use crate::{cache, github::GithubClient, term::bold};
+
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const LATEST_RELEASE_CACHE_KEY: &str = "latest_release_tag";
+/// Looking up the latest release should not hold up the command when GitHub is slow to answer
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Tell if a newer version of ghtool has been released. The latest release is looked up at most
+/// once per UPDATE_CHECK_INTERVAL, and failing to look it up is not an error. Releases are on
+/// github.com, so nothing is looked up with a token of another host, e.g. GitHub Enterprise.
+pub async fn print_update_notice(client: &GithubClient, hostname: &str) {
+    if hostname != "github.com" {
+        info!(
+            ?hostname,
+            "not checking for updates on a host other than github.com"
+        );
+        return;
+    }
+
+    let latest_release_tag = tokio::time::timeout(UPDATE_CHECK_TIMEOUT, latest_release_tag(client))
+        .await
+        .unwrap_or_else(|_| Err(eyre::eyre!("timed out")));
+    match latest_release_tag {
+        Ok(tag) if is_newer_version(&tag, env!("CARGO_PKG_VERSION")) => {
+            eprintln!(
+                "A newer version of ghtool ({}) is available: https://github.com/raine/ghtool/releases",
+                bold(&tag)
+            );
+        }
+        Ok(_) => {}
+        Err(e) => info!(?e, "failed to check for updates"),
+    }
+}
+
+async fn latest_release_tag(client: &GithubClient) -> Result<String> {
+    if let Some(tag) = cache::get_fresh(LATEST_RELEASE_CACHE_KEY, UPDATE_CHECK_INTERVAL)? {
+        return Ok(tag);
+    }
+    let release = client.get_latest_release("raine", "ghtool").await?;
+    cache::put(LATEST_RELEASE_CACHE_KEY, &release.tag_name)?;
+    Ok(release.tag_name)
+}
+
+/// Compare versions like v0.10.6 by their numeric parts. Versions that can't be parsed are never
+/// newer.
+fn is_newer_version(version: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Option<Vec<u64>> {
+        version
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect()
+    }
+
+    match (parse(version), parse(current)) {
+        (Some(version), Some(current)) => version > current,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer_version() {
+        assert!(is_newer_version("v0.11.0", "0.10.6"));
+        assert!(is_newer_version("0.10.10", "0.10.6"));
+        assert!(!is_newer_version("v0.10.6", "0.10.6"));
+        assert!(!is_newer_version("v0.9.0", "0.10.6"));
+        assert!(!is_newer_version("nightly", "0.10.6"));
+    }
+}